@@ -1,6 +1,25 @@
 extern crate cc;
 
+use std::env;
+
 fn main() {
+    // Used by the ABI layout tests, which compile a C program for the same target.
+    for var in &["TARGET", "HOST"] {
+        println!(
+            "cargo:rustc-env=CHUNKWM_{}={}",
+            var,
+            env::var(var).unwrap_or_default()
+        );
+    }
+
+    // The C/C++ library only builds on macOS; lib.rs reports the features as unsupported elsewhere.
+    if env::var("CARGO_CFG_TARGET_OS").unwrap_or_default() == "macos" {
+        compile_library();
+    }
+}
+
+/// Compile the parts of chunkwm's C/C++ library that the enabled features need.
+fn compile_library() {
     #[cfg(feature = "border")]
     {
         cc::Build::new()
@@ -0,0 +1,88 @@
+/*
+ * Prints the layout of the types in chunkwm.h, using the names of the Rust types and fields in
+ * `src/raw.rs`. Used by `tests/abi.rs`.
+ *
+ *     type <rust type> <size> <alignment>
+ *     field <rust type> <rust field> <offset>
+ */
+#include <stdio.h>
+#include "chunkwm.h"
+
+#define TYPE(rust, c) printf("type %s %zu %zu\n", #rust, sizeof(c), _Alignof(c))
+#define FIELD(rust, c, rust_field, c_field) \
+    printf("field %s %s %zu\n", #rust, #rust_field, offsetof(c, c_field))
+
+int main(void)
+{
+    printf("api_version %d\n", CHUNKWM_PLUGIN_API_VERSION);
+
+    TYPE(Subscription, chunkwm_plugin_export);
+    TYPE(LogLevel, enum c_log_level);
+    TYPE(SpaceType, enum CGSSpaceType);
+
+    TYPE(API, chunkwm_api);
+
+    TYPE(ChunkWMPlugin, struct plugin);
+    FIELD(ChunkWMPlugin, struct plugin, init, Init);
+    FIELD(ChunkWMPlugin, struct plugin, deinit, DeInit);
+    FIELD(ChunkWMPlugin, struct plugin, run, Run);
+    FIELD(ChunkWMPlugin, struct plugin, subscriptions, Subscriptions);
+    FIELD(ChunkWMPlugin, struct plugin, subscription_count, SubscriptionCount);
+
+    TYPE(ChunkWMPluginDetails, struct plugin_details);
+    FIELD(ChunkWMPluginDetails, struct plugin_details, magic, Magic);
+    FIELD(ChunkWMPluginDetails, struct plugin_details, api_version, ApiVersion);
+    FIELD(ChunkWMPluginDetails, struct plugin_details, file_name, FileName);
+    FIELD(ChunkWMPluginDetails, struct plugin_details, plugin_name, PluginName);
+    FIELD(ChunkWMPluginDetails, struct plugin_details, plugin_version, PluginVersion);
+    FIELD(ChunkWMPluginDetails, struct plugin_details, initialize, Initialize);
+
+    TYPE(RawPayload, struct chunkwm_payload);
+    FIELD(RawPayload, struct chunkwm_payload, sock_fd, SockFD);
+    FIELD(RawPayload, struct chunkwm_payload, command, Command);
+    FIELD(RawPayload, struct chunkwm_payload, message, Message);
+
+    TYPE(RawObserver, struct macos_observer);
+    FIELD(RawObserver, struct macos_observer, observer, Ref);
+    FIELD(RawObserver, struct macos_observer, enabled, Enabled);
+    FIELD(RawObserver, struct macos_observer, valid, Valid);
+
+    TYPE(ProcessSerialNumber, ProcessSerialNumber);
+    FIELD(ProcessSerialNumber, ProcessSerialNumber, high_long, highLongOfPSN);
+    FIELD(ProcessSerialNumber, ProcessSerialNumber, low_long, lowLongOfPSN);
+
+    TYPE(RawApplication, struct macos_application);
+    FIELD(RawApplication, struct macos_application, element, Ref);
+    FIELD(RawApplication, struct macos_application, observer, Observer);
+    FIELD(RawApplication, struct macos_application, name, Name);
+    FIELD(RawApplication, struct macos_application, pid, PID);
+    FIELD(RawApplication, struct macos_application, process_serial_number, PSN);
+
+    TYPE(RawWindow, struct macos_window);
+    FIELD(RawWindow, struct macos_window, element, Ref);
+    FIELD(RawWindow, struct macos_window, main_role, Mainrole);
+    FIELD(RawWindow, struct macos_window, sub_role, Subrole);
+    FIELD(RawWindow, struct macos_window, owner, Owner);
+    FIELD(RawWindow, struct macos_window, id, Id);
+    FIELD(RawWindow, struct macos_window, name, Name);
+    FIELD(RawWindow, struct macos_window, flags, Flags);
+    FIELD(RawWindow, struct macos_window, level, Level);
+    FIELD(RawWindow, struct macos_window, position, Position);
+    FIELD(RawWindow, struct macos_window, size, Size);
+
+    TYPE(RawDisplay, struct macos_display);
+    FIELD(RawDisplay, struct macos_display, display_ref, Ref);
+    FIELD(RawDisplay, struct macos_display, id, Id);
+    FIELD(RawDisplay, struct macos_display, arrangement, Arrangement);
+    FIELD(RawDisplay, struct macos_display, x, X);
+    FIELD(RawDisplay, struct macos_display, y, Y);
+    FIELD(RawDisplay, struct macos_display, width, Width);
+    FIELD(RawDisplay, struct macos_display, height, Height);
+
+    TYPE(RawSpace, struct macos_space);
+    FIELD(RawSpace, struct macos_space, space_ref, Ref);
+    FIELD(RawSpace, struct macos_space, id, Id);
+    FIELD(RawSpace, struct macos_space, space_type, Type);
+
+    return 0;
+}
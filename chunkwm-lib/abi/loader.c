@@ -0,0 +1,91 @@
+/*
+ * Loads a plugin the way chunkwm's loader does, and drives it with a fake chunkwm: a window
+ * focused event and the `chunkwm-rs-version` daemon command. Used by `tests/plugin.rs`.
+ *
+ *     usage: loader <plugin library>
+ *
+ * Compile with the header generated by `chunkwm::plugin::write_exports_header`, as
+ * `chunkwm_plugin.h`.
+ */
+#include <dlfcn.h>
+#include <stdio.h>
+#include <string.h>
+#include <unistd.h>
+#include "chunkwm_plugin.h"
+
+static void UpdateCVar(const char *Name, const char *Value) { printf("update_cvar %s %s\n", Name, Value); }
+static char *AcquireCVar(const char *Name) { return (char *) ""; }
+static bool FindCVar(const char *Name) { return false; }
+static void Broadcast(const char *PluginName, const char *EventName, void *Data, size_t Size) {}
+#if CHUNKWM_PLUGIN_API_VERSION >= 8
+static void Log(enum c_log_level Level, const char *Format, ...) { printf("log %s", Format); }
+#endif
+
+int main(int Count, char **Args)
+{
+    if (Count != 2) {
+        fprintf(stderr, "usage: loader <plugin library>\n");
+        return 2;
+    }
+
+    void *Handle = dlopen(Args[1], RTLD_NOW | RTLD_LOCAL);
+    if (!Handle) {
+        fprintf(stderr, "dlopen: %s\n", dlerror());
+        return 1;
+    }
+
+    struct plugin_details *Info = (struct plugin_details *) dlsym(Handle, "Exports");
+    if (!Info) {
+        fprintf(stderr, "dlsym: %s\n", dlerror());
+        return 1;
+    }
+
+    printf("magic %.6s\n", Info->Magic);
+    printf("api_version %d\n", Info->ApiVersion);
+    printf("file_name %s\n", Info->FileName);
+    printf("plugin_name %s\n", Info->PluginName);
+    printf("plugin_version %s\n", Info->PluginVersion);
+    printf("initialize_is_get_plugin %d\n", (void *) Info->Initialize == dlsym(Handle, "GetPlugin"));
+
+    plugin *Plugin = Info->Initialize();
+    printf("same_plugin %d\n", Plugin == Info->Initialize());
+    printf("subscriptions");
+    for (unsigned Index = 0; Index < Plugin->SubscriptionCount; ++Index) {
+        printf(" %d", Plugin->Subscriptions[Index]);
+    }
+    printf("\n");
+
+    chunkwm_api API = { UpdateCVar, AcquireCVar, FindCVar, Broadcast,
+#if CHUNKWM_PLUGIN_API_VERSION >= 8
+                        Log,
+#endif
+    };
+    printf("init %d\n", Plugin->Init(API));
+    fflush(stdout);
+
+    struct macos_application Application;
+    memset(&Application, 0, sizeof(Application));
+    Application.Name = (char *) "Terminal";
+    struct macos_window Window;
+    memset(&Window, 0, sizeof(Window));
+    Window.Owner = &Application;
+    Window.Name = (char *) "~";
+    printf("run %d\n", Plugin->Run("chunkwm_export_window_focused", &Window));
+    fflush(stdout);
+
+    int Pipe[2];
+    if (pipe(Pipe) != 0) {
+        perror("pipe");
+        return 1;
+    }
+    struct chunkwm_payload Payload = { Pipe[1], (char *) "chunkwm-rs-version", (char *) "" };
+    printf("version_command %d\n", Plugin->Run("chunkwm_daemon_command", &Payload));
+    close(Pipe[1]);
+    char Response[256] = {0};
+    ssize_t Length = read(Pipe[0], Response, sizeof(Response) - 1);
+    printf("version %s", Length > 0 ? Response : "\n");
+
+    Plugin->DeInit();
+    printf("deinit\n");
+    return 0;
+}
@@ -77,6 +77,12 @@ extern "C" macos_space **axlib_spaces_for_display(CFStringRef DisplayRef) {
 extern "C" macos_space **axlib_spaces_for_window(uint32_t WindowId) {
 	return AXLibSpacesForWindow(WindowId);
 }
+extern "C" void axlib_destroy_space_list(macos_space **Spaces) {
+	for (macos_space **Space = Spaces; *Space; ++Space) {
+		AXLibDestroySpace(*Space);
+	}
+	free(Spaces);
+}
 extern "C" void axlib_space_move_window(CGSSpaceID SpaceId, uint32_t WindowId) {
 	AXLibSpaceMoveWindow(SpaceId, WindowId);
 }
@@ -0,0 +1,203 @@
+/*
+ * The chunkwm plugin ABI, as used by the types in `src/raw.rs`.
+ *
+ * Reduced from chunkwm's `plugin_api.h`, `plugin_export.h`, `chunkwm.h` and the
+ * `common/accessibility` and `common/misc` headers, reduced to the type declarations. Define
+ * CHUNKWM_PLUGIN_API_VERSION to select the API version; it defaults to the latest version.
+ *
+ * On macOS the system headers are used for the Core Foundation, Core Graphics and Carbon types.
+ * Elsewhere stand-ins with the same layout are declared, so that the layout can be checked on
+ * any machine with a C compiler.
+ */
+#ifndef CHUNKWM_RS_CHUNKWM_H
+#define CHUNKWM_RS_CHUNKWM_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/types.h>
+
+#ifndef CHUNKWM_PLUGIN_API_VERSION
+#define CHUNKWM_PLUGIN_API_VERSION 8
+#endif
+
+#if CHUNKWM_PLUGIN_API_VERSION < 7 || CHUNKWM_PLUGIN_API_VERSION > 8
+#error "unsupported chunkwm plugin API version"
+#endif
+
+#ifdef __APPLE__
+#include <ApplicationServices/ApplicationServices.h>
+#include <Carbon/Carbon.h>
+#else
+typedef const void *CFTypeRef;
+typedef const struct __CFString *CFStringRef;
+typedef const struct __AXUIElement *AXUIElementRef;
+typedef struct __AXObserver *AXObserverRef;
+typedef uint32_t CGDirectDisplayID;
+#if defined(__LP64__) && __LP64__
+typedef double CGFloat;
+#else
+typedef float CGFloat;
+#endif
+typedef struct { CGFloat x; CGFloat y; } CGPoint;
+typedef struct { CGFloat width; CGFloat height; } CGSize;
+typedef uint32_t UInt32;
+typedef struct { UInt32 highLongOfPSN; UInt32 lowLongOfPSN; } ProcessSerialNumber;
+#endif
+
+/* plugin_export.h */
+enum chunkwm_plugin_export
+{
+    chunkwm_export_application_launched,
+    chunkwm_export_application_terminated,
+    chunkwm_export_application_activated,
+    chunkwm_export_application_deactivated,
+    chunkwm_export_application_hidden,
+    chunkwm_export_application_unhidden,
+
+    chunkwm_export_space_changed,
+    chunkwm_export_display_changed,
+
+    chunkwm_export_display_added,
+    chunkwm_export_display_removed,
+    chunkwm_export_display_moved,
+    chunkwm_export_display_resized,
+
+    chunkwm_export_window_created,
+    chunkwm_export_window_destroyed,
+    chunkwm_export_window_focused,
+    chunkwm_export_window_moved,
+    chunkwm_export_window_resized,
+    chunkwm_export_window_minimized,
+    chunkwm_export_window_deminimized,
+    chunkwm_export_window_sheet_created,
+    chunkwm_export_window_title_changed,
+
+    chunkwm_export_count
+};
+typedef enum chunkwm_plugin_export chunkwm_plugin_export;
+
+/* chunkwm.h */
+enum c_log_level
+{
+    C_LOG_LEVEL_DEBUG = 0,
+    C_LOG_LEVEL_PROFILE = 1,
+    C_LOG_LEVEL_WARN = 2,
+    C_LOG_LEVEL_ERROR = 3,
+    C_LOG_LEVEL_NONE = 10,
+};
+
+typedef void chunkwm_update_cvar(const char *Name, const char *Value);
+typedef char *chunkwm_acquire_cvar(const char *Name);
+typedef bool chunkwm_find_cvar(const char *Name);
+typedef void chunkwm_broadcast(const char *PluginName, const char *EventName, void *Data, size_t Size);
+#if CHUNKWM_PLUGIN_API_VERSION >= 8
+typedef void chunkwm_log(enum c_log_level Level, const char *Format, ...);
+#endif
+
+struct chunkwm_api
+{
+    chunkwm_update_cvar *UpdateCVar;
+    chunkwm_acquire_cvar *AcquireCVar;
+    chunkwm_find_cvar *FindCVar;
+    chunkwm_broadcast *Broadcast;
+#if CHUNKWM_PLUGIN_API_VERSION >= 8
+    chunkwm_log *Log;
+#endif
+};
+typedef struct chunkwm_api chunkwm_api;
+
+struct chunkwm_payload
+{
+    int SockFD;
+    char *Command;
+    char *Message;
+};
+
+/* plugin_api.h */
+typedef bool plugin_bool_func(chunkwm_api ChunkwmAPI);
+typedef void plugin_void_func(void);
+typedef bool plugin_main_func(const char *Node, void *Data);
+
+struct plugin
+{
+    plugin_bool_func *Init;
+    plugin_void_func *DeInit;
+    plugin_main_func *Run;
+    chunkwm_plugin_export *Subscriptions;
+    unsigned SubscriptionCount;
+};
+typedef struct plugin plugin;
+
+typedef plugin *plugin_func(void);
+
+struct plugin_details
+{
+    char Magic[6];
+    int ApiVersion;
+    const char *FileName;
+    const char *PluginName;
+    const char *PluginVersion;
+    plugin_func *Initialize;
+};
+
+/* common/accessibility/observer.h */
+struct macos_observer
+{
+    AXObserverRef Ref;
+    bool Enabled;
+    bool Valid;
+};
+
+/* common/accessibility/application.h */
+struct macos_application
+{
+    AXUIElementRef Ref;
+    struct macos_observer Observer;
+    char *Name;
+    pid_t PID;
+    ProcessSerialNumber PSN;
+};
+
+/* common/accessibility/window.h */
+struct macos_window
+{
+    AXUIElementRef Ref;
+    CFStringRef Mainrole;
+    CFStringRef Subrole;
+    struct macos_application *Owner;
+    uint32_t Id;
+    char *Name;
+    volatile uint32_t Flags;
+    uint32_t Level;
+    CGPoint Position;
+    CGSize Size;
+};
+
+/* common/accessibility/display.h */
+typedef int CGSSpaceID;
+enum CGSSpaceType
+{
+    kCGSSpaceUser,
+    kCGSSpaceFullscreen,
+    kCGSSpaceSystem,
+    kCGSSpaceUnknown
+};
+
+struct macos_display
+{
+    CFStringRef Ref;
+    CGDirectDisplayID Id;
+    unsigned Arrangement;
+    float X, Y;
+    float Width, Height;
+};
+
+struct macos_space
+{
+    CFStringRef Ref;
+    CGSSpaceID Id;
+    enum CGSSpaceType Type;
+};
+
+#endif
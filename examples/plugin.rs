@@ -0,0 +1,42 @@
+//! A minimal plugin. Build it with `cargo build --example plugin`; the resulting library can be
+//! loaded by chunkwm without any C/C++ code:
+//!
+//! ```sh
+//! chunkc core::load /path/to/target/debug/examples/libplugin.dylib
+//! ```
+
+#[macro_use]
+extern crate chunkwm;
+
+use chunkwm::prelude::*;
+
+pub struct Plugin {
+    api: API,
+}
+
+chunkwm_plugin! {
+    Plugin,
+    name: "example",
+    version: "0.1.0",
+}
+
+impl HandleEvent for Plugin {
+    fn new(api: API) -> Plugin {
+        Plugin { api }
+    }
+
+    subscribe!(Subscription::WindowFocused, Subscription::WindowCreated);
+
+    fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+        match event {
+            Event::WindowFocused(window) | Event::WindowCreated(window) => {
+                self.api
+                    .log(LogLevel::Debug, format!("window: {}", window.name()?));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn shutdown(&self) {}
+}
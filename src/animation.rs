@@ -0,0 +1,494 @@
+//! The `animation` module animates window moves and resizes, instead of placing windows
+//! instantaneously.
+//!
+//! The frames of an animation are generated by `Frames`, a pure iterator that interpolates from
+//! the current to the target frame with an easing curve. The `Animator` runs the animations of
+//! several windows at once: every step yields one frame for each animating window, and animating a
+//! window again cancels its previous animation. Applying the frames is left to the caller, e.g.
+//! with `apply_frame`.
+//!
+//! `Animator::run` sleeps between frames, so it blocks the calling thread, which in a plugin is
+//! chunkwm's event thread, until the animations have finished. Plugins can call `Animator::step`
+//! from a repeating timer instead (see the `timer` module), which keeps chunkwm responsive.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use chunkwm::animation::{AnimationSettings, Animator};
+//! use chunkwm::geometry::{CGPoint, CGRect, CGSize};
+//!
+//! let mut animator = Animator::new(AnimationSettings::default());
+//! let from = CGRect::new(&CGPoint::new(0.0, 0.0), &CGSize::new(800.0, 600.0));
+//! let to = CGRect::new(&CGPoint::new(800.0, 0.0), &CGSize::new(800.0, 1200.0));
+//! animator.animate(1, from, to);
+//! animator.run(|id, frame| {
+//!     // Move and resize window `id`, e.g. with `chunkwm::animation::apply_frame`.
+//! });
+//! ```
+
+use geometry::{CGFloat, CGPoint, CGRect, CGSize};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
+use system::WindowSystem;
+use window::Window;
+use ChunkWMError;
+
+/// An easing curve, which maps the progress of an animation in time to the progress in distance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// Start slowly (cubic).
+    EaseIn,
+    /// Stop slowly (cubic).
+    EaseOut,
+    /// Start and stop slowly (cubic).
+    EaseInOut,
+}
+
+impl Easing {
+    /// Apply the curve to a progress between 0 and 1.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+        }
+    }
+}
+
+impl Display for Easing {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Easing::Linear => "linear",
+            Easing::EaseIn => "ease_in",
+            Easing::EaseOut => "ease_out",
+            Easing::EaseInOut => "ease_in_out",
+        })
+    }
+}
+
+impl FromStr for Easing {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<Easing, ChunkWMError> {
+        match s {
+            "linear" => Ok(Easing::Linear),
+            "ease_in" => Ok(Easing::EaseIn),
+            "ease_out" => Ok(Easing::EaseOut),
+            "ease_in_out" => Ok(Easing::EaseInOut),
+            _ => Err(ChunkWMError::ParseError(
+                "expected linear, ease_in, ease_out or ease_in_out",
+            )),
+        }
+    }
+}
+
+/// The settings of an animation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationSettings {
+    /// The duration of an animation. A zero duration moves windows in one frame.
+    pub duration: Duration,
+    /// The number of frames per second.
+    pub frame_rate: u32,
+    pub easing: Easing,
+}
+
+impl Default for AnimationSettings {
+    fn default() -> AnimationSettings {
+        AnimationSettings {
+            duration: Duration::from_millis(150),
+            frame_rate: 60,
+            easing: Easing::EaseOut,
+        }
+    }
+}
+
+impl AnimationSettings {
+    /// The time between two frames.
+    pub fn frame_interval(&self) -> Duration {
+        Duration::from_secs(1) / self.frame_rate.max(1)
+    }
+
+    /// The number of frames of an animation; at least one.
+    pub fn frame_count(&self) -> u32 {
+        let nanos = self.duration.as_nanos() * u128::from(self.frame_rate.max(1));
+        let frames = nanos.div_ceil(1_000_000_000);
+        frames.max(1).min(u128::from(u32::MAX)) as u32
+    }
+}
+
+/// Interpolate linearly between two rectangles; `progress` 0 is `from`, 1 is `to`.
+pub fn interpolate(from: &CGRect, to: &CGRect, progress: f64) -> CGRect {
+    let lerp = |a: CGFloat, b: CGFloat| a + (b - a) * progress as CGFloat;
+    CGRect::new(
+        &CGPoint::new(
+            lerp(from.origin.x, to.origin.x),
+            lerp(from.origin.y, to.origin.y),
+        ),
+        &CGSize::new(
+            lerp(from.size.width, to.size.width),
+            lerp(from.size.height, to.size.height),
+        ),
+    )
+}
+
+/// A frame of an animation.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    /// The time since the start of the animation at which the frame should be shown.
+    pub time: Duration,
+    /// The rectangle of the window in this frame.
+    pub rect: CGRect,
+}
+
+/// The frames of an animation from one rectangle to another. The first frame is one interval
+/// after the start, and the last frame is exactly the target.
+#[derive(Debug, Clone)]
+pub struct Frames {
+    from: CGRect,
+    to: CGRect,
+    settings: AnimationSettings,
+    index: u32,
+    count: u32,
+}
+
+impl Frames {
+    /// Create the frames of an animation from `from` to `to`, with the duration, frame rate and
+    /// easing of `settings`.
+    pub fn new(from: CGRect, to: CGRect, settings: AnimationSettings) -> Frames {
+        Frames {
+            from,
+            to,
+            settings,
+            index: 0,
+            count: settings.frame_count(),
+        }
+    }
+
+    /// The rectangle the animation started from.
+    pub fn from(&self) -> CGRect {
+        self.from
+    }
+
+    /// The rectangle the animation ends at.
+    pub fn to(&self) -> CGRect {
+        self.to
+    }
+}
+
+impl Iterator for Frames {
+    type Item = Frame;
+
+    fn next(&mut self) -> Option<Frame> {
+        if self.index >= self.count {
+            return None;
+        }
+        self.index += 1;
+
+        let rect = if self.index == self.count {
+            self.to
+        } else {
+            let t = f64::from(self.index) / f64::from(self.count);
+            interpolate(&self.from, &self.to, self.settings.easing.apply(t))
+        };
+        Some(Frame {
+            time: self.settings.frame_interval() * self.index,
+            rect,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.count - self.index) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Frames {}
+
+struct Animation {
+    frames: Frames,
+    current: CGRect,
+}
+
+/// Runs the animations of several windows at once.
+pub struct Animator {
+    settings: AnimationSettings,
+    animations: BTreeMap<u32, Animation>,
+}
+
+impl Animator {
+    /// Create an animator without animations. New animations use `settings`.
+    pub fn new(settings: AnimationSettings) -> Animator {
+        Animator {
+            settings,
+            animations: BTreeMap::new(),
+        }
+    }
+
+    /// Get the settings new animations use.
+    pub fn settings(&self) -> AnimationSettings {
+        self.settings
+    }
+
+    /// Change the settings. Running animations keep their settings.
+    pub fn set_settings(&mut self, settings: AnimationSettings) {
+        self.settings = settings;
+    }
+
+    /// Animate a window from `from` to `to`. If the window is already animating, that animation is
+    /// cancelled, and the new animation starts where the window currently is instead of at
+    /// `from`. Returns whether an animation was cancelled.
+    pub fn animate(&mut self, id: u32, from: CGRect, to: CGRect) -> bool {
+        let (from, superseded) = match self.animations.get(&id) {
+            Some(animation) => (animation.current, true),
+            None => (from, false),
+        };
+        self.animations.insert(
+            id,
+            Animation {
+                frames: Frames::new(from, to, self.settings),
+                current: from,
+            },
+        );
+        superseded
+    }
+
+    /// Cancel the animation of a window, leaving it where it currently is. Returns the current
+    /// frame of the window, if it was animating.
+    pub fn cancel(&mut self, id: u32) -> Option<CGRect> {
+        self.animations
+            .remove(&id)
+            .map(|animation| animation.current)
+    }
+
+    /// Cancel all animations.
+    pub fn cancel_all(&mut self) {
+        self.animations.clear();
+    }
+
+    /// Check whether a window is animating.
+    pub fn is_animating(&self, id: u32) -> bool {
+        self.animations.contains_key(&id)
+    }
+
+    /// Check whether no window is animating.
+    pub fn is_idle(&self) -> bool {
+        self.animations.is_empty()
+    }
+
+    /// The frame a window is animating to.
+    pub fn target(&self, id: u32) -> Option<CGRect> {
+        self.animations
+            .get(&id)
+            .map(|animation| animation.frames.to())
+    }
+
+    /// Advance all animations by one frame, and return the new frame of every animating window,
+    /// ordered by id. Finished animations are removed after their last frame.
+    pub fn step(&mut self) -> Vec<(u32, CGRect)> {
+        let mut batch = Vec::with_capacity(self.animations.len());
+        let mut finished = Vec::new();
+        for (&id, animation) in &mut self.animations {
+            match animation.frames.next() {
+                Some(frame) => {
+                    animation.current = frame.rect;
+                    batch.push((id, frame.rect));
+                    if animation.frames.len() == 0 {
+                        finished.push(id);
+                    }
+                }
+                None => finished.push(id),
+            }
+        }
+        for id in finished {
+            self.animations.remove(&id);
+        }
+        batch
+    }
+
+    /// Run all animations until they have finished, calling `apply` with every new frame and
+    /// sleeping for the frame interval between steps.
+    ///
+    /// This blocks the calling thread for the whole animation. In a plugin, that is chunkwm's
+    /// event thread, so no other events are handled in the meantime; call `step` from a repeating
+    /// timer with the frame interval to animate without blocking.
+    pub fn run<F: FnMut(u32, &CGRect)>(&mut self, mut apply: F) {
+        while !self.is_idle() {
+            for (id, rect) in self.step() {
+                apply(id, &rect);
+            }
+            if !self.is_idle() {
+                thread::sleep(self.settings.frame_interval());
+            }
+        }
+    }
+}
+
+/// Move and resize a window to a frame.
+pub fn apply_frame<S: WindowSystem + ?Sized>(
+    system: &mut S,
+    window: &Window,
+    rect: &CGRect,
+) -> Result<(), ChunkWMError> {
+    system.set_position(window, rect.origin)?;
+    system.set_size(window, rect.size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::rect;
+
+    fn tuple(rect: &CGRect) -> (CGFloat, CGFloat, CGFloat, CGFloat) {
+        (
+            rect.origin.x,
+            rect.origin.y,
+            rect.size.width,
+            rect.size.height,
+        )
+    }
+
+    fn linear(frames: u32) -> AnimationSettings {
+        AnimationSettings {
+            duration: Duration::from_millis(u64::from(frames) * 10),
+            frame_rate: 100,
+            easing: Easing::Linear,
+        }
+    }
+
+    #[test]
+    fn easing_curves() {
+        for &easing in &[
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0);
+            assert_eq!(easing.apply(1.0), 1.0);
+            assert_eq!(easing.apply(2.0), 1.0);
+            let mut previous = 0.0;
+            for i in 1..=100 {
+                let value = easing.apply(f64::from(i) / 100.0);
+                assert!(value >= previous, "{} is not monotonic", easing);
+                previous = value;
+            }
+            assert_eq!(easing.to_string().parse::<Easing>().unwrap(), easing);
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+        assert!("bounce".parse::<Easing>().is_err());
+    }
+
+    #[test]
+    fn frame_count() {
+        assert_eq!(linear(4).frame_count(), 4);
+        let instant = AnimationSettings {
+            duration: Duration::from_millis(0),
+            ..AnimationSettings::default()
+        };
+        assert_eq!(instant.frame_count(), 1);
+        assert_eq!(AnimationSettings::default().frame_count(), 9);
+    }
+
+    #[test]
+    fn frames() {
+        let frames: Vec<_> = Frames::new(
+            rect(0.0, 0.0, 100.0, 100.0),
+            rect(100.0, 40.0, 200.0, 100.0),
+            linear(4),
+        )
+        .collect();
+
+        let rects: Vec<_> = frames.iter().map(|frame| tuple(&frame.rect)).collect();
+        assert_eq!(
+            rects,
+            vec![
+                (25.0, 10.0, 125.0, 100.0),
+                (50.0, 20.0, 150.0, 100.0),
+                (75.0, 30.0, 175.0, 100.0),
+                (100.0, 40.0, 200.0, 100.0),
+            ]
+        );
+        let times: Vec<_> = frames.iter().map(|frame| frame.time).collect();
+        assert_eq!(
+            times,
+            (1..=4)
+                .map(|i| Duration::from_millis(10 * i))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn last_frame_is_exact_target() {
+        let to = rect(0.1, 0.2, 0.3, 0.7);
+        let settings = AnimationSettings {
+            easing: Easing::EaseInOut,
+            ..AnimationSettings::default()
+        };
+        let frames = Frames::new(rect(13.0, 17.0, 19.0, 23.0), to, settings);
+        assert_eq!(frames.len(), 9);
+        assert_eq!(tuple(&frames.last().unwrap().rect), tuple(&to));
+    }
+
+    #[test]
+    fn batches_windows() {
+        let mut animator = Animator::new(linear(2));
+        animator.animate(2, rect(0.0, 0.0, 10.0, 10.0), rect(10.0, 0.0, 10.0, 10.0));
+        animator.animate(1, rect(0.0, 0.0, 10.0, 10.0), rect(0.0, 10.0, 10.0, 10.0));
+
+        let batch: Vec<_> = animator
+            .step()
+            .iter()
+            .map(|&(id, ref rect)| (id, tuple(rect)))
+            .collect();
+        assert_eq!(
+            batch,
+            vec![(1, (0.0, 5.0, 10.0, 10.0)), (2, (5.0, 0.0, 10.0, 10.0))]
+        );
+        assert_eq!(animator.step().len(), 2);
+        assert!(animator.is_idle());
+        assert!(animator.step().is_empty());
+    }
+
+    #[test]
+    fn supersede_and_cancel() {
+        let mut animator = Animator::new(linear(4));
+        assert!(!animator.animate(1, rect(0.0, 0.0, 10.0, 10.0), rect(40.0, 0.0, 10.0, 10.0)));
+        animator.step();
+
+        // The new animation starts where the window is, not at the given frame.
+        assert!(animator.animate(1, rect(0.0, 0.0, 10.0, 10.0), rect(10.0, 0.0, 10.0, 10.0)));
+        assert_eq!(tuple(&animator.target(1).unwrap()), (10.0, 0.0, 10.0, 10.0));
+        let (_, first) = animator.step()[0];
+        assert_eq!(tuple(&first), (10.0, 0.0, 10.0, 10.0));
+        assert_eq!(tuple(&animator.cancel(1).unwrap()), (10.0, 0.0, 10.0, 10.0));
+        assert!(!animator.is_animating(1));
+        assert!(animator.cancel(1).is_none());
+    }
+
+    #[test]
+    fn run_applies_all_frames() {
+        let mut animator = Animator::new(AnimationSettings {
+            duration: Duration::from_millis(3),
+            frame_rate: 1000,
+            easing: Easing::EaseOut,
+        });
+        animator.animate(1, rect(0.0, 0.0, 10.0, 10.0), rect(30.0, 0.0, 10.0, 10.0));
+        let mut applied = Vec::new();
+        animator.run(|id, rect| applied.push((id, tuple(rect))));
+        assert_eq!(applied.len(), 3);
+        assert_eq!(applied[2], (1, (30.0, 0.0, 10.0, 10.0)));
+        assert!(animator.is_idle());
+    }
+}
@@ -0,0 +1,661 @@
+//! The `borders` module contains a `BorderManager`, which keeps a border around the focused window
+//! (and optionally around all other visible windows) while the plugin feeds it events.
+//!
+//! The manager only decides which borders should exist and how they should look; a
+//! `BorderBackend` from `common::border` draws them. With the `border` feature, `CocoaBackend`
+//! draws them on screen. `RecordingBackend` only records them, which is useful in tests.
+//!
+//! The settings implement `FromConfig`, so they can be loaded with `Config` and overridden with
+//! `CVar`s (e.g. `chunkc set border_width 6` with the prefix `border_`):
+//!
+//! ```text
+//! width = 4
+//! radius = 6
+//! active_color = 0xffd5c4a1
+//! inactive_color = 0xff504945
+//! all_windows = 0
+//! exclude = Finder, System Preferences
+//! ```
+//!
+//! chunkwm doesn't send an event when a window enters or leaves fullscreen, so the plugin has to
+//! call `set_fullscreen`, e.g. after checking `Window::is_fullscreen` on `WindowResized`.
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::borders::{BorderBackend, BorderManager, BorderSettings};
+//! use chunkwm::config::Config;
+//!
+//! fn handle<B: BorderBackend>(
+//!     borders: &mut BorderManager<B>,
+//!     config: &mut Config<BorderSettings>,
+//!     api: &API,
+//!     event: &Event,
+//! ) -> Result<(), ChunkWMError> {
+//!     if config.handle(event, api)? {
+//!         return borders.set_settings(config.settings().clone());
+//!     }
+//!     borders.handle(event)
+//! }
+//! # fn main() {}
+//! ```
+
+use api::NumericBool;
+#[cfg(feature = "border")]
+pub use common::border::CocoaBackend;
+pub use common::border::{BorderBackend, BorderStyle, Color, RecordingBackend};
+use config::{ConfigValues, FromConfig};
+use event::Event;
+use geometry::CGRect;
+use raw::PID;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+use window::Window;
+use ChunkWMError;
+
+/// The settings of a `BorderManager`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderSettings {
+    /// The border width. Key: `width`.
+    pub width: i32,
+    /// The corner radius. Key: `radius`.
+    pub radius: i32,
+    /// The color of the focused window's border. Key: `active_color`.
+    pub active_color: Color,
+    /// The color of the other windows' borders. Key: `inactive_color`.
+    pub inactive_color: Color,
+    /// Whether all visible windows get a border, instead of only the focused window. Key:
+    /// `all_windows` (a numeric bool).
+    pub all_windows: bool,
+    /// The names of applications whose windows never get a border. Key: `exclude` (a
+    /// comma-separated list).
+    pub exclude: Vec<String>,
+}
+
+impl Default for BorderSettings {
+    fn default() -> BorderSettings {
+        BorderSettings {
+            width: 4,
+            radius: 6,
+            active_color: Color(0xffd5_c4a1),
+            inactive_color: Color(0xff50_4945),
+            all_windows: false,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+impl FromConfig for BorderSettings {
+    fn from_config(values: &ConfigValues) -> BorderSettings {
+        let default = BorderSettings::default();
+        BorderSettings {
+            width: values.get("width", default.width),
+            radius: values.get("radius", default.radius),
+            active_color: values.get("active_color", default.active_color),
+            inactive_color: values.get("inactive_color", default.inactive_color),
+            all_windows: values
+                .get("all_windows", NumericBool::from(default.all_windows))
+                .value,
+            exclude: values
+                .get("exclude", String::new())
+                .split(',')
+                .map(str::trim)
+                .filter(|name| !name.is_empty())
+                .map(str::to_owned)
+                .collect(),
+        }
+    }
+}
+
+/// A border that is shown, and the frame and style it was last given.
+struct Shown<B> {
+    border: B,
+    frame: CGRect,
+    style: BorderStyle,
+}
+
+/// A window the manager knows about.
+struct Tracked<B> {
+    owner: PID,
+    application: String,
+    frame: CGRect,
+    minimized: bool,
+    fullscreen: bool,
+    border: Option<Shown<B>>,
+}
+
+/// Keeps borders around the focused window, or around all visible windows, up to date.
+///
+/// A window gets no border while it is minimized or fullscreen, while its application is hidden,
+/// or when its application is excluded. The borders are destroyed when the manager is dropped.
+pub struct BorderManager<B: BorderBackend> {
+    backend: B,
+    settings: BorderSettings,
+    windows: BTreeMap<u32, Tracked<B::Border>>,
+    hidden: BTreeSet<PID>,
+    focused: Option<u32>,
+}
+
+impl<B: BorderBackend> BorderManager<B> {
+    /// Create a manager without any borders.
+    pub fn new(backend: B, settings: BorderSettings) -> BorderManager<B> {
+        BorderManager {
+            backend,
+            settings,
+            windows: BTreeMap::new(),
+            hidden: BTreeSet::new(),
+            focused: None,
+        }
+    }
+
+    /// Get the backend.
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Get the backend mutably.
+    pub fn backend_mut(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    /// Get the settings.
+    pub fn settings(&self) -> &BorderSettings {
+        &self.settings
+    }
+
+    /// Change the settings, and update all borders.
+    pub fn set_settings(&mut self, settings: BorderSettings) -> Result<(), ChunkWMError> {
+        self.settings = settings;
+        self.sync_all()
+    }
+
+    /// Get the id of the focused window.
+    pub fn focused(&self) -> Option<u32> {
+        self.focused
+    }
+
+    /// Check whether a window has a border.
+    pub fn has_border(&self, id: u32) -> bool {
+        self.windows
+            .get(&id)
+            .is_some_and(|window| window.border.is_some())
+    }
+
+    /// Get the style of a window's border, if it has one.
+    pub fn border_style(&self, id: u32) -> Option<BorderStyle> {
+        self.windows
+            .get(&id)
+            .and_then(|window| window.border.as_ref())
+            .map(|shown| shown.style)
+    }
+
+    /// Mark a window as fullscreen or not. Fullscreen windows get no border.
+    pub fn set_fullscreen(&mut self, id: u32, fullscreen: bool) -> Result<(), ChunkWMError> {
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.fullscreen = fullscreen;
+        }
+        self.sync(id)
+    }
+
+    /// Update the borders from an event. Events that carry a null pointer are ignored; errors of
+    /// the backend are returned.
+    pub fn handle(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        match *event {
+            Event::WindowCreated(ref window) => match self.track(window) {
+                Some(id) => self.sync(id),
+                None => Ok(()),
+            },
+            Event::WindowFocused(ref window) => match self.track(window) {
+                Some(id) => {
+                    let previous = self.focused.replace(id);
+                    if let Some(previous) = previous.filter(|previous| *previous != id) {
+                        self.sync(previous)?;
+                    }
+                    self.sync(id)
+                }
+                None => Ok(()),
+            },
+            Event::WindowMoved(ref window) | Event::WindowResized(ref window) => {
+                match self.track(window) {
+                    Some(id) => self.sync(id),
+                    None => Ok(()),
+                }
+            }
+            Event::WindowMinimized(ref window) => self.set_minimized(window, true),
+            Event::WindowDeminimized(ref window) => self.set_minimized(window, false),
+            Event::WindowDestroyed(ref window) => {
+                if let Ok(id) = window.id() {
+                    self.remove(id);
+                }
+                Ok(())
+            }
+            Event::ApplicationHidden(ref application) => match application.pid() {
+                Ok(pid) => {
+                    self.hidden.insert(pid);
+                    self.sync_application(pid)
+                }
+                Err(_) => Ok(()),
+            },
+            Event::ApplicationUnhidden(ref application) => match application.pid() {
+                Ok(pid) => {
+                    self.hidden.remove(&pid);
+                    self.sync_application(pid)
+                }
+                Err(_) => Ok(()),
+            },
+            Event::ApplicationTerminated(ref application) => {
+                if let Ok(pid) = application.pid() {
+                    self.hidden.remove(&pid);
+                    let ids: Vec<u32> = self.application_windows(pid);
+                    for id in ids {
+                        self.remove(id);
+                    }
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Destroy all borders, and forget all windows.
+    pub fn clear(&mut self) {
+        let windows = mem::take(&mut self.windows);
+        for (_, window) in windows {
+            if let Some(shown) = window.border {
+                self.backend.destroy(shown.border);
+            }
+        }
+        self.hidden.clear();
+        self.focused = None;
+    }
+
+    /// Start tracking a window, or update its frame. Returns `None` for a null window.
+    fn track(&mut self, window: &Window) -> Option<u32> {
+        let id = window.id().ok()?;
+        let frame = window.frame().ok()?;
+        let owner = window.owner().ok()?;
+        let pid = owner.pid().ok()?;
+        let application = owner.name().ok()?;
+
+        let tracked = self.windows.entry(id).or_insert_with(|| Tracked {
+            owner: pid,
+            application,
+            frame,
+            minimized: false,
+            fullscreen: false,
+            border: None,
+        });
+        tracked.frame = frame;
+        Some(id)
+    }
+
+    fn set_minimized(&mut self, window: &Window, minimized: bool) -> Result<(), ChunkWMError> {
+        match self.track(window) {
+            Some(id) => {
+                if let Some(window) = self.windows.get_mut(&id) {
+                    window.minimized = minimized;
+                }
+                self.sync(id)
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn remove(&mut self, id: u32) {
+        if let Some(window) = self.windows.remove(&id) {
+            if let Some(shown) = window.border {
+                self.backend.destroy(shown.border);
+            }
+        }
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
+
+    fn application_windows(&self, pid: PID) -> Vec<u32> {
+        self.windows
+            .iter()
+            .filter(|&(_, window)| window.owner == pid)
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    fn sync_application(&mut self, pid: PID) -> Result<(), ChunkWMError> {
+        for id in self.application_windows(pid) {
+            self.sync(id)?;
+        }
+        Ok(())
+    }
+
+    fn sync_all(&mut self) -> Result<(), ChunkWMError> {
+        let ids: Vec<u32> = self.windows.keys().cloned().collect();
+        for id in ids {
+            self.sync(id)?;
+        }
+        Ok(())
+    }
+
+    /// The style a window's border should have, or `None` if it should have no border.
+    fn wanted_style(&self, id: u32) -> Option<BorderStyle> {
+        let window = self.windows.get(&id)?;
+        let settings = &self.settings;
+        if window.minimized
+            || window.fullscreen
+            || self.hidden.contains(&window.owner)
+            || settings.exclude.contains(&window.application)
+        {
+            return None;
+        }
+
+        let focused = self.focused == Some(id);
+        if !focused && !settings.all_windows {
+            return None;
+        }
+        Some(BorderStyle {
+            width: settings.width,
+            radius: settings.radius,
+            color: if focused {
+                settings.active_color
+            } else {
+                settings.inactive_color
+            },
+        })
+    }
+
+    /// Create, update or destroy the border of a window, so that it matches the wanted style.
+    fn sync(&mut self, id: u32) -> Result<(), ChunkWMError> {
+        let style = self.wanted_style(id);
+        let window = match self.windows.get_mut(&id) {
+            Some(window) => window,
+            None => return Ok(()),
+        };
+        let backend = &mut self.backend;
+
+        match (window.border.take(), style) {
+            (None, None) => Ok(()),
+            (Some(shown), None) => {
+                backend.destroy(shown.border);
+                Ok(())
+            }
+            (Some(shown), Some(style)) if shown.style.radius != style.radius => {
+                // The radius of an existing border can't be changed.
+                backend.destroy(shown.border);
+                window.border = Some(Shown {
+                    border: backend.create(&window.frame, &style)?,
+                    frame: window.frame,
+                    style,
+                });
+                Ok(())
+            }
+            (None, Some(style)) => {
+                window.border = Some(Shown {
+                    border: backend.create(&window.frame, &style)?,
+                    frame: window.frame,
+                    style,
+                });
+                Ok(())
+            }
+            (Some(mut shown), Some(style)) => {
+                let result = update(backend, &mut shown, &window.frame, &style);
+                window.border = Some(shown);
+                result
+            }
+        }
+    }
+}
+
+impl<B: BorderBackend> Drop for BorderManager<B> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// Apply the changed parts of the frame and style to a shown border.
+fn update<B: BorderBackend>(
+    backend: &mut B,
+    shown: &mut Shown<B::Border>,
+    frame: &CGRect,
+    style: &BorderStyle,
+) -> Result<(), ChunkWMError> {
+    if !same_rect(&shown.frame, frame) {
+        backend.set_frame(&mut shown.border, frame)?;
+        shown.frame = *frame;
+    }
+    if shown.style.color != style.color {
+        backend.set_color(&mut shown.border, style.color)?;
+        shown.style.color = style.color;
+    }
+    if shown.style.width != style.width {
+        backend.set_width(&mut shown.border, style.width)?;
+        shown.style.width = style.width;
+    }
+    Ok(())
+}
+
+fn same_rect(a: &CGRect, b: &CGRect) -> bool {
+    a.origin.x == b.origin.x
+        && a.origin.y == b.origin.y
+        && a.size.width == b.size.width
+        && a.size.height == b.size.height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::border::BorderOp;
+    use testing::*;
+
+    /// The recorded operations, in a short format.
+    fn log(manager: &BorderManager<RecordingBackend>) -> Vec<String> {
+        manager
+            .backend()
+            .take_ops()
+            .into_iter()
+            .map(|op| match op {
+                BorderOp::Create { id, style, .. } => format!("create {} {}", id, style.color),
+                BorderOp::SetRect { id, rect } => format!("frame {} {} {}", id, rect.0, rect.1),
+                BorderOp::SetColor { id, color } => format!("color {} {}", id, color),
+                BorderOp::SetWidth { id, width } => format!("width {} {}", id, width),
+                BorderOp::Destroy { id } => format!("destroy {}", id),
+            })
+            .collect()
+    }
+
+    const ACTIVE: Color = Color(0xffff_0000);
+    const INACTIVE: Color = Color(0xff00_00ff);
+
+    fn manager(all_windows: bool) -> BorderManager<RecordingBackend> {
+        BorderManager::new(
+            RecordingBackend::new(),
+            BorderSettings {
+                active_color: ACTIVE,
+                inactive_color: INACTIVE,
+                all_windows,
+                exclude: vec!["Finder".to_owned()],
+                ..BorderSettings::default()
+            },
+        )
+    }
+
+    #[test]
+    fn parse_settings() {
+        use config::{read_settings, ConfigFile};
+
+        let (file, _) = ConfigFile::parse(
+            "width = 2\n\
+             active_color = 0xFF00FF00\n\
+             all_windows = 1\n\
+             exclude = Finder, iTerm2\n",
+        );
+        let cvar = |name: &str| {
+            if name == "border_inactive_color" {
+                Some("255".to_owned())
+            } else {
+                None
+            }
+        };
+        let (settings, errors) = read_settings::<BorderSettings>(&file, "border_", &cvar);
+        assert!(errors.is_empty());
+        assert_eq!(
+            settings,
+            BorderSettings {
+                width: 2,
+                active_color: Color(0xff00_ff00),
+                inactive_color: Color(255),
+                all_windows: true,
+                exclude: vec!["Finder".to_owned(), "iTerm2".to_owned()],
+                ..BorderSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn border_follows_focus() {
+        let mut manager = manager(false);
+        let app = application(1, "Terminal");
+        let a = window(&app, 10, "a", (0.0, 0.0, 100.0, 100.0));
+        let b = window(&app, 20, "b", (100.0, 0.0, 100.0, 100.0));
+
+        manager
+            .handle(&Event::WindowCreated(same_window(&a)))
+            .unwrap();
+        assert!(manager.backend().live().is_empty());
+
+        manager
+            .handle(&Event::WindowFocused(same_window(&a)))
+            .unwrap();
+        manager
+            .handle(&Event::WindowFocused(same_window(&b)))
+            .unwrap();
+        assert_eq!(
+            log(&manager),
+            vec!["create 1 0xffff0000", "destroy 1", "create 2 0xffff0000"]
+        );
+        assert_eq!(manager.focused(), Some(20));
+        assert!(!manager.has_border(10) && manager.has_border(20));
+
+        set_frame(&b, (150.0, 50.0, 100.0, 100.0));
+        manager
+            .handle(&Event::WindowMoved(same_window(&b)))
+            .unwrap();
+        set_frame(&b, (150.0, 50.0, 100.0, 100.0));
+        manager
+            .handle(&Event::WindowResized(same_window(&b)))
+            .unwrap();
+        assert_eq!(log(&manager), vec!["frame 2 150 50"]);
+
+        manager
+            .handle(&Event::WindowDestroyed(same_window(&b)))
+            .unwrap();
+        assert_eq!(log(&manager), vec!["destroy 2"]);
+        assert_eq!(manager.focused(), None);
+    }
+
+    #[test]
+    fn all_windows_and_hiding() {
+        let mut manager = manager(true);
+        let app = application(1, "Terminal");
+        let finder = application(2, "Finder");
+        let a = window(&app, 10, "a", (0.0, 0.0, 100.0, 100.0));
+        let b = window(&app, 20, "b", (100.0, 0.0, 100.0, 100.0));
+        let c = window(&finder, 30, "c", (200.0, 0.0, 100.0, 100.0));
+
+        for window in &[&a, &b, &c] {
+            manager
+                .handle(&Event::WindowCreated(same_window(window)))
+                .unwrap();
+        }
+        manager
+            .handle(&Event::WindowFocused(same_window(&a)))
+            .unwrap();
+        manager
+            .handle(&Event::WindowFocused(same_window(&c)))
+            .unwrap();
+        assert_eq!(
+            log(&manager),
+            vec![
+                "create 1 0xff0000ff",
+                "create 2 0xff0000ff",
+                "color 1 0xffff0000",
+                "color 1 0xff0000ff",
+            ]
+        );
+        assert!(!manager.has_border(30), "excluded application");
+
+        manager
+            .handle(&Event::WindowMinimized(same_window(&b)))
+            .unwrap();
+        manager
+            .handle(&Event::WindowDeminimized(same_window(&b)))
+            .unwrap();
+        manager.set_fullscreen(10, true).unwrap();
+        manager.set_fullscreen(10, false).unwrap();
+        assert_eq!(
+            log(&manager),
+            vec![
+                "destroy 2",
+                "create 3 0xff0000ff",
+                "destroy 1",
+                "create 4 0xff0000ff",
+            ]
+        );
+
+        manager
+            .handle(&Event::ApplicationHidden(same_application(&app)))
+            .unwrap();
+        assert!(manager.backend().live().is_empty());
+        manager
+            .handle(&Event::ApplicationUnhidden(same_application(&app)))
+            .unwrap();
+        assert_eq!(manager.backend().live().len(), 2);
+
+        manager
+            .handle(&Event::ApplicationTerminated(same_application(&app)))
+            .unwrap();
+        assert!(manager.backend().live().is_empty());
+    }
+
+    #[test]
+    fn settings_changes() {
+        let mut manager = manager(true);
+        let app = application(1, "Terminal");
+        let a = window(&app, 10, "a", (0.0, 0.0, 100.0, 100.0));
+        let b = window(&app, 20, "b", (100.0, 0.0, 100.0, 100.0));
+        manager
+            .handle(&Event::WindowCreated(same_window(&a)))
+            .unwrap();
+        manager
+            .handle(&Event::WindowFocused(same_window(&b)))
+            .unwrap();
+        log(&manager);
+
+        let mut settings = manager.settings().clone();
+        settings.width = 8;
+        settings.active_color = Color(0xffff_ffff);
+        manager.set_settings(settings.clone()).unwrap();
+        assert_eq!(
+            log(&manager),
+            vec!["width 1 8", "color 2 0xffffffff", "width 2 8"]
+        );
+
+        settings.radius = 0;
+        settings.all_windows = false;
+        manager.set_settings(settings).unwrap();
+        assert_eq!(
+            log(&manager),
+            vec!["destroy 1", "destroy 2", "create 3 0xffffffff"]
+        );
+        assert_eq!(manager.border_style(20).map(|style| style.radius), Some(0));
+
+        manager.clear();
+        assert!(manager.backend().live().is_empty());
+    }
+
+    #[cfg(feature = "border")]
+    #[test]
+    fn cocoa_manager_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<BorderManager<CocoaBackend>>();
+    }
+}
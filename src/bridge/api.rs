@@ -10,6 +10,7 @@ use ChunkWMError;
 
 /// The `LogLevel` defines how something should be logged for chunkwm.
 /// See the [chunkwm readme](https://github.com/koekeishiya/chunkwm#configuration).
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
 pub enum LogLevel {
     Debug = 0,
@@ -31,10 +32,29 @@ pub struct API {
     _acquire_cvar: unsafe extern "C" fn(*const c_char) -> *const c_char,
     _find_cvar: unsafe extern "C" fn(*const c_char) -> bool,
     _plugin_broadcast: unsafe extern "C" fn(*const c_char, *const c_char, *mut c_void, usize),
+    #[cfg(not(feature = "api-7"))]
     _log: unsafe extern "C" fn(level: LogLevel, format: *const c_char),
 }
 
 impl API {
+    /// Create an `API` from the functions that implement it, e.g. for the `simulator`.
+    pub(crate) fn from_functions(
+        update_cvar: unsafe extern "C" fn(*const c_char, *const c_char),
+        acquire_cvar: unsafe extern "C" fn(*const c_char) -> *const c_char,
+        find_cvar: unsafe extern "C" fn(*const c_char) -> bool,
+        plugin_broadcast: unsafe extern "C" fn(*const c_char, *const c_char, *mut c_void, usize),
+        #[cfg(not(feature = "api-7"))] log: unsafe extern "C" fn(LogLevel, *const c_char),
+    ) -> API {
+        API {
+            _update_cvar: update_cvar,
+            _acquire_cvar: acquire_cvar,
+            _find_cvar: find_cvar,
+            _plugin_broadcast: plugin_broadcast,
+            #[cfg(not(feature = "api-7"))]
+            _log: log,
+        }
+    }
+
     /// Checks whether a CVar has been declared.
     pub fn cvar_exists(&self, name: &str) -> bool {
         let name: *const c_char = CString::new(name).unwrap().into_raw();
@@ -84,10 +104,21 @@ impl API {
 
     /// Log with a specified log level. A newline will be added to the message, so no need to do
     /// that yourself.
+    #[cfg(not(feature = "api-7"))]
     pub fn log<S: AsRef<str>>(&self, level: LogLevel, message: S) {
         let message = format!("{}\n", message.as_ref());
         unsafe { (self._log)(level, CString::new(message).unwrap().into_raw()) }
     }
+
+    /// Log a message. Plugin API version 7 has no log function, so the message is written to
+    /// stderr.
+    #[cfg(feature = "api-7")]
+    pub fn log<S: AsRef<str>>(&self, level: LogLevel, message: S) {
+        if let LogLevel::None = level {
+            return;
+        }
+        eprintln!("{}", message.as_ref());
+    }
 }
 
 /// The `CVar` struct allows you to easily create a handle to `CVars`, that might be updated. You
@@ -145,7 +176,7 @@ impl<T: FromStr + Display> CVar<T> {
             Ok(_) => Ok(CVar {
                 name,
                 api,
-                value_marker: PhantomData::default(),
+                value_marker: PhantomData,
             }),
             Err(_) => Err(ChunkWMError::CVarNotFound(name)),
         }
@@ -157,7 +188,7 @@ impl<T: FromStr + Display> CVar<T> {
         CVar {
             name,
             api,
-            value_marker: PhantomData::default(),
+            value_marker: PhantomData,
         }
     }
 
@@ -195,6 +226,8 @@ impl<T: FromStr + Display> CVar<T> {
 /// assert_eq!(b.value, true);
 /// ```
 #[derive(Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
 pub struct NumericBool {
     pub value: bool,
 }
@@ -212,7 +245,7 @@ impl From<bool> for NumericBool {
     }
 }
 
-impl<'a> From<&'a NumericBool> for u8 {
+impl From<&NumericBool> for u8 {
     fn from(v: &NumericBool) -> u8 {
         if v.value {
             1
@@ -222,9 +255,9 @@ impl<'a> From<&'a NumericBool> for u8 {
     }
 }
 
-impl Into<u8> for NumericBool {
-    fn into(self) -> u8 {
-        if self.value {
+impl From<NumericBool> for u8 {
+    fn from(numeric_bool: NumericBool) -> u8 {
+        if numeric_bool.value {
             1
         } else {
             0
@@ -35,6 +35,9 @@ impl Application {
     }
 
     /// Get the raw application pointer.
+    ///
+    /// # Safety
+    /// The pointer is owned by chunkwm, and is only valid while the application is running.
     pub unsafe fn application_ref(&self) -> Result<ApplicationRef, ChunkWMError> {
         if !self.0.is_null() {
             Ok(self.0)
@@ -87,18 +90,12 @@ impl From<ApplicationRef> for Application {
     }
 }
 
-impl<'a> From<&'a mut RawApplication> for Application {
+impl From<&mut RawApplication> for Application {
     fn from(raw_app: &mut RawApplication) -> Application {
         Application(raw_app)
     }
 }
 
-impl Into<Application> for RawApplication {
-    fn into(mut self) -> Application {
-        Application(&mut self)
-    }
-}
-
 impl From<*mut c_void> for Application {
     fn from(application_ref: *mut c_void) -> Application {
         Application(application_ref as ApplicationRef)
@@ -0,0 +1,354 @@
+//! The `cursor` module contains functions for getting and setting the cursor position, and the
+//! logic for the focus-follows-mouse and mouse-follows-focus behaviours.
+//!
+//! The decision logic in [`CursorTracker`](./struct.CursorTracker.html) only works on window
+//! frames, so that it can be used (and tested) without talking to the window server. It is up to
+//! the plugin to apply the returned [`CursorAction`](./enum.CursorAction.html)s.
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! use chunkwm::cursor::*;
+//! use chunkwm::geometry::{CGPoint, CGRect, CGSize};
+//!
+//! # fn main() {
+//! let mut tracker = CursorTracker::new(CursorSettings {
+//!     focus_follows_mouse: true,
+//!     mouse_follows_focus: false,
+//! });
+//! let windows = [
+//!     WindowFrame::new(1, CGRect::new(&CGPoint::new(0.0, 0.0), &CGSize::new(100.0, 100.0))),
+//!     WindowFrame::new(2, CGRect::new(&CGPoint::new(100.0, 0.0), &CGSize::new(100.0, 100.0))),
+//! ];
+//!
+//! let action = tracker.cursor_moved(CGPoint::new(150.0, 50.0), &windows);
+//! assert_eq!(action, Some(CursorAction::Focus(2)));
+//! # }
+//! ```
+
+use api::{NumericBool, API};
+use geometry::{self, CGPoint, CGRect};
+use system::WindowSystem;
+use window::Window;
+use ChunkWMError;
+
+#[cfg(feature = "accessibility")]
+use common::accessibility::element;
+#[cfg(feature = "accessibility")]
+use core_graphics::display::CGDisplay;
+
+/// Get the position of the cursor.
+/// Needed features: `accessibility`.
+#[cfg(feature = "accessibility")]
+pub fn cursor_position() -> CGPoint {
+    unsafe { element::get_cursor_position() }
+}
+
+/// Move the cursor to a position, without generating mouse events.
+/// Needed features: `accessibility`.
+#[cfg(feature = "accessibility")]
+pub fn set_cursor_position(point: CGPoint) -> Result<(), ChunkWMError> {
+    CGDisplay::warp_mouse_cursor_position(point)
+        .map_err(|_| ChunkWMError::Internal("could not move the cursor"))
+}
+
+/// The frame of a window, used for hit-testing.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WindowFrame {
+    /// The window's id.
+    pub id: u32,
+    /// The window's frame.
+    #[cfg_attr(feature = "serde", serde(with = "geometry::CGRectDef"))]
+    pub frame: CGRect,
+}
+
+impl WindowFrame {
+    /// Create a new `WindowFrame`.
+    pub fn new(id: u32, frame: CGRect) -> WindowFrame {
+        WindowFrame { id, frame }
+    }
+
+    /// Create a `WindowFrame` from a `Window`.
+    pub fn from_window(window: &Window) -> Result<WindowFrame, ChunkWMError> {
+        Ok(WindowFrame::new(window.id()?, window.frame()?))
+    }
+
+    /// Check whether the frame contains a point.
+    pub fn contains(&self, point: &CGPoint) -> bool {
+        geometry::contains(&self.frame, point)
+    }
+}
+
+/// Get the id of the window under a point.
+///
+/// The windows should be ordered from front to back: the first window that contains the point is
+/// returned.
+pub fn window_at_point(point: &CGPoint, windows: &[WindowFrame]) -> Option<u32> {
+    windows
+        .iter()
+        .find(|window| window.contains(point))
+        .map(|window| window.id)
+}
+
+/// Get the window under the cursor, from a list of windows ordered from front to back.
+/// Windows of which the frame cannot be read are skipped.
+pub fn window_under_cursor<'a, S: WindowSystem + ?Sized>(
+    system: &S,
+    windows: &'a [Window],
+) -> Result<Option<&'a Window>, ChunkWMError> {
+    let point = system.cursor_position()?;
+    Ok(windows.iter().find(|window| {
+        WindowFrame::from_window(window)
+            .map(|frame| frame.contains(&point))
+            .unwrap_or(false)
+    }))
+}
+
+/// The settings for the cursor behaviours.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CursorSettings {
+    /// Focus the window under the cursor when the cursor moves.
+    pub focus_follows_mouse: bool,
+    /// Move the cursor to the center of a window when it is focused.
+    pub mouse_follows_focus: bool,
+}
+
+impl CursorSettings {
+    /// Read the settings from two `NumericBool` `CVar`s. The `CVar`s are created (disabled) when
+    /// they do not exist yet, so that they can be set from the chunkwm config.
+    pub fn from_cvars(api: &API, focus_follows_mouse: &str, mouse_follows_focus: &str) -> Self {
+        let read = |name: &str| {
+            api.create_cvar(name, &NumericBool::from(false));
+            api.get_cvar::<NumericBool>(name)
+                .map(|v| v.value)
+                .unwrap_or(false)
+        };
+
+        CursorSettings {
+            focus_follows_mouse: read(focus_follows_mouse),
+            mouse_follows_focus: read(mouse_follows_focus),
+        }
+    }
+}
+
+/// An action the plugin should perform.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum CursorAction {
+    /// Focus the window with this id.
+    Focus(u32),
+    /// Move the cursor to this point.
+    MoveCursor(#[cfg_attr(feature = "serde", serde(with = "geometry::CGPointDef"))] CGPoint),
+}
+
+impl PartialEq for CursorAction {
+    fn eq(&self, other: &CursorAction) -> bool {
+        match (*self, *other) {
+            (CursorAction::Focus(a), CursorAction::Focus(b)) => a == b,
+            (CursorAction::MoveCursor(a), CursorAction::MoveCursor(b)) => a.x == b.x && a.y == b.y,
+            _ => false,
+        }
+    }
+}
+
+/// The `CursorTracker` decides when to focus a window or move the cursor.
+#[derive(Debug, Clone, Default)]
+pub struct CursorTracker {
+    settings: CursorSettings,
+    focused: Option<u32>,
+}
+
+impl CursorTracker {
+    /// Create a new `CursorTracker`.
+    pub fn new(settings: CursorSettings) -> CursorTracker {
+        CursorTracker {
+            settings,
+            focused: None,
+        }
+    }
+
+    /// Get the current settings.
+    pub fn settings(&self) -> CursorSettings {
+        self.settings
+    }
+
+    /// Replace the settings, e.g. after the `CVar`s have been updated.
+    pub fn set_settings(&mut self, settings: CursorSettings) {
+        self.settings = settings;
+    }
+
+    /// Get the id of the window the tracker thinks is focused.
+    pub fn focused(&self) -> Option<u32> {
+        self.focused
+    }
+
+    /// Call this when the cursor has moved. The windows should be ordered from front to back.
+    ///
+    /// Returns `CursorAction::Focus` when focus-follows-mouse is enabled and the window under the
+    /// cursor is not focused yet.
+    pub fn cursor_moved(
+        &mut self,
+        point: CGPoint,
+        windows: &[WindowFrame],
+    ) -> Option<CursorAction> {
+        if !self.settings.focus_follows_mouse {
+            return None;
+        }
+
+        match window_at_point(&point, windows) {
+            Some(id) if Some(id) != self.focused => {
+                self.focused = Some(id);
+                Some(CursorAction::Focus(id))
+            }
+            _ => None,
+        }
+    }
+
+    /// Call this when a window has been focused, e.g. on `Event::WindowFocused`.
+    ///
+    /// Returns `CursorAction::MoveCursor` with the center of the window when mouse-follows-focus
+    /// is enabled and the cursor is not inside the window already.
+    pub fn window_focused(
+        &mut self,
+        window: &WindowFrame,
+        cursor: CGPoint,
+    ) -> Option<CursorAction> {
+        self.focused = Some(window.id);
+
+        if !self.settings.mouse_follows_focus || window.contains(&cursor) {
+            return None;
+        }
+
+        Some(CursorAction::MoveCursor(geometry::center(&window.frame)))
+    }
+
+    /// Call this when a window has been destroyed or minimized.
+    pub fn window_removed(&mut self, id: u32) {
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geometry::CGSize;
+
+    fn frame(id: u32, x: f64, y: f64, w: f64, h: f64) -> WindowFrame {
+        WindowFrame::new(id, CGRect::new(&CGPoint::new(x, y), &CGSize::new(w, h)))
+    }
+
+    fn settings(focus_follows_mouse: bool, mouse_follows_focus: bool) -> CursorSettings {
+        CursorSettings {
+            focus_follows_mouse,
+            mouse_follows_focus,
+        }
+    }
+
+    #[test]
+    fn front_most_window_wins() {
+        let windows = [
+            frame(1, 50.0, 50.0, 100.0, 100.0),
+            frame(2, 0.0, 0.0, 500.0, 500.0),
+        ];
+        assert_eq!(
+            window_at_point(&CGPoint::new(60.0, 60.0), &windows),
+            Some(1)
+        );
+        assert_eq!(
+            window_at_point(&CGPoint::new(10.0, 10.0), &windows),
+            Some(2)
+        );
+        assert_eq!(window_at_point(&CGPoint::new(600.0, 10.0), &windows), None);
+    }
+
+    #[test]
+    fn focus_follows_mouse() {
+        let windows = [
+            frame(1, 0.0, 0.0, 100.0, 100.0),
+            frame(2, 100.0, 0.0, 100.0, 100.0),
+        ];
+        let mut tracker = CursorTracker::new(settings(true, false));
+
+        assert_eq!(
+            tracker.cursor_moved(CGPoint::new(10.0, 10.0), &windows),
+            Some(CursorAction::Focus(1))
+        );
+        assert_eq!(
+            tracker.cursor_moved(CGPoint::new(20.0, 10.0), &windows),
+            None
+        );
+        assert_eq!(
+            tracker.cursor_moved(CGPoint::new(120.0, 10.0), &windows),
+            Some(CursorAction::Focus(2))
+        );
+        // Moving over the desktop doesn't change focus.
+        assert_eq!(
+            tracker.cursor_moved(CGPoint::new(500.0, 500.0), &windows),
+            None
+        );
+        assert_eq!(tracker.focused(), Some(2));
+    }
+
+    #[test]
+    fn focus_follows_mouse_disabled() {
+        let windows = [frame(1, 0.0, 0.0, 100.0, 100.0)];
+        let mut tracker = CursorTracker::new(settings(false, false));
+        assert_eq!(
+            tracker.cursor_moved(CGPoint::new(10.0, 10.0), &windows),
+            None
+        );
+    }
+
+    #[test]
+    fn mouse_follows_focus() {
+        let window = frame(1, 100.0, 100.0, 200.0, 100.0);
+        let mut tracker = CursorTracker::new(settings(false, true));
+
+        assert_eq!(
+            tracker.window_focused(&window, CGPoint::new(0.0, 0.0)),
+            Some(CursorAction::MoveCursor(CGPoint::new(200.0, 150.0)))
+        );
+        // The cursor is already inside the window.
+        assert_eq!(
+            tracker.window_focused(&window, CGPoint::new(110.0, 110.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn behaviours_do_not_fight() {
+        let windows = [
+            frame(1, 0.0, 0.0, 100.0, 100.0),
+            frame(2, 100.0, 0.0, 100.0, 100.0),
+        ];
+        let mut tracker = CursorTracker::new(settings(true, true));
+
+        // The cursor is moved into window 2 after it is focused, which shouldn't refocus.
+        let action = tracker.window_focused(&windows[1], CGPoint::new(10.0, 10.0));
+        assert_eq!(
+            action,
+            Some(CursorAction::MoveCursor(CGPoint::new(150.0, 50.0)))
+        );
+        assert_eq!(
+            tracker.cursor_moved(CGPoint::new(150.0, 50.0), &windows),
+            None
+        );
+    }
+
+    #[test]
+    fn removed_window_is_forgotten() {
+        let windows = [frame(1, 0.0, 0.0, 100.0, 100.0)];
+        let mut tracker = CursorTracker::new(settings(true, false));
+        tracker.cursor_moved(CGPoint::new(10.0, 10.0), &windows);
+        tracker.window_removed(1);
+        assert_eq!(tracker.focused(), None);
+        assert_eq!(
+            tracker.cursor_moved(CGPoint::new(10.0, 10.0), &windows),
+            Some(CursorAction::Focus(1))
+        );
+    }
+}
@@ -2,16 +2,23 @@
 
 #[cfg(feature = "accessibility")]
 use bridge::window::Window;
-use core_foundation::string::CFStringRef;
-use core_graphics::display::CGDirectDisplayID;
+#[cfg(feature = "accessibility")]
+use geometry::CGRect;
 use raw::*;
+use std::fmt;
+use std::str::FromStr;
 use ChunkWMError;
 
 #[cfg(feature = "accessibility")]
 use common::accessibility::display;
+#[cfg(target_os = "macos")]
+use core_foundation::base::TCFType;
+#[cfg(target_os = "macos")]
+use core_foundation::string::CFString;
 
 /// The way the dock is oriented.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
 pub enum DockOrientation {
     Top = 1,
@@ -22,6 +29,7 @@ pub enum DockOrientation {
 
 /// The space type.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
 pub enum SpaceType {
     /// Space is handled by user.
@@ -33,6 +41,132 @@ pub enum SpaceType {
     Unknown,
 }
 
+/// The UUID of a display. Unlike the id of a display, it stays the same when the display is
+/// reconnected, so it can be stored and compared across connections and restarts.
+///
+/// It is parsed from and formatted as `XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX`, with uppercase
+/// hexadecimal digits.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DisplayUuid(String);
+
+impl DisplayUuid {
+    /// Get the UUID as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Read a UUID from a `CFStringRef` that is not owned, such as the identifier in a
+    /// `RawDisplay`.
+    ///
+    /// # Safety
+    /// The pointer must be null or point to a valid `CFString`.
+    #[cfg(target_os = "macos")]
+    pub unsafe fn from_cf_string_ref(string: CFStringRef) -> Result<DisplayUuid, ChunkWMError> {
+        if string.is_null() {
+            Err(ChunkWMError::NullPointer)
+        } else {
+            CFString::wrap_under_get_rule(string).to_string().parse()
+        }
+    }
+
+    /// Read a UUID from a `CFStringRef` that was created for the caller, and release the string.
+    #[cfg(feature = "accessibility")]
+    unsafe fn from_created(string: CFStringRef) -> Result<DisplayUuid, ChunkWMError> {
+        if string.is_null() {
+            Err(ChunkWMError::NullPointer)
+        } else {
+            CFString::wrap_under_create_rule(string).to_string().parse()
+        }
+    }
+
+    /// Create a `CFString` of the UUID, for the functions that take a display identifier. Pass
+    /// `as_concrete_TypeRef()` of the string while it is alive.
+    #[cfg(target_os = "macos")]
+    pub fn to_cf_string(&self) -> CFString {
+        CFString::new(&self.0)
+    }
+
+    /// Get the UUID of the display with an id.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn of_display(id: CGDirectDisplayID) -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_created(display::get_display_identifier(id)) }
+    }
+
+    /// Get the UUID of the display at a place in the arrangement.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn of_arrangement(arrangement: u32) -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_created(display::get_display_id_from_arrangement(arrangement)) }
+    }
+
+    /// Get the UUID of the display a space is on.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn of_space(space: CGSSpaceID) -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_created(display::get_display_id_from_space(space)) }
+    }
+
+    /// Get the UUID of the display a window is on.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn of_window(window: &Window) -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_created(display::get_display_id_from_window(window.id()?)) }
+    }
+
+    /// Get the UUID of the display that contains most of a rectangle.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn of_rect(rect: &CGRect) -> Result<DisplayUuid, ChunkWMError> {
+        unsafe {
+            DisplayUuid::from_created(display::get_display_id_from_window_rect(
+                rect.origin,
+                rect.size,
+            ))
+        }
+    }
+
+    /// Get the UUID of the right most display.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn right_most() -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_created(display::get_display_id_for_right_most_display()) }
+    }
+
+    /// Get the UUID of the left most display.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn left_most() -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_created(display::get_display_id_for_left_most_display()) }
+    }
+
+    /// Get the UUID of the bottom most display.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn bottom_most() -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_created(display::get_display_id_for_bottom_most_display()) }
+    }
+}
+
+impl FromStr for DisplayUuid {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<DisplayUuid, ChunkWMError> {
+        let groups = s.split('-').map(str::len).collect::<Vec<_>>();
+        if groups != [8, 4, 4, 4, 12] || !s.chars().all(|c| c == '-' || c.is_ascii_hexdigit()) {
+            return Err(ChunkWMError::ParseError("invalid display UUID"));
+        }
+        Ok(DisplayUuid(s.to_ascii_uppercase()))
+    }
+}
+
+impl fmt::Display for DisplayUuid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// The `Display` struct.
 pub struct Display(DisplayRef);
 
@@ -58,6 +192,12 @@ impl Display {
         unsafe { Ok((*self.display_ref()?).display_ref) }
     }
 
+    /// Get the display's UUID.
+    #[cfg(target_os = "macos")]
+    pub fn uuid(&self) -> Result<DisplayUuid, ChunkWMError> {
+        unsafe { DisplayUuid::from_cf_string_ref(self.display_identifier_ref()?) }
+    }
+
     /// Get the display's id.
     pub fn id(&self) -> Result<CGDirectDisplayID, ChunkWMError> {
         unsafe { Ok((*self.display_ref()?).id) }
@@ -102,6 +242,9 @@ pub struct Space(SpaceRef);
 
 impl Space {
     /// Get the raw `SpaceRef`.
+    ///
+    /// # Safety
+    /// The pointer is only valid until the space is destroyed.
     pub unsafe fn space_ref(&self) -> Result<SpaceRef, ChunkWMError> {
         if !self.0.is_null() {
             Ok(self.0)
@@ -147,3 +290,22 @@ impl Space {
         unsafe { Ok(display::space_has_window(self.id()?, window.id()?)) }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_display_uuid() {
+        let uuid = "37d8832a-2d66-02ca-b9f7-8f30a301b230"
+            .parse::<DisplayUuid>()
+            .unwrap();
+        assert_eq!(uuid.as_str(), "37D8832A-2D66-02CA-B9F7-8F30A301B230");
+        assert_eq!(uuid.to_string().parse::<DisplayUuid>().unwrap(), uuid);
+        assert!("37D8832A-2D66-02CA-B9F7".parse::<DisplayUuid>().is_err());
+        assert!("37D8832A-2D66-02CA-B9F7-8F30A301B23G"
+            .parse::<DisplayUuid>()
+            .is_err());
+        assert!("".parse::<DisplayUuid>().is_err());
+    }
+}
@@ -2,9 +2,14 @@
 
 use api::*;
 use application::*;
-use core_graphics::display::CGDirectDisplayID;
+use filter::EventFilters;
 use payload::*;
+use raw::CGDirectDisplayID;
+use state::TrackedState;
+use system::WindowSystem;
+use timer::TimerId;
 use window::*;
+use workspace::{ApplicationInfo, WindowInfo};
 use ChunkWMError;
 
 /// The display identifier.
@@ -20,6 +25,18 @@ pub trait HandleEvent {
     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError>;
     /// Is run when the plugin has been stopped.
     fn shutdown(&self);
+    /// The per-window and per-application state that is cleaned up automatically after
+    /// `WindowDestroyed` and `ApplicationTerminated` events, see the `state` module. The plugin
+    /// doesn't have to subscribe to these events for the cleanup.
+    fn tracked_state(&mut self) -> Vec<&mut dyn TrackedState> {
+        Vec::new()
+    }
+    /// The filters events must pass to reach `handle`, and the window system they are checked
+    /// with, see the `filter` module. Events that don't pass are still used for the cleanup of
+    /// `tracked_state`.
+    fn filters(&mut self) -> Option<(&EventFilters, &dyn WindowSystem)> {
+        None
+    }
 }
 
 /// The `Event` enum allows for easy pattern matching on events.
@@ -45,12 +62,121 @@ pub enum Event {
     DisplayResized(DisplayID),
     DisplayChanged,
     SpaceChanged,
+    /// A timer scheduled with the `timer` module expired.
+    Timer(TimerId),
     DaemonCommand(Payload),
     Other(String),
 }
 
+/// The `EventSnapshot` enum is an owned copy of an `Event`: windows and applications are replaced
+/// by snapshots of their data, so that it can be stored, sent elsewhere, or serialized (with the
+/// `serde` feature).
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "event", content = "data"))]
+pub enum EventSnapshot {
+    ApplicationLaunched(ApplicationInfo),
+    ApplicationTerminated(ApplicationInfo),
+    ApplicationActivated(ApplicationInfo),
+    ApplicationDeactivated(ApplicationInfo),
+    ApplicationHidden(ApplicationInfo),
+    ApplicationUnhidden(ApplicationInfo),
+    WindowCreated(WindowInfo),
+    WindowDestroyed(WindowInfo),
+    WindowFocused(WindowInfo),
+    WindowMoved(WindowInfo),
+    WindowResized(WindowInfo),
+    WindowMinimized(WindowInfo),
+    WindowDeminimized(WindowInfo),
+    WindowSheetCreated(WindowInfo),
+    WindowTitleChanged(WindowInfo),
+    DisplayAdded(DisplayID),
+    DisplayRemoved(DisplayID),
+    DisplayMoved(DisplayID),
+    DisplayResized(DisplayID),
+    DisplayChanged,
+    SpaceChanged,
+    Timer(TimerId),
+    DaemonCommand { command: String, message: String },
+    Other(String),
+}
+
+impl Event {
+    /// The subscription that delivers this event. Timers, daemon commands and other events are
+    /// delivered without a subscription.
+    pub fn subscription(&self) -> Option<Subscription> {
+        use self::Subscription as S;
+
+        Some(match *self {
+            Event::ApplicationLaunched(_) => S::ApplicationLaunched,
+            Event::ApplicationTerminated(_) => S::ApplicationTerminated,
+            Event::ApplicationActivated(_) => S::ApplicationActivated,
+            Event::ApplicationDeactivated(_) => S::ApplicationDeactivated,
+            Event::ApplicationHidden(_) => S::ApplicationHidden,
+            Event::ApplicationUnhidden(_) => S::ApplicationUnhidden,
+            Event::WindowCreated(_) => S::WindowCreated,
+            Event::WindowDestroyed(_) => S::WindowDestroyed,
+            Event::WindowFocused(_) => S::WindowFocused,
+            Event::WindowMoved(_) => S::WindowMoved,
+            Event::WindowResized(_) => S::WindowResized,
+            Event::WindowMinimized(_) => S::WindowMinimized,
+            Event::WindowDeminimized(_) => S::WindowDeminimized,
+            Event::WindowSheetCreated(_) => S::WindowSheetCreated,
+            Event::WindowTitleChanged(_) => S::WindowTitleChanged,
+            Event::DisplayAdded(_) => S::DisplayAdded,
+            Event::DisplayRemoved(_) => S::DisplayRemoved,
+            Event::DisplayMoved(_) => S::DisplayMoved,
+            Event::DisplayResized(_) => S::DisplayResized,
+            Event::DisplayChanged => S::DisplayChanged,
+            Event::SpaceChanged => S::SpaceChanged,
+            Event::Timer(_) | Event::DaemonCommand(_) | Event::Other(_) => return None,
+        })
+    }
+}
+
+impl EventSnapshot {
+    /// Create a snapshot of an `Event`. Fails when the event carries a null pointer.
+    pub fn from_event(event: &Event) -> Result<EventSnapshot, ChunkWMError> {
+        use self::EventSnapshot as S;
+        let app = ApplicationInfo::from_application;
+        let window = WindowInfo::from_window;
+
+        Ok(match *event {
+            Event::ApplicationLaunched(ref a) => S::ApplicationLaunched(app(a)?),
+            Event::ApplicationTerminated(ref a) => S::ApplicationTerminated(app(a)?),
+            Event::ApplicationActivated(ref a) => S::ApplicationActivated(app(a)?),
+            Event::ApplicationDeactivated(ref a) => S::ApplicationDeactivated(app(a)?),
+            Event::ApplicationHidden(ref a) => S::ApplicationHidden(app(a)?),
+            Event::ApplicationUnhidden(ref a) => S::ApplicationUnhidden(app(a)?),
+            Event::WindowCreated(ref w) => S::WindowCreated(window(w)?),
+            Event::WindowDestroyed(ref w) => S::WindowDestroyed(window(w)?),
+            Event::WindowFocused(ref w) => S::WindowFocused(window(w)?),
+            Event::WindowMoved(ref w) => S::WindowMoved(window(w)?),
+            Event::WindowResized(ref w) => S::WindowResized(window(w)?),
+            Event::WindowMinimized(ref w) => S::WindowMinimized(window(w)?),
+            Event::WindowDeminimized(ref w) => S::WindowDeminimized(window(w)?),
+            Event::WindowSheetCreated(ref w) => S::WindowSheetCreated(window(w)?),
+            Event::WindowTitleChanged(ref w) => S::WindowTitleChanged(window(w)?),
+            Event::DisplayAdded(id) => S::DisplayAdded(id),
+            Event::DisplayRemoved(id) => S::DisplayRemoved(id),
+            Event::DisplayMoved(id) => S::DisplayMoved(id),
+            Event::DisplayResized(id) => S::DisplayResized(id),
+            Event::DisplayChanged => S::DisplayChanged,
+            Event::SpaceChanged => S::SpaceChanged,
+            Event::Timer(id) => S::Timer(id),
+            Event::DaemonCommand(ref payload) => S::DaemonCommand {
+                command: payload.command()?,
+                message: payload.message()?,
+            },
+            Event::Other(ref name) => S::Other(name.clone()),
+        })
+    }
+}
+
 /// The `Subscription` enum is used to subscribe to chunkwm events.
 // NOTE(splintah): order must be the same as the C version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
 pub enum Subscription {
     ApplicationLaunched,
@@ -107,3 +233,70 @@ impl Subscription {
         ]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use raw::WindowRef;
+    use testing;
+
+    #[test]
+    fn snapshot_window_event() {
+        let app = testing::application(100, "Terminal");
+        let window = testing::window(&app, 1, "vim", (10.0, 20.0, 300.0, 400.0));
+
+        match EventSnapshot::from_event(&Event::WindowFocused(window)).unwrap() {
+            EventSnapshot::WindowFocused(info) => {
+                assert_eq!((info.id, info.owner), (1, 100));
+                assert_eq!(info.name, "vim");
+                assert_eq!(info.size.height, 400.0);
+            }
+            _ => panic!("wrong snapshot"),
+        }
+    }
+
+    #[test]
+    fn snapshot_null_pointer() {
+        let window = Window::from(::std::ptr::null_mut() as WindowRef);
+        assert!(EventSnapshot::from_event(&Event::WindowCreated(window)).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_snapshot() {
+        let app = testing::application(100, "Terminal");
+        let snapshot = EventSnapshot::from_event(&Event::ApplicationLaunched(app)).unwrap();
+        let json = ::serde_json::to_string(&snapshot).unwrap();
+        assert_eq!(
+            json,
+            r#"{"event":"ApplicationLaunched","data":{"pid":100,"name":"Terminal","process_serial_number":{"high_long":0,"low_long":100},"hidden":false}}"#
+        );
+
+        let snapshot: EventSnapshot = ::serde_json::from_str(&json).unwrap();
+        match snapshot {
+            EventSnapshot::ApplicationLaunched(info) => assert_eq!(info.name, "Terminal"),
+            _ => panic!("wrong snapshot"),
+        }
+
+        let json = ::serde_json::to_string(&EventSnapshot::DisplayAdded(2)).unwrap();
+        assert_eq!(json, r#"{"event":"DisplayAdded","data":2}"#);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_owned_types() {
+        use display::{DockOrientation, SpaceType};
+
+        let json = ::serde_json::to_string(&WindowFlag::from(0b1010)).unwrap();
+        assert_eq!(json, r#"["Movable","Minimized"]"#);
+        let flags: Vec<WindowFlag> = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(flags, vec![WindowFlag::Movable, WindowFlag::Minimized]);
+
+        let json =
+            ::serde_json::to_string(&(SpaceType::Fullscreen, DockOrientation::Left)).unwrap();
+        assert_eq!(json, r#"["Fullscreen","Left"]"#);
+
+        let json = ::serde_json::to_string(&NumericBool::from(true)).unwrap();
+        assert_eq!(json, "true");
+    }
+}
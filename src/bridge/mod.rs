@@ -1,5 +1,6 @@
 pub mod api;
 pub mod application;
+pub mod cursor;
 pub mod display;
 pub mod event;
 pub mod payload;
@@ -2,7 +2,11 @@
 
 use raw::*;
 use std::ffi;
+use std::fs::File;
+use std::io::Write;
 use std::os::raw::c_void;
+use std::os::unix::io::{FromRawFd, IntoRawFd};
+use std::str::FromStr;
 use ChunkWMError;
 
 /// The `Payload` struct.
@@ -10,6 +14,10 @@ use ChunkWMError;
 pub struct Payload(PayloadRef);
 
 impl Payload {
+    /// Get the raw payload pointer.
+    ///
+    /// # Safety
+    /// The payload is owned by chunkwm, and is only valid while the command is being handled.
     pub unsafe fn payload_ref(&self) -> Result<PayloadRef, ChunkWMError> {
         if !self.0.is_null() {
             Ok(self.0)
@@ -39,11 +47,37 @@ impl Payload {
                 .into_owned())
         }
     }
-}
 
-impl Into<Payload> for RawPayload {
-    fn into(mut self) -> Payload {
-        Payload(&mut self)
+    /// Send a response to the client (e.g. `chunkc`) that sent the command.
+    pub fn respond<S: AsRef<str>>(&self, message: S) -> Result<(), ChunkWMError> {
+        let mut socket = unsafe { File::from_raw_fd(self.sock_fd()?) };
+        let result = socket.write_all(message.as_ref().as_bytes());
+        // The socket is owned by chunkwm, which closes it after the command has been handled.
+        let _ = socket.into_raw_fd();
+        result.map_err(|_| ChunkWMError::Internal("could not write to the socket"))
+    }
+
+    /// Respond with the reply to the command, or with the message of the error (followed by a
+    /// newline) when the command failed.
+    pub fn reply(&self, reply: Result<String, ChunkWMError>) -> Result<(), ChunkWMError> {
+        match reply {
+            Ok(reply) => self.respond(reply),
+            Err(e) => self.respond(format!("{}\n", e.message())),
+        }
+    }
+
+    /// Answer the command `command`: parse the message, run it with `run`, and `reply`. Returns
+    /// whether the payload was the command; other commands are left alone.
+    pub fn answer<C, F>(&self, command: &str, run: F) -> Result<bool, ChunkWMError>
+    where
+        C: FromStr<Err = ChunkWMError>,
+        F: FnOnce(C) -> Result<String, ChunkWMError>,
+    {
+        if self.command()? != command {
+            return Ok(false);
+        }
+        self.reply(self.message()?.parse().and_then(run))?;
+        Ok(true)
     }
 }
 
@@ -58,3 +92,32 @@ impl From<*mut c_void> for Payload {
         Payload(payload_ref as PayloadRef)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn respond_keeps_socket_open() {
+        let (daemon, mut client) = UnixStream::pair().unwrap();
+        let mut raw = RawPayload {
+            sock_fd: daemon.as_raw_fd(),
+            command: b"query\0".as_ptr() as *const _,
+            message: b"--windows\0".as_ptr() as *const _,
+        };
+        let payload = Payload::from(&mut raw as PayloadRef);
+        assert_eq!(payload.command().unwrap(), "query");
+        assert_eq!(payload.message().unwrap(), "--windows");
+
+        payload.respond("first ").unwrap();
+        payload.respond("second").unwrap();
+        drop(daemon);
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "first second");
+    }
+}
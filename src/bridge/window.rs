@@ -1,21 +1,27 @@
 //! The `window` module contains the window type and its implementations.
 
 use application::*;
-use core_foundation::base::TCFType;
-use core_foundation::string::CFString;
-use core_graphics::geometry::{CGPoint, CGSize};
+use geometry::{CGPoint, CGRect, CGSize};
 use raw::*;
 use std::ffi;
 use std::os::raw::c_void;
+use std::ptr;
 use ChunkWMError;
 
 #[cfg(feature = "accessibility")]
 use common::accessibility::element;
 #[cfg(feature = "accessibility")]
 use common::accessibility::window;
+#[cfg(target_os = "macos")]
+use core_foundation::base::TCFType;
+#[cfg(feature = "accessibility")]
+use core_foundation::boolean::CFBoolean;
+#[cfg(target_os = "macos")]
+use core_foundation::string::CFString;
 
 /// The flags on a `Window`.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum WindowFlag {
     InitMinimized,
     Movable,
@@ -59,6 +65,11 @@ impl WindowFlag {
 
         flags
     }
+
+    /// The bit of the flag in the raw flags of a window.
+    pub fn bit(self) -> u32 {
+        1 << self as u32
+    }
 }
 
 /// The `Window` struct.
@@ -80,6 +91,9 @@ impl Window {
     }
 
     /// Get the raw window pointer.
+    ///
+    /// # Safety
+    /// The pointer is owned by chunkwm, and is only valid until the window is destroyed.
     pub unsafe fn window_ref(&self) -> Result<WindowRef, ChunkWMError> {
         if !self.0.is_null() {
             Ok(self.0)
@@ -95,12 +109,12 @@ impl Window {
 
     /// Get main role.
     pub fn main_role(&self) -> Result<String, ChunkWMError> {
-        unsafe { Ok(CFString::wrap_under_get_rule((*self.window_ref()?).main_role).to_string()) }
+        unsafe { role_to_string((*self.window_ref()?).main_role) }
     }
 
     /// Get sub role.
     pub fn sub_role(&self) -> Result<String, ChunkWMError> {
-        unsafe { Ok(CFString::wrap_under_get_rule((*self.window_ref()?).sub_role).to_string()) }
+        unsafe { role_to_string((*self.window_ref()?).sub_role) }
     }
 
     /// Get owner.
@@ -124,7 +138,12 @@ impl Window {
 
     /// Get flags.
     pub fn flags(&self) -> Result<Vec<WindowFlag>, ChunkWMError> {
-        unsafe { Ok(WindowFlag::from((*self.window_ref()?).flags)) }
+        Ok(WindowFlag::from(self.raw_flags()?))
+    }
+
+    /// Get the flags as the raw bit field.
+    pub fn raw_flags(&self) -> Result<u32, ChunkWMError> {
+        unsafe { Ok(ptr::read_volatile(&(*self.window_ref()?).flags)) }
     }
 
     /// Get level.
@@ -142,6 +161,11 @@ impl Window {
         unsafe { Ok((*self.window_ref()?).size) }
     }
 
+    /// Get the frame, i.e. the position and size combined.
+    pub fn frame(&self) -> Result<CGRect, ChunkWMError> {
+        Ok(CGRect::new(&self.position()?, &self.size()?))
+    }
+
     /// Check whether window is minimized.
     /// Needed features: `accessibility`.
     #[cfg(feature = "accessibility")]
@@ -196,6 +220,44 @@ impl Window {
         }
     }
 
+    /// Minimize or deminimize the window.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn set_minimized(&self, minimized: bool) -> Result<(), ChunkWMError> {
+        let attribute = CFString::from_static_string("AXMinimized");
+        let value = if minimized {
+            CFBoolean::true_value()
+        } else {
+            CFBoolean::false_value()
+        };
+        let error = unsafe {
+            element::set_window_property(
+                self.element()?,
+                attribute.as_concrete_TypeRef(),
+                value.as_CFTypeRef(),
+            )
+        };
+        if error == 0 {
+            Ok(())
+        } else {
+            Err(ChunkWMError::Internal(
+                "could not run set_minimized successfully",
+            ))
+        }
+    }
+
+    /// Focus the window, and activate the application it belongs to.
+    /// Needed features: `accessibility`.
+    #[cfg(feature = "accessibility")]
+    pub fn focus(&self) -> Result<(), ChunkWMError> {
+        let psn = self.owner()?.process_serial_number()?;
+        unsafe {
+            element::set_focused_window(self.element()?);
+            element::set_focused_application_psn(psn);
+        }
+        Ok(())
+    }
+
     /// Close the window.
     /// Needed features: `accessibility`.
     #[cfg(feature = "accessibility")]
@@ -223,7 +285,7 @@ impl Window {
     /// Needed features: `accessibility`.
     #[cfg(feature = "accessibility")]
     pub fn add_flag(&self, flag: WindowFlag) -> Result<(), ChunkWMError> {
-        unsafe { window::add_flags(self.window_ref()?, flag as u32) };
+        unsafe { window::add_flags(self.window_ref()?, flag.bit()) };
         Ok(())
     }
 
@@ -231,18 +293,38 @@ impl Window {
     /// Needed features: `accessibility`.
     #[cfg(feature = "accessibility")]
     pub fn has_flag(&self, flag: WindowFlag) -> Result<bool, ChunkWMError> {
-        unsafe { Ok(window::has_flags(self.window_ref()?, flag as u32)) }
+        unsafe { Ok(window::has_flags(self.window_ref()?, flag.bit())) }
     }
 
     /// Remove a flag to from window.
     /// Needed features: `accessibility`.
     #[cfg(feature = "accessibility")]
     pub fn remove_flag(&self, flag: WindowFlag) -> Result<(), ChunkWMError> {
-        unsafe { window::clear_flags(self.window_ref()?, flag as u32) };
+        unsafe { window::clear_flags(self.window_ref()?, flag.bit()) };
         Ok(())
     }
 }
 
+/// Convert a role to a `String`. Not every window has a (sub) role, so the pointer may be null.
+#[cfg(target_os = "macos")]
+unsafe fn role_to_string(role: CFStringRef) -> Result<String, ChunkWMError> {
+    if role.is_null() {
+        Err(ChunkWMError::NullPointer)
+    } else {
+        Ok(CFString::wrap_under_get_rule(role).to_string())
+    }
+}
+
+/// Roles are CoreFoundation strings, which can only be read on macOS.
+#[cfg(not(target_os = "macos"))]
+unsafe fn role_to_string(role: CFStringRef) -> Result<String, ChunkWMError> {
+    if role.is_null() {
+        Err(ChunkWMError::NullPointer)
+    } else {
+        Err(ChunkWMError::Internal("roles can only be read on macOS"))
+    }
+}
+
 #[cfg(feature = "accessibility")]
 impl Clone for Window {
     fn clone(&self) -> Self {
@@ -250,13 +332,7 @@ impl Clone for Window {
     }
 }
 
-impl Into<Window> for RawWindow {
-    fn into(mut self) -> Window {
-        Window(&mut self)
-    }
-}
-
-impl<'a> From<&'a mut RawWindow> for Window {
+impl From<&mut RawWindow> for Window {
     fn from(raw_window: &mut RawWindow) -> Window {
         Window(&mut *raw_window)
     }
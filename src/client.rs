@@ -0,0 +1,397 @@
+//! The `client` module sends commands to chunkwm, like `chunkc` does.
+//!
+//! chunkc connects to the daemon on a local TCP port, sends the command and its message as one
+//! line (e.g. `tiling::window --focus east`), and reads the response until the daemon closes the
+//! connection.
+//!
+//! The `StandInDaemon` implements the daemon's side of the protocol, so that code using a
+//! `Client` can be tested without chunkwm: it records the commands it receives and returns
+//! scripted responses.
+//!
+//! ## Example
+//! ```rust,no_run
+//! use chunkwm::client::Client;
+//! use std::time::Duration;
+//!
+//! let client = Client::new().timeout(Duration::from_secs(1));
+//! client.send("tiling::window", "--focus east").unwrap();
+//! client.set("global_desktop_mode", "bsp").unwrap();
+//! let mode = client.get("global_desktop_mode").unwrap();
+//! ```
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// The port chunkwm listens on.
+pub const DEFAULT_PORT: u16 = 3920;
+
+/// The error type of the `Client`.
+#[derive(Debug)]
+pub enum ClientError {
+    /// The command is empty, or contains a character that can't be sent (a NUL or a newline).
+    InvalidCommand(&'static str),
+    /// The daemon isn't running, or can't be reached.
+    Connect(io::Error),
+    /// The daemon didn't respond in time.
+    Timeout,
+    /// Sending the command or reading the response failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ClientError::InvalidCommand(reason) => write!(f, "invalid command: {}", reason),
+            ClientError::Connect(ref e) => write!(f, "could not connect to chunkwm: {}", e),
+            ClientError::Timeout => write!(f, "chunkwm did not respond in time"),
+            ClientError::Io(ref e) => write!(f, "could not talk to chunkwm: {}", e),
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ClientError::Connect(ref e) | ClientError::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(e: io::Error) -> ClientError {
+        match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => ClientError::Timeout,
+            _ => ClientError::Io(e),
+        }
+    }
+}
+
+/// A client for the chunkwm daemon.
+#[derive(Debug, Clone)]
+pub struct Client {
+    address: SocketAddr,
+    timeout: Option<Duration>,
+}
+
+impl Default for Client {
+    fn default() -> Client {
+        Client::new()
+    }
+}
+
+impl Client {
+    /// Create a client for the daemon on the default port, without a timeout.
+    pub fn new() -> Client {
+        Client::with_port(DEFAULT_PORT)
+    }
+
+    /// Create a client for the daemon on a port on this machine.
+    pub fn with_port(port: u16) -> Client {
+        Client::with_address(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+    }
+
+    /// Create a client for the daemon on an address.
+    pub fn with_address(address: SocketAddr) -> Client {
+        Client {
+            address,
+            timeout: None,
+        }
+    }
+
+    /// Set the timeout for connecting, sending the command and reading the response.
+    pub fn timeout(mut self, timeout: Duration) -> Client {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// The address of the daemon.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Send a command with a message, e.g. `send("tiling::window", "--focus east")`, and return
+    /// the response.
+    pub fn send(&self, command: &str, message: &str) -> Result<String, ClientError> {
+        if command.is_empty() {
+            return Err(ClientError::InvalidCommand("the command is empty"));
+        }
+        if command.contains(char::is_whitespace) {
+            return Err(ClientError::InvalidCommand(
+                "the command contains whitespace",
+            ));
+        }
+        let line = if message.is_empty() {
+            command.to_owned()
+        } else {
+            format!("{} {}", command, message)
+        };
+        self.send_line(&line)
+    }
+
+    /// Send a command given as arguments, like chunkc's command line, e.g.
+    /// `send_args(&["tiling::window", "--focus", "east"])`.
+    pub fn send_args<S: AsRef<str>>(&self, args: &[S]) -> Result<String, ClientError> {
+        match args.split_first() {
+            Some((command, message)) => {
+                let message: Vec<&str> = message.iter().map(AsRef::as_ref).collect();
+                self.send(command.as_ref(), &message.join(" "))
+            }
+            None => Err(ClientError::InvalidCommand("the command is empty")),
+        }
+    }
+
+    /// Set a `CVar`, like `chunkc set <name> <value>`.
+    pub fn set<T: fmt::Display>(&self, name: &str, value: T) -> Result<(), ClientError> {
+        self.send("set", &format!("{} {}", name, value)).map(|_| ())
+    }
+
+    /// Get the value of a `CVar`, like `chunkc get <name>`.
+    pub fn get(&self, name: &str) -> Result<String, ClientError> {
+        self.send("get", name)
+            .map(|value| value.trim_end_matches('\n').to_owned())
+    }
+
+    fn send_line(&self, line: &str) -> Result<String, ClientError> {
+        if line.contains(['\0', '\n']) {
+            return Err(ClientError::InvalidCommand(
+                "the command contains a NUL or a newline",
+            ));
+        }
+
+        let mut stream = match self.timeout {
+            Some(timeout) => TcpStream::connect_timeout(&self.address, timeout),
+            None => TcpStream::connect(self.address),
+        }
+        .map_err(|e| match ClientError::from(e) {
+            ClientError::Io(e) => ClientError::Connect(e),
+            e => e,
+        })?;
+        stream.set_read_timeout(self.timeout)?;
+        stream.set_write_timeout(self.timeout)?;
+
+        stream.write_all(line.as_bytes())?;
+        stream.shutdown(Shutdown::Write)?;
+
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response)?;
+        Ok(String::from_utf8_lossy(&response).into_owned())
+    }
+}
+
+/// A command received by the `StandInDaemon`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedCommand {
+    pub command: String,
+    pub message: String,
+}
+
+#[derive(Default)]
+struct DaemonState {
+    received: Vec<ReceivedCommand>,
+    responses: HashMap<String, Vec<String>>,
+    delay: Option<Duration>,
+}
+
+/// A stand-in for the chunkwm daemon, for tests. It listens on a free port on this machine until
+/// it is dropped.
+pub struct StandInDaemon {
+    address: SocketAddr,
+    state: Arc<Mutex<DaemonState>>,
+    stopped: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl StandInDaemon {
+    /// Start the daemon.
+    pub fn start() -> io::Result<StandInDaemon> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let address = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(DaemonState::default()));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = state.clone();
+            let stopped = stopped.clone();
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if stopped.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Ok(stream) = stream {
+                        let state = state.clone();
+                        thread::spawn(move || {
+                            let _ = StandInDaemon::serve(stream, &state);
+                        });
+                    }
+                }
+            })
+        };
+
+        Ok(StandInDaemon {
+            address,
+            state,
+            stopped,
+            thread: Some(thread),
+        })
+    }
+
+    /// A client for this daemon.
+    pub fn client(&self) -> Client {
+        Client::with_address(self.address)
+    }
+
+    /// The address the daemon listens on.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Respond to the next `command` (e.g. `tiling::query`) with `response`. Responses for the
+    /// same command are used in the order they were scripted; without a scripted response the
+    /// daemon responds with nothing.
+    pub fn script(&self, command: &str, response: &str) {
+        self.lock()
+            .responses
+            .entry(command.to_owned())
+            .or_default()
+            .push(response.to_owned());
+    }
+
+    /// Wait before responding, e.g. to test timeouts.
+    pub fn set_delay(&self, delay: Option<Duration>) {
+        self.lock().delay = delay;
+    }
+
+    /// The commands the daemon received, in order.
+    pub fn received(&self) -> Vec<ReceivedCommand> {
+        self.lock().received.clone()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DaemonState> {
+        self.state.lock().unwrap()
+    }
+
+    fn serve(mut stream: TcpStream, state: &Mutex<DaemonState>) -> io::Result<()> {
+        let mut line = String::new();
+        stream.read_to_string(&mut line)?;
+        let mut parts = line.splitn(2, ' ');
+        let command = parts.next().unwrap_or("").to_owned();
+        let message = parts.next().unwrap_or("").to_owned();
+
+        let (response, delay) = {
+            let mut state = state.lock().unwrap();
+            let response = match state.responses.get_mut(&command) {
+                Some(ref mut responses) if !responses.is_empty() => responses.remove(0),
+                _ => String::new(),
+            };
+            state.received.push(ReceivedCommand { command, message });
+            (response, state.delay)
+        };
+
+        if let Some(delay) = delay {
+            thread::sleep(delay);
+        }
+        stream.write_all(response.as_bytes())
+    }
+}
+
+impl Drop for StandInDaemon {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // Wake up the listener thread.
+        let _ = TcpStream::connect(self.address);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_commands() {
+        let daemon = StandInDaemon::start().unwrap();
+        daemon.script("tiling::query", "1\n");
+        daemon.script("tiling::query", "2\n");
+        let client = daemon.client().timeout(Duration::from_secs(5));
+
+        assert_eq!(client.send("tiling::query", "--window id").unwrap(), "1\n");
+        assert_eq!(
+            client
+                .send_args(&["tiling::query", "--desktop", "id"])
+                .unwrap(),
+            "2\n"
+        );
+        assert_eq!(client.send("tiling::window", "--focus east").unwrap(), "");
+        client.set("global_desktop_mode", "bsp").unwrap();
+
+        let received = daemon.received();
+        let lines: Vec<_> = received
+            .iter()
+            .map(|r| (r.command.as_str(), r.message.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                ("tiling::query", "--window id"),
+                ("tiling::query", "--desktop id"),
+                ("tiling::window", "--focus east"),
+                ("set", "global_desktop_mode bsp"),
+            ]
+        );
+    }
+
+    #[test]
+    fn get_cvar() {
+        let daemon = StandInDaemon::start().unwrap();
+        daemon.script("get", "bsp\n");
+        assert_eq!(daemon.client().get("global_desktop_mode").unwrap(), "bsp");
+    }
+
+    #[test]
+    fn invalid_commands() {
+        let client = Client::with_port(1);
+        match client.send("", "message") {
+            Err(ClientError::InvalidCommand(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match client.send("tiling::window", "--focus\neast") {
+            Err(ClientError::InvalidCommand(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+        match client.send_args::<&str>(&[]) {
+            Err(ClientError::InvalidCommand(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        let daemon = StandInDaemon::start().unwrap();
+        daemon.set_delay(Some(Duration::from_millis(500)));
+        let client = daemon.client().timeout(Duration::from_millis(50));
+        match client.send("tiling::query", "--window id") {
+            Err(ClientError::Timeout) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn daemon_not_running() {
+        let address = StandInDaemon::start().unwrap().address();
+        // The daemon has been dropped, so nothing listens on the address any more.
+        match Client::with_address(address).send("tiling::query", "--window id") {
+            Err(ClientError::Connect(_)) => {}
+            r => panic!("unexpected result: {:?}", r),
+        }
+    }
+}
@@ -68,7 +68,7 @@ extern "C" {
     pub fn cgsspaceid_from_desktop_id(
         desktop_id: c_uint,
         out_arrangement: *mut c_uint,
-        out_desktop_id: *mut c_uint,
+        out_space_id: *mut CGSSpaceID,
     ) -> bool;
 
     #[link_name = "\u{1}_axlib_spaces_for_display_with_count"]
@@ -81,6 +81,11 @@ extern "C" {
     #[link_name = "\u{1}_axlib_spaces_for_window"]
     pub fn spaces_for_window(window_id: u32) -> *mut SpaceRef;
 
+    /// Destroy the spaces in an array returned by `spaces_for_display` or `spaces_for_window`,
+    /// and free the array.
+    #[link_name = "\u{1}_axlib_destroy_space_list"]
+    pub fn destroy_space_list(spaces: *mut SpaceRef);
+
     #[link_name = "\u{1}_axlib_space_move_window"]
     pub fn space_move_window(space_id: CGSSpaceID, window_id: u32);
 
@@ -1,125 +1,561 @@
-//! The `border` module contains a wrapper for the C methods that handle borders.
+//! The `border` module contains the borders that are drawn around windows: the `BorderBackend`
+//! trait, the `CocoaBackend` that draws them with the C methods (feature `border`), and the
+//! `RecordingBackend` that only records them, so that code drawing borders can be tested on any
+//! platform.
 
-use std::os::raw::{c_int, c_uint};
+use geometry::CGRect;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use std::str::FromStr;
 use ChunkWMError;
 
-#[repr(C)]
-#[derive(Debug)]
-struct BorderWindow {
-    pub width: c_int,
-    pub radius: c_int,
-    pub color: c_uint,
-}
-
-type BorderWindowRef = *mut BorderWindow;
-
-#[link(name = "Cocoa", kind = "framework")]
-extern "C" {
-    #[link_name = "\u{1}_create_border_window"]
-    fn create_border_window(
-        x: c_int,
-        y: c_int,
-        w: c_int,
-        h: c_int,
-        border_width: c_int,
-        border_radius: c_int,
-        border_color: c_uint,
-    ) -> BorderWindowRef;
-
-    #[link_name = "\u{1}_update_border_window_rect"]
-    fn update_border_window_rect(border: BorderWindowRef, x: c_int, y: c_int, w: c_int, h: c_int);
-    #[link_name = "\u{1}_update_border_window_color"]
-    fn update_border_window_color(border: BorderWindowRef, color: c_uint);
-    #[link_name = "\u{1}_update_border_window_width"]
-    fn update_border_window_width(border: BorderWindowRef, width: c_int);
-    #[link_name = "\u{1}_destroy_border_window"]
-    fn destroy_border_window(border: BorderWindowRef);
-}
-
-/// This struct is the wrapper for the border methods.
-pub struct Border(BorderWindowRef);
-
-impl Border {
-    /// Create a new border. The color format is 0xRRGGBBAA.
-    pub fn new(
-        x: i32,
-        y: i32,
-        w: i32,
-        h: i32,
-        border_width: i32,
-        border_radius: i32,
-        border_color: u32,
-    ) -> Self {
-        let border_window_ref =
-            unsafe { create_border_window(x, y, w, h, border_width, border_radius, border_color) };
-        Border(border_window_ref)
-    }
-
-    /// Set the border rectangle.
-    pub fn set_rect(&self, x: i32, y: i32, w: i32, h: i32) -> Result<(), ChunkWMError> {
-        if !self.0.is_null() {
-            unsafe { update_border_window_rect(self.0, x, y, w, h) }
-            Ok(())
+/// A border color. The color format is 0xRRGGBBAA.
+///
+/// Colors are parsed from hexadecimal numbers starting with `0x` (as in chunkwmrc), or from
+/// decimal numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Color(pub u32);
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "0x{:08x}", self.0)
+    }
+}
+
+impl FromStr for Color {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Color, Self::Err> {
+        let s = s.trim();
+        let value = if s.starts_with("0x") || s.starts_with("0X") {
+            u32::from_str_radix(&s[2..], 16)
         } else {
-            Err(ChunkWMError::NullPointer)
-        }
+            s.parse()
+        };
+        value
+            .map(Color)
+            .map_err(|_| "could not convert string to Color")
+    }
+}
+
+/// The look of a single border.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderStyle {
+    pub width: i32,
+    pub radius: i32,
+    pub color: Color,
+}
+
+/// A rectangle in whole points: `(x, y, width, height)`.
+pub type BorderRect = (i32, i32, i32, i32);
+
+/// The rectangle a border around `frame` is drawn at.
+pub fn border_rect(frame: &CGRect) -> BorderRect {
+    (
+        frame.origin.x.round() as i32,
+        frame.origin.y.round() as i32,
+        frame.size.width.round() as i32,
+        frame.size.height.round() as i32,
+    )
+}
+
+/// Draws borders.
+///
+/// A border is owned by its handle: `destroy` consumes the handle, and a handle that is dropped
+/// without calling `destroy` destroys its border too. A border is therefore destroyed exactly once.
+pub trait BorderBackend {
+    /// The handle of a border.
+    type Border;
+
+    /// Create a border around a frame.
+    fn create(&mut self, frame: &CGRect, style: &BorderStyle)
+        -> Result<Self::Border, ChunkWMError>;
+    /// Move and resize a border.
+    fn set_frame(&mut self, border: &mut Self::Border, frame: &CGRect) -> Result<(), ChunkWMError>;
+    /// Change the color of a border.
+    fn set_color(&mut self, border: &mut Self::Border, color: Color) -> Result<(), ChunkWMError>;
+    /// Change the width of a border.
+    fn set_width(&mut self, border: &mut Self::Border, width: i32) -> Result<(), ChunkWMError>;
+    /// Destroy a border.
+    fn destroy(&mut self, border: Self::Border);
+}
+
+#[cfg(feature = "border")]
+pub use self::cocoa::*;
+
+#[cfg(feature = "border")]
+mod cocoa {
+    use super::*;
+    use std::os::raw::{c_int, c_uint};
+    use std::ptr;
+
+    #[repr(C)]
+    #[derive(Debug)]
+    struct BorderWindow {
+        pub width: c_int,
+        pub radius: c_int,
+        pub color: c_uint,
     }
 
-    /// Set the border color. The color format is 0xRRGGBBAA.
-    ///
-    /// # Warning: (maybe) do not use (yet).
-    /// TODO(splintah): (signal: 11, SIGSEGV: invalid memory reference)...
-    /// NOTE(splintah): `BorderInternal->View` often becomes 562949953421312, which happens to be
-    /// 2^49.
-    pub fn set_color(&self, color: u32) -> Result<(), ChunkWMError> {
-        if !self.0.is_null() {
-            unsafe { update_border_window_color(self.0, color) }
+    type BorderWindowRef = *mut BorderWindow;
+
+    #[link(name = "Cocoa", kind = "framework")]
+    extern "C" {
+        #[link_name = "\u{1}_create_border_window"]
+        fn create_border_window(
+            x: c_int,
+            y: c_int,
+            w: c_int,
+            h: c_int,
+            border_width: c_int,
+            border_radius: c_int,
+            border_color: c_uint,
+        ) -> BorderWindowRef;
+
+        #[link_name = "\u{1}_update_border_window_rect"]
+        fn update_border_window_rect(
+            border: BorderWindowRef,
+            x: c_int,
+            y: c_int,
+            w: c_int,
+            h: c_int,
+        );
+        #[link_name = "\u{1}_update_border_window_color"]
+        fn update_border_window_color(border: BorderWindowRef, color: c_uint);
+        #[link_name = "\u{1}_update_border_window_width"]
+        fn update_border_window_width(border: BorderWindowRef, width: c_int);
+        #[link_name = "\u{1}_destroy_border_window"]
+        fn destroy_border_window(border: BorderWindowRef);
+    }
+
+    /// This struct is the wrapper for the border methods. It owns the border window, which is
+    /// destroyed by `destroy` or when the `Border` is dropped.
+    /// Needed features: `border`.
+    pub struct Border(BorderWindowRef);
+
+    impl Border {
+        /// Create a new border. The color format is 0xRRGGBBAA.
+        pub fn new(
+            x: i32,
+            y: i32,
+            w: i32,
+            h: i32,
+            border_width: i32,
+            border_radius: i32,
+            border_color: u32,
+        ) -> Self {
+            let border_window_ref = unsafe {
+                create_border_window(x, y, w, h, border_width, border_radius, border_color)
+            };
+            Border(border_window_ref)
+        }
+
+        fn border_window_ref(&self) -> Result<BorderWindowRef, ChunkWMError> {
+            if !self.0.is_null() {
+                Ok(self.0)
+            } else {
+                Err(ChunkWMError::NullPointer)
+            }
+        }
+
+        /// Set the border rectangle.
+        pub fn set_rect(&self, x: i32, y: i32, w: i32, h: i32) -> Result<(), ChunkWMError> {
+            unsafe { update_border_window_rect(self.border_window_ref()?, x, y, w, h) }
             Ok(())
-        } else {
-            Err(ChunkWMError::NullPointer)
         }
+
+        /// Set the border color. The color format is 0xRRGGBBAA.
+        ///
+        /// # Warning: (maybe) do not use (yet).
+        /// TODO(splintah): (signal: 11, SIGSEGV: invalid memory reference)...
+        /// NOTE(splintah): `BorderInternal->View` often becomes 562949953421312, which happens to
+        /// be 2^49.
+        pub fn set_color(&self, color: u32) -> Result<(), ChunkWMError> {
+            unsafe { update_border_window_color(self.border_window_ref()?, color) }
+            Ok(())
+        }
+
+        /// Set the border width.
+        ///
+        /// # Warning: (maybe) do not use (yet).
+        /// TODO(splintah): (signal: 11, SIGSEGV: invalid memory reference)...
+        /// NOTE(splintah): `BorderInternal->View` often becomes 562949953421312, which happens to
+        /// be 2^49.
+        pub fn set_width(&self, width: i32) -> Result<(), ChunkWMError> {
+            unsafe { update_border_window_width(self.border_window_ref()?, width) }
+            Ok(())
+        }
+
+        /// Destroy the border.
+        pub fn destroy(mut self) {
+            self.release();
+        }
+
+        fn release(&mut self) {
+            if !self.0.is_null() {
+                unsafe { destroy_border_window(self.0) }
+                self.0 = ptr::null_mut();
+            }
+        }
+    }
+
+    // The border window is only used through the `Border` that owns it, and chunkwm's border
+    // functions do their Cocoa work on the main thread, so a `Border` can be moved to another
+    // thread. This makes `BorderManager<CocoaBackend>` `Send`, as a plugin must be.
+    unsafe impl Send for Border {}
+
+    impl Drop for Border {
+        fn drop(&mut self) {
+            self.release();
+        }
+    }
+
+    /// Draws borders with `Border`.
+    /// Needed features: `border`.
+    #[derive(Debug, Default)]
+    pub struct CocoaBackend;
+
+    /// A border drawn by the `CocoaBackend`. It remembers its frame and style, so that it can be
+    /// recreated with a new color or width.
+    /// Needed features: `border`.
+    pub struct CocoaBorder {
+        border: Border,
+        frame: CGRect,
+        style: BorderStyle,
     }
 
-    /// Set the border width.
-    ///
-    /// # Warning: (maybe) do not use (yet).
-    /// TODO(splintah): (signal: 11, SIGSEGV: invalid memory reference)...
-    /// NOTE(splintah): `BorderInternal->View` often becomes 562949953421312, which happens to be
-    /// 2^49.
-    pub fn set_width(&self, width: i32) -> Result<(), ChunkWMError> {
-        if !self.0.is_null() {
-            unsafe { update_border_window_width(self.0, width) }
+    impl CocoaBorder {
+        fn new(frame: &CGRect, style: &BorderStyle) -> Result<CocoaBorder, ChunkWMError> {
+            let (x, y, w, h) = border_rect(frame);
+            let border = Border::new(x, y, w, h, style.width, style.radius, style.color.0);
+            border.border_window_ref()?;
+            Ok(CocoaBorder {
+                border,
+                frame: *frame,
+                style: *style,
+            })
+        }
+
+        /// Replace the border with a new one in the current style. The old border is destroyed
+        /// when it is dropped.
+        fn recreate(&mut self) -> Result<(), ChunkWMError> {
+            *self = CocoaBorder::new(&self.frame, &self.style)?;
             Ok(())
-        } else {
-            Err(ChunkWMError::NullPointer)
         }
     }
 
-    /// Destroy the border.
-    pub fn destroy(&self) {
-        if !self.0.is_null() {
-            unsafe { destroy_border_window(self.0) }
+    impl CocoaBackend {
+        pub fn new() -> CocoaBackend {
+            CocoaBackend
+        }
+    }
+
+    impl BorderBackend for CocoaBackend {
+        type Border = CocoaBorder;
+
+        fn create(
+            &mut self,
+            frame: &CGRect,
+            style: &BorderStyle,
+        ) -> Result<CocoaBorder, ChunkWMError> {
+            CocoaBorder::new(frame, style)
+        }
+
+        fn set_frame(
+            &mut self,
+            border: &mut CocoaBorder,
+            frame: &CGRect,
+        ) -> Result<(), ChunkWMError> {
+            let (x, y, w, h) = border_rect(frame);
+            border.border.set_rect(x, y, w, h)?;
+            border.frame = *frame;
+            Ok(())
+        }
+
+        // `Border::set_color` and `Border::set_width` are not safe to use yet, so the border is
+        // recreated instead.
+
+        fn set_color(
+            &mut self,
+            border: &mut CocoaBorder,
+            color: Color,
+        ) -> Result<(), ChunkWMError> {
+            border.style.color = color;
+            border.recreate()
+        }
+
+        fn set_width(&mut self, border: &mut CocoaBorder, width: i32) -> Result<(), ChunkWMError> {
+            border.style.width = width;
+            border.recreate()
         }
+
+        fn destroy(&mut self, border: CocoaBorder) {
+            border.border.destroy();
+        }
+    }
+}
+
+/// An operation recorded by the `RecordingBackend`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BorderOp {
+    Create {
+        id: u32,
+        rect: BorderRect,
+        style: BorderStyle,
+    },
+    SetRect {
+        id: u32,
+        rect: BorderRect,
+    },
+    SetColor {
+        id: u32,
+        color: Color,
+    },
+    SetWidth {
+        id: u32,
+        width: i32,
+    },
+    Destroy {
+        id: u32,
+    },
+}
+
+#[derive(Debug, Default)]
+struct Recording {
+    next: u32,
+    borders: BTreeMap<u32, (BorderRect, BorderStyle)>,
+    ops: Vec<BorderOp>,
+}
+
+impl Recording {
+    fn border(&mut self, id: u32) -> Result<&mut (BorderRect, BorderStyle), ChunkWMError> {
+        self.borders
+            .get_mut(&id)
+            .ok_or(ChunkWMError::Internal("border was already destroyed"))
+    }
+}
+
+/// A border created by the `RecordingBackend`.
+#[derive(Debug)]
+pub struct RecordedBorder {
+    id: u32,
+    recording: Rc<RefCell<Recording>>,
+}
+
+impl RecordedBorder {
+    /// The id of the border, as used in the `BorderOp`s.
+    pub fn id(&self) -> u32 {
+        self.id
     }
 }
 
-impl Drop for Border {
+impl Drop for RecordedBorder {
     fn drop(&mut self) {
-        if !self.0.is_null() {
-            unsafe { destroy_border_window(self.0) }
-        }
+        let mut recording = self.recording.borrow_mut();
+        assert!(
+            recording.borders.remove(&self.id).is_some(),
+            "border {} was destroyed twice",
+            self.id
+        );
+        recording.ops.push(BorderOp::Destroy { id: self.id });
+    }
+}
+
+/// A backend that keeps the borders in memory, and records every operation. Clones share the
+/// recording, so a clone can be kept to inspect a backend that was moved into e.g. a
+/// `BorderManager`.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingBackend {
+    recording: Rc<RefCell<Recording>>,
+}
+
+impl RecordingBackend {
+    pub fn new() -> RecordingBackend {
+        RecordingBackend::default()
+    }
+
+    /// Get the recorded operations.
+    pub fn ops(&self) -> Vec<BorderOp> {
+        self.recording.borrow().ops.clone()
+    }
+
+    /// Get the recorded operations, and clear them.
+    pub fn take_ops(&self) -> Vec<BorderOp> {
+        ::std::mem::take(&mut self.recording.borrow_mut().ops)
+    }
+
+    /// Get the ids of the borders that have not been destroyed.
+    pub fn live(&self) -> Vec<u32> {
+        self.recording.borrow().borders.keys().cloned().collect()
+    }
+
+    /// Get the rectangle and style of a border that has not been destroyed.
+    pub fn border(&self, id: u32) -> Option<(BorderRect, BorderStyle)> {
+        self.recording.borrow().borders.get(&id).cloned()
+    }
+}
+
+impl BorderBackend for RecordingBackend {
+    type Border = RecordedBorder;
+
+    fn create(
+        &mut self,
+        frame: &CGRect,
+        style: &BorderStyle,
+    ) -> Result<RecordedBorder, ChunkWMError> {
+        let mut recording = self.recording.borrow_mut();
+        recording.next += 1;
+        let id = recording.next;
+        let rect = border_rect(frame);
+        recording.borders.insert(id, (rect, *style));
+        recording.ops.push(BorderOp::Create {
+            id,
+            rect,
+            style: *style,
+        });
+        Ok(RecordedBorder {
+            id,
+            recording: self.recording.clone(),
+        })
+    }
+
+    fn set_frame(
+        &mut self,
+        border: &mut RecordedBorder,
+        frame: &CGRect,
+    ) -> Result<(), ChunkWMError> {
+        let mut recording = self.recording.borrow_mut();
+        let rect = border_rect(frame);
+        recording.border(border.id)?.0 = rect;
+        recording.ops.push(BorderOp::SetRect {
+            id: border.id,
+            rect,
+        });
+        Ok(())
+    }
+
+    fn set_color(&mut self, border: &mut RecordedBorder, color: Color) -> Result<(), ChunkWMError> {
+        let mut recording = self.recording.borrow_mut();
+        recording.border(border.id)?.1.color = color;
+        recording.ops.push(BorderOp::SetColor {
+            id: border.id,
+            color,
+        });
+        Ok(())
+    }
+
+    fn set_width(&mut self, border: &mut RecordedBorder, width: i32) -> Result<(), ChunkWMError> {
+        let mut recording = self.recording.borrow_mut();
+        recording.border(border.id)?.1.width = width;
+        recording.ops.push(BorderOp::SetWidth {
+            id: border.id,
+            width,
+        });
+        Ok(())
+    }
+
+    fn destroy(&mut self, border: RecordedBorder) {
+        drop(border);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use testing::rect;
+
+    const STYLE: BorderStyle = BorderStyle {
+        width: 5,
+        radius: 5,
+        color: Color(0xff00_00ff),
+    };
+
+    #[test]
+    fn recorded_border_operations() {
+        let mut backend = RecordingBackend::new();
+        let mut border = backend
+            .create(&rect(0.0, 0.0, 100.0, 100.0), &STYLE)
+            .unwrap();
+        let id = border.id();
+        backend
+            .set_frame(&mut border, &rect(100.4, 99.6, 100.0, 100.0))
+            .unwrap();
+        backend.set_color(&mut border, Color(0x00ff_00ff)).unwrap();
+        backend.set_width(&mut border, 10).unwrap();
+        assert_eq!(
+            backend.border(id),
+            Some((
+                (100, 100, 100, 100),
+                BorderStyle {
+                    width: 10,
+                    radius: 5,
+                    color: Color(0x00ff_00ff),
+                }
+            ))
+        );
+
+        backend.destroy(border);
+        assert_eq!(
+            backend.take_ops(),
+            vec![
+                BorderOp::Create {
+                    id,
+                    rect: (0, 0, 100, 100),
+                    style: STYLE,
+                },
+                BorderOp::SetRect {
+                    id,
+                    rect: (100, 100, 100, 100),
+                },
+                BorderOp::SetColor {
+                    id,
+                    color: Color(0x00ff_00ff),
+                },
+                BorderOp::SetWidth { id, width: 10 },
+                BorderOp::Destroy { id },
+            ]
+        );
+        assert!(backend.live().is_empty());
+    }
 
+    #[test]
+    fn dropped_borders_are_destroyed_once() {
+        let recording = RecordingBackend::new();
+        let mut backend = recording.clone();
+        let a = backend.create(&rect(0.0, 0.0, 10.0, 10.0), &STYLE).unwrap();
+        let b = backend.create(&rect(0.0, 0.0, 10.0, 10.0), &STYLE).unwrap();
+        assert_eq!(recording.live(), vec![1, 2]);
+
+        drop(a);
+        backend.destroy(b);
+        assert!(recording.live().is_empty());
+        assert_eq!(
+            recording
+                .ops()
+                .into_iter()
+                .filter(|op| matches!(*op, BorderOp::Destroy { .. }))
+                .collect::<Vec<_>>(),
+            vec![BorderOp::Destroy { id: 1 }, BorderOp::Destroy { id: 2 }]
+        );
+    }
+
+    #[test]
+    fn parse_color() {
+        assert_eq!("0xFF00FF00".parse(), Ok(Color(0xff00_ff00)));
+        assert_eq!("255".parse(), Ok(Color(255)));
+        assert!("0xzz".parse::<Color>().is_err());
+        assert_eq!(Color(0xff).to_string(), "0x000000ff");
+    }
+
+    #[cfg(feature = "border")]
     #[test]
     fn create_border() {
         let _ = Border::new(0, 0, 100, 100, 5, 5, 0xFF0000FF);
     }
 
+    #[cfg(feature = "border")]
     #[test]
     fn update_border_rect() {
         let border = Border::new(0, 0, 100, 100, 5, 5, 0xFF0000FF);
@@ -138,9 +574,25 @@ mod tests {
     //     assert!(border.set_width(10).is_ok())
     // }
 
+    #[cfg(feature = "border")]
     #[test]
     fn destroy_border() {
         let border = Border::new(0, 0, 100, 100, 5, 5, 0xFF0000FF);
         border.destroy();
     }
+
+    #[cfg(feature = "border")]
+    #[test]
+    fn cocoa_backend_recreates_borders() {
+        let mut backend = CocoaBackend::new();
+        let mut border = backend
+            .create(&rect(0.0, 0.0, 100.0, 100.0), &STYLE)
+            .unwrap();
+        assert!(backend
+            .set_frame(&mut border, &rect(100.0, 100.0, 100.0, 100.0))
+            .is_ok());
+        assert!(backend.set_color(&mut border, Color(0x00FF00FF)).is_ok());
+        assert!(backend.set_width(&mut border, 10).is_ok());
+        backend.destroy(border);
+    }
 }
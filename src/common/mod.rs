@@ -6,5 +6,4 @@
 
 #[cfg(feature = "accessibility")]
 pub mod accessibility;
-#[cfg(feature = "border")]
 pub mod border;
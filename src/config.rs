@@ -0,0 +1,666 @@
+//! The `config` module loads plugin settings from a configuration file, merges them with `CVar`
+//! overrides, and reloads them when the file changes or on a `reload` daemon command.
+//!
+//! The configuration file uses a simple `key = value` format:
+//!
+//! ```text
+//! # Comments start with a '#'.
+//! gap = 10
+//! focused_color = 0xffd5c4a1   # trailing comments are allowed after unquoted values
+//! title = "a quoted value, # not a comment"
+//! ```
+//!
+//! A value is looked up in this order: the `CVar` `<prefix><key>` (e.g. set from chunkwmrc with
+//! `chunkc set plugin_gap 20`), the configuration file, and finally the default value. Errors are
+//! logged with `API::log`, including the line number in the file.
+//!
+//! ## Example
+//! ```rust
+//! # #[macro_use] extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::config::{Config, ConfigValues, FromConfig};
+//!
+//! #[derive(Default)]
+//! struct Settings {
+//!     gap: u32,
+//!     focused_color: String,
+//! }
+//!
+//! impl FromConfig for Settings {
+//!     fn from_config(values: &ConfigValues) -> Settings {
+//!         Settings {
+//!             gap: values.get("gap", 10),
+//!             focused_color: values.get("focused_color", "0xffd5c4a1".to_owned()),
+//!         }
+//!     }
+//! }
+//!
+//! pub struct Plugin {
+//!     api: API,
+//!     config: Config<Settings>,
+//! }
+//!
+//! impl HandleEvent for Plugin {
+//!     fn new(api: API) -> Plugin {
+//!         let config = Config::load("/usr/local/etc/plugin.conf", "plugin_", &api);
+//!         Plugin { api, config }
+//!     }
+//!
+//!     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+//!         if self.config.handle(&event, &self.api)? {
+//!             return Ok(());
+//!         }
+//!         self.config.reload_if_changed(&self.api);
+//!         let _gap = self.config.settings().gap;
+//!         Ok(())
+//!     }
+//!     // some methods omitted
+//!     # subscribe_all!();
+//!     # fn shutdown(&self) {}
+//! }
+//! # fn main() {}
+//! ```
+
+use api::{LogLevel, API};
+use event::Event;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+use ChunkWMError;
+
+/// An error in the configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    /// The line in the configuration file, if the error comes from the file.
+    pub line: Option<usize>,
+    /// What went wrong.
+    pub message: String,
+}
+
+impl ConfigError {
+    fn new<S: Into<String>>(line: Option<usize>, message: S) -> ConfigError {
+        ConfigError {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// A value in the configuration file.
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    value: String,
+    line: usize,
+}
+
+/// The parsed contents of a configuration file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigFile {
+    entries: BTreeMap<String, Entry>,
+}
+
+impl ConfigFile {
+    /// Parse a configuration file. Invalid lines are skipped and reported.
+    pub fn parse(source: &str) -> (ConfigFile, Vec<ConfigError>) {
+        let mut file = ConfigFile::default();
+        let mut errors = Vec::new();
+
+        for (i, line) in source.lines().enumerate() {
+            let number = i + 1;
+            match parse_line(line) {
+                Ok(Some((key, value))) => {
+                    if let Some(previous) = file.entries.get(&key) {
+                        errors.push(ConfigError::new(
+                            Some(number),
+                            format!("`{}` was already set on line {}", key, previous.line),
+                        ));
+                    }
+                    file.entries.insert(
+                        key,
+                        Entry {
+                            value,
+                            line: number,
+                        },
+                    );
+                }
+                Ok(None) => {}
+                Err(message) => errors.push(ConfigError::new(Some(number), message)),
+            }
+        }
+
+        (file, errors)
+    }
+
+    /// Get the raw value of a key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.get(key).map(|entry| entry.value.as_str())
+    }
+
+    /// Get the line a key is set on.
+    pub fn line(&self, key: &str) -> Option<usize> {
+        self.entries.get(key).map(|entry| entry.line)
+    }
+
+    /// Get all keys in the file.
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|key| key.as_str())
+    }
+}
+
+fn parse_line(line: &str) -> Result<Option<(String, String)>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+
+    let equals = match line.find('=') {
+        Some(i) => i,
+        None => return Err("expected `key = value`".to_owned()),
+    };
+    let key = line[..equals].trim();
+    if key.is_empty() {
+        return Err("missing key before `=`".to_owned());
+    }
+    if !key
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+    {
+        return Err(format!("invalid key `{}`", key));
+    }
+
+    let value = parse_value(line[equals + 1..].trim())?;
+    Ok(Some((key.to_owned(), value)))
+}
+
+fn parse_value(value: &str) -> Result<String, String> {
+    if !value.starts_with('"') {
+        // Unquoted values end at a comment, which starts with a `#` after whitespace.
+        let end = value
+            .char_indices()
+            .find(|&(i, c)| c == '#' && value[..i].ends_with(char::is_whitespace))
+            .map_or(value.len(), |(i, _)| i);
+        return Ok(value[..end].trim().to_owned());
+    }
+
+    let mut result = String::new();
+    let mut chars = value[1..].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let rest = chars.as_str().trim();
+                if rest.is_empty() || rest.starts_with('#') {
+                    return Ok(result);
+                }
+                return Err(format!("unexpected `{}` after quoted value", rest));
+            }
+            '\\' => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some(c @ '"') | Some(c @ '\\') => result.push(c),
+                Some(c) => return Err(format!("unknown escape `\\{}`", c)),
+                None => break,
+            },
+            c => result.push(c),
+        }
+    }
+
+    Err("unterminated quoted value".to_owned())
+}
+
+/// The values settings are read from: `CVar` overrides, the configuration file and defaults.
+/// Values that cannot be parsed are reported, and the default is used instead.
+pub struct ConfigValues<'a> {
+    file: &'a ConfigFile,
+    prefix: &'a str,
+    cvar: &'a dyn Fn(&str) -> Option<String>,
+    used: RefCell<BTreeSet<String>>,
+    errors: RefCell<Vec<ConfigError>>,
+}
+
+impl<'a> ConfigValues<'a> {
+    /// Create the values from a configuration file and a `CVar` lookup function. The `CVar` for a
+    /// key is named `<prefix><key>`.
+    pub fn new(
+        file: &'a ConfigFile,
+        prefix: &'a str,
+        cvar: &'a dyn Fn(&str) -> Option<String>,
+    ) -> ConfigValues<'a> {
+        ConfigValues {
+            file,
+            prefix,
+            cvar,
+            used: RefCell::new(BTreeSet::new()),
+            errors: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Get a value, or the default when it is not set or cannot be parsed.
+    pub fn get<T: FromStr>(&self, key: &str, default: T) -> T {
+        self.try_get(key).unwrap_or(default)
+    }
+
+    /// Get a value, or `None` when it is not set or cannot be parsed.
+    pub fn try_get<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.used.borrow_mut().insert(key.to_owned());
+
+        let name = format!("{}{}", self.prefix, key);
+        if let Some(value) = (self.cvar)(&name) {
+            match value.parse() {
+                Ok(value) => return Some(value),
+                Err(_) => self.error(
+                    None,
+                    format!("invalid value `{}` for CVar `{}`", value, name),
+                ),
+            }
+        }
+
+        let value = self.file.get(key)?;
+        match value.parse() {
+            Ok(value) => Some(value),
+            Err(_) => {
+                self.error(
+                    self.file.line(key),
+                    format!("invalid value `{}` for `{}`", value, key),
+                );
+                None
+            }
+        }
+    }
+
+    fn error(&self, line: Option<usize>, message: String) {
+        self.errors
+            .borrow_mut()
+            .push(ConfigError::new(line, message));
+    }
+
+    /// Get the errors, including keys in the file that were never read.
+    fn into_errors(self) -> Vec<ConfigError> {
+        let used = self.used.into_inner();
+        let mut errors = self.errors.into_inner();
+        for key in self.file.keys().filter(|key| !used.contains(*key)) {
+            errors.push(ConfigError::new(
+                self.file.line(key),
+                format!("unknown key `{}`", key),
+            ));
+        }
+        errors
+    }
+}
+
+/// Implement this on the settings of your plugin.
+pub trait FromConfig {
+    /// Read the settings from the values.
+    fn from_config(values: &ConfigValues) -> Self;
+}
+
+/// Read settings from a parsed configuration file. Returns the settings and all errors.
+pub fn read_settings<T: FromConfig>(
+    file: &ConfigFile,
+    prefix: &str,
+    cvar: &dyn Fn(&str) -> Option<String>,
+) -> (T, Vec<ConfigError>) {
+    let values = ConfigValues::new(file, prefix, cvar);
+    let settings = T::from_config(&values);
+    (settings, values.into_errors())
+}
+
+/// The `Config` loads settings from a file, and reloads them when needed.
+pub struct Config<T> {
+    path: PathBuf,
+    prefix: String,
+    command: &'static str,
+    modified: Option<SystemTime>,
+    settings: T,
+}
+
+impl<T: FromConfig> Config<T> {
+    /// Load the settings from a file. A missing file is not an error: the settings are then read
+    /// from the `CVar`s and defaults. `prefix` is prepended to a key to get the `CVar` name.
+    pub fn load<P: Into<PathBuf>>(path: P, prefix: &str, api: &API) -> Config<T> {
+        let path = path.into();
+        let (settings, modified) = load_file(&path, prefix, &cvar_lookup(api), &mut |message| {
+            api.log(LogLevel::Error, message)
+        });
+
+        Config {
+            path,
+            prefix: prefix.to_owned(),
+            command: "reload",
+            modified,
+            settings,
+        }
+    }
+
+    /// Reload on a different daemon command than `reload`.
+    pub fn with_command(mut self, command: &'static str) -> Config<T> {
+        self.command = command;
+        self
+    }
+
+    /// Get the settings.
+    pub fn settings(&self) -> &T {
+        &self.settings
+    }
+
+    /// Get the path of the configuration file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reload the settings.
+    pub fn reload(&mut self, api: &API) {
+        self.reload_logging(api, &mut |message| api.log(LogLevel::Error, message));
+    }
+
+    fn reload_logging(&mut self, api: &API, log: &mut dyn FnMut(String)) {
+        let (settings, modified) = load_file(&self.path, &self.prefix, &cvar_lookup(api), log);
+        self.settings = settings;
+        self.modified = modified;
+    }
+
+    /// Reload the settings if the modification time of the file changed since it was loaded.
+    /// Returns whether the settings were reloaded.
+    pub fn reload_if_changed(&mut self, api: &API) -> bool {
+        if modified(&self.path) == self.modified {
+            return false;
+        }
+        self.reload(api);
+        true
+    }
+
+    /// Reload the settings on the reload daemon command, and reply with the errors in the
+    /// configuration, or that it was reloaded. Returns `Ok(false)` when the event is not the
+    /// reload command.
+    pub fn handle(&mut self, event: &Event, api: &API) -> Result<bool, ChunkWMError> {
+        match *event {
+            Event::DaemonCommand(ref payload) if payload.command()? == self.command => {
+                let mut errors = Vec::new();
+                self.reload_logging(api, &mut |message| {
+                    api.log(LogLevel::Error, message.as_str());
+                    errors.push(message);
+                });
+                let reply = if errors.is_empty() {
+                    format!("reloaded {}\n", self.path.display())
+                } else {
+                    errors.join("\n") + "\n"
+                };
+                payload.respond(reply)?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+pub(crate) fn cvar_lookup(api: &API) -> impl Fn(&str) -> Option<String> + '_ {
+    move |name: &str| {
+        if api.cvar_exists(name) {
+            api.get_cvar::<String>(name).ok()
+        } else {
+            None
+        }
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Read a configuration file and the settings in it, and log every error.
+fn load_file<T: FromConfig>(
+    path: &Path,
+    prefix: &str,
+    cvar: &dyn Fn(&str) -> Option<String>,
+    log: &mut dyn FnMut(String),
+) -> (T, Option<SystemTime>) {
+    let modified = modified(path);
+    let (file, mut errors) = match fs::read_to_string(path) {
+        Ok(source) => ConfigFile::parse(&source),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => (ConfigFile::default(), Vec::new()),
+        Err(e) => (
+            ConfigFile::default(),
+            vec![ConfigError::new(
+                None,
+                format!("could not read file: {}", e),
+            )],
+        ),
+    };
+
+    let (settings, setting_errors) = read_settings(&file, prefix, cvar);
+    errors.extend(setting_errors);
+    for error in errors {
+        log(format!("{}: {}", path.display(), error));
+    }
+
+    (settings, modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use payload::Payload;
+    use raw::{PayloadRef, RawPayload};
+    use simulator::Simulator;
+    use std::env;
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+    use std::os::unix::net::UnixStream;
+    use std::process;
+    use std::time::Duration;
+
+    #[derive(Debug, PartialEq)]
+    struct Settings {
+        gap: u32,
+        color: String,
+        enabled: bool,
+    }
+
+    impl FromConfig for Settings {
+        fn from_config(values: &ConfigValues) -> Settings {
+            Settings {
+                gap: values.get("gap", 10),
+                color: values.get("color", "red".to_owned()),
+                enabled: values.get("enabled", false),
+            }
+        }
+    }
+
+    fn no_cvars(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn parse_file() {
+        let (file, errors) = ConfigFile::parse(
+            "# comment\n\
+             \n\
+             gap = 20 # pixels\n\
+             color = \"blue # not a comment\"\n\
+             escaped=\"a \\\"b\\\"\"\n\
+             enabled = true\t# tab\n\
+             tag = a#b\n",
+        );
+        assert!(errors.is_empty());
+        assert_eq!(file.get("gap"), Some("20"));
+        assert_eq!(file.get("color"), Some("blue # not a comment"));
+        assert_eq!(file.get("escaped"), Some("a \"b\""));
+        assert_eq!(file.get("enabled"), Some("true"));
+        assert_eq!(file.get("tag"), Some("a#b"));
+        assert_eq!(file.line("gap"), Some(3));
+    }
+
+    #[test]
+    fn parse_errors_have_line_numbers() {
+        let (file, errors) = ConfigFile::parse(
+            "gap = 1\n\
+             no equals sign\n\
+             = 3\n\
+             bad key = 4\n\
+             color = \"unterminated\n\
+             gap = 2\n",
+        );
+        let lines: Vec<Option<usize>> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![Some(2), Some(3), Some(4), Some(5), Some(6)]);
+        assert_eq!(
+            errors[4].to_string(),
+            "line 6: `gap` was already set on line 1"
+        );
+        // The last value wins.
+        assert_eq!(file.get("gap"), Some("2"));
+    }
+
+    #[test]
+    fn defaults_file_and_cvars() {
+        let (file, _) = ConfigFile::parse("gap = 20\ncolor = blue\n");
+        let cvar = |name: &str| match name {
+            "plugin_color" => Some("green".to_owned()),
+            _ => None,
+        };
+        let (settings, errors) = read_settings::<Settings>(&file, "plugin_", &cvar);
+        assert!(errors.is_empty());
+        assert_eq!(
+            settings,
+            Settings {
+                gap: 20,
+                color: "green".to_owned(),
+                enabled: false,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_and_unknown_values() {
+        let (file, _) = ConfigFile::parse("gap = wide\nenabled = true\ncolour = blue\n");
+        let (settings, errors) = read_settings::<Settings>(&file, "plugin_", &no_cvars);
+        assert_eq!(settings.gap, 10);
+        assert!(settings.enabled);
+        assert_eq!(
+            errors,
+            vec![
+                ConfigError::new(Some(1), "invalid value `wide` for `gap`"),
+                ConfigError::new(Some(3), "unknown key `colour`"),
+            ]
+        );
+    }
+
+    #[test]
+    fn load_and_log() {
+        let path = env::temp_dir().join(format!("chunkwm-config-test-{}.conf", process::id()));
+        fs::write(&path, "gap = 5\ngap2 = 6\n").unwrap();
+
+        let mut logged = Vec::new();
+        let (settings, modified) =
+            load_file::<Settings>(&path, "plugin_", &no_cvars, &mut |m| logged.push(m));
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(settings.gap, 5);
+        assert!(modified.is_some());
+        assert_eq!(
+            logged,
+            vec![format!("{}: line 2: unknown key `gap2`", path.display())]
+        );
+
+        // A missing file is not an error.
+        let mut logged = Vec::new();
+        let (settings, modified) =
+            load_file::<Settings>(&path, "plugin_", &no_cvars, &mut |m| logged.push(m));
+        assert_eq!(settings.gap, 10);
+        assert!(modified.is_none());
+        assert!(logged.is_empty());
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("chunkwm-config-{}-{}.conf", name, process::id()))
+    }
+
+    /// Rewrite a configuration file, with a modification time of `time` seconds after the epoch,
+    /// so that the change is seen even when the file was written in the same instant.
+    fn rewrite(path: &Path, source: &str, time: u64) {
+        fs::write(path, source).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(time))
+            .unwrap();
+    }
+
+    #[test]
+    fn reload_when_changed() {
+        let simulator = Simulator::new();
+        let api = simulator.api();
+        let path = temp_file("changed");
+        fs::write(&path, "gap = 5\n").unwrap();
+        let mut config = Config::<Settings>::load(&path, "plugin_", &api);
+        assert_eq!(config.settings().gap, 5);
+        assert!(!config.reload_if_changed(&api));
+
+        rewrite(&path, "gap = 6\ncolor = blue\n", 1);
+        assert!(config.reload_if_changed(&api));
+        assert_eq!(config.settings().gap, 6);
+        assert!(!config.reload_if_changed(&api));
+
+        // `CVar`s still take precedence over the reloaded file.
+        simulator.set_cvar("plugin_gap", 20);
+        rewrite(&path, "gap = 7\ncolor = green\n", 2);
+        assert!(config.reload_if_changed(&api));
+        assert_eq!(config.settings().gap, 20);
+        assert_eq!(config.settings().color, "green");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reload_command() {
+        let simulator = Simulator::new();
+        let api = simulator.api();
+        let path = temp_file("command");
+        fs::write(&path, "gap = 5\n").unwrap();
+        let mut config = Config::<Settings>::load(&path, "plugin_", &api);
+        fs::write(&path, "gap = 6\n").unwrap();
+
+        let (daemon, mut client) = UnixStream::pair().unwrap();
+        let mut raw = RawPayload {
+            sock_fd: daemon.as_raw_fd(),
+            command: b"query\0".as_ptr() as *const _,
+            message: b"\0".as_ptr() as *const _,
+        };
+        let event = Event::DaemonCommand(Payload::from(&mut raw as PayloadRef));
+        assert!(!config.handle(&event, &api).unwrap());
+        assert_eq!(config.settings().gap, 5);
+
+        raw.command = b"reload\0".as_ptr() as *const _;
+        let event = Event::DaemonCommand(Payload::from(&mut raw as PayloadRef));
+        assert!(config.handle(&event, &api).unwrap());
+        assert_eq!(config.settings().gap, 6);
+
+        fs::write(&path, "gap = wide\n").unwrap();
+        assert!(config.handle(&event, &api).unwrap());
+        assert_eq!(config.settings().gap, 10);
+        drop(daemon);
+        fs::remove_file(&path).unwrap();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(
+            response,
+            format!(
+                "reloaded {0}\n{0}: line 1: invalid value `wide` for `gap`\n",
+                path.display()
+            )
+        );
+    }
+}
@@ -0,0 +1,51 @@
+//! Helpers for the files plugins write for other programs, such as saved layouts and status
+//! files, and for the tab-separated fields in them.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use ChunkWMError;
+
+/// Write a file by writing a temporary file next to it and renaming it over the file, so that
+/// readers never see it half written.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+/// Escape tabs, newlines and backslashes, so that a field stays on its line and in its column.
+pub fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Undo `escape`.
+pub fn unescape(field: &str) -> Result<String, ChunkWMError> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            _ => return Err(ChunkWMError::ParseError("invalid escape in a field")),
+        }
+    }
+    Ok(unescaped)
+}
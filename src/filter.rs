@@ -0,0 +1,753 @@
+//! The `filter` module contains declarative event filters, so that a handler only gets the events
+//! it is interested in, instead of checking the application or window role of every event itself.
+//!
+//! A filter is a list of predicates, which must all match:
+//!
+//! ```text
+//! app=Terminal,iTerm2 subrole=AXStandardWindow !flag=float space=user
+//! ```
+//!
+//! - `app=<names>`: the application of the event, or the application the window belongs to.
+//! - `role=<roles>` and `subrole=<subroles>`: the accessibility role and subrole of the window.
+//! - `flag=<flags>`: a flag of the window: `init_minimized`, `movable`, `resizable`,
+//!   `minimized`, `float`, `sticky`, `invalid` or `fore_tile`.
+//! - `display=<displays>`: the display, by its number (from 1, in arrangement order) or its UUID.
+//!   Window events happen on the window's display, display events on their display, and other
+//!   events on the display under the cursor.
+//! - `space=<types>`: the type of the active space of that display: `user`, `fullscreen`,
+//!   `system` or `unknown`.
+//!
+//! A predicate matches when any of its comma separated values does, and `!` negates it. Values
+//! with spaces or commas are quoted: `app="Google Chrome"`. Predicates on windows don't match
+//! events without a window, and predicates of which the value cannot be read, e.g. because the
+//! window was destroyed, don't match either. The empty filter matches every event.
+//!
+//! `EventFilters` attaches filters to subscriptions, and implements `FromConfig`. The key `filter`
+//! applies to all events, and `filter_<subscription>` to the events of one subscription. Events
+//! without a subscription, like daemon commands and timers, always match.
+//!
+//! ```text
+//! filter = space=user
+//! filter_window_created = subrole=AXStandardWindow !app=Finder
+//! ```
+//!
+//! The filters can be changed at runtime with `CVar`s, e.g. with
+//! `chunkc set plugin_filter_window_focused "app=Terminal"`, and
+//! [`EventFilters::refresh`](./struct.EventFilters.html#method.refresh).
+//!
+//! A plugin returns its filters from `HandleEvent::filters`, and `chunkwm_plugin!` only passes
+//! the events that match them on to `handle`.
+//!
+//! ## Example
+//! ```rust
+//! # #[macro_use] extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::filter::EventFilters;
+//! use chunkwm::system::WindowSystem;
+//!
+//! /// E.g. `Plugin<Axlib>`, with the `accessibility` feature.
+//! pub struct Plugin<S: WindowSystem> {
+//!     api: API,
+//!     system: S,
+//!     filters: EventFilters,
+//! }
+//!
+//! impl<S: WindowSystem + Default> HandleEvent for Plugin<S> {
+//!     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+//!         // handle the events that passed the filters
+//!         Ok(())
+//!     }
+//!
+//!     fn filters(&mut self) -> Option<(&EventFilters, &dyn WindowSystem)> {
+//!         self.filters.refresh("plugin_", &self.api);
+//!         Some((&self.filters, &self.system))
+//!     }
+//!     // some methods omitted
+//!     # fn new(api: API) -> Self {
+//!     #     Plugin { api, system: S::default(), filters: EventFilters::new() }
+//!     # }
+//!     # subscribe_all!();
+//!     # fn shutdown(&self) {}
+//! }
+//! # fn main() {}
+//! ```
+
+use api::{LogLevel, API};
+use config::{self, ConfigValues, FromConfig};
+use display::{DisplayUuid, SpaceType};
+use event::{DisplayID, Event, Subscription};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter;
+use std::str::FromStr;
+use system::WindowSystem;
+use window::{Window, WindowFlag};
+use ChunkWMError;
+
+/// A display in a filter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayMatch {
+    /// The number of the display, from 1, in arrangement order.
+    Number(u32),
+    /// The UUID of the display.
+    Uuid(DisplayUuid),
+}
+
+/// A predicate on an event.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// `app=<names>`
+    Application(Vec<String>),
+    /// `role=<roles>`
+    Role(Vec<String>),
+    /// `subrole=<subroles>`
+    Subrole(Vec<String>),
+    /// `flag=<flags>`
+    Flag(Vec<WindowFlag>),
+    /// `display=<displays>`
+    Display(Vec<DisplayMatch>),
+    /// `space=<types>`
+    Space(Vec<SpaceType>),
+    /// `!<predicate>`
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    fn matches<S: WindowSystem + ?Sized>(&self, subject: &Subject, system: &S) -> bool {
+        match *self {
+            Predicate::Application(ref names) => subject
+                .application
+                .as_ref()
+                .is_some_and(|name| names.contains(name)),
+            Predicate::Role(ref roles) => subject
+                .window
+                .and_then(|window| system.role(window).ok())
+                .is_some_and(|role| roles.contains(&role)),
+            Predicate::Subrole(ref subroles) => subject
+                .window
+                .and_then(|window| system.subrole(window).ok())
+                .is_some_and(|subrole| subroles.contains(&subrole)),
+            Predicate::Flag(ref flags) => subject.window.is_some_and(|window| {
+                flags
+                    .iter()
+                    .any(|&flag| system.has_flag(window, flag).unwrap_or(false))
+            }),
+            Predicate::Display(ref displays) => subject
+                .display
+                .is_some_and(|display| displays.iter().any(|d| is_display(system, display, d))),
+            Predicate::Space(ref types) => subject
+                .display
+                .and_then(|display| system.active_space(display).ok())
+                .and_then(|space| system.space_type(space).ok())
+                .is_some_and(|space_type| types.contains(&space_type)),
+            Predicate::Not(ref predicate) => !predicate.matches(subject, system),
+        }
+    }
+}
+
+fn is_display<S: WindowSystem + ?Sized>(
+    system: &S,
+    display: DisplayID,
+    wanted: &DisplayMatch,
+) -> bool {
+    match *wanted {
+        DisplayMatch::Number(number) => system
+            .displays()
+            .ok()
+            .and_then(|displays| displays.iter().position(|&d| d == display))
+            .is_some_and(|i| i + 1 == number as usize),
+        DisplayMatch::Uuid(ref uuid) => system
+            .display_identifier(display)
+            .is_ok_and(|identifier| identifier == *uuid),
+    }
+}
+
+/// What an event is about.
+struct Subject<'a> {
+    application: Option<String>,
+    window: Option<&'a Window>,
+    display: Option<DisplayID>,
+}
+
+impl<'a> Subject<'a> {
+    fn of<S: WindowSystem + ?Sized>(event: &'a Event, system: &S) -> Subject<'a> {
+        match *event {
+            Event::ApplicationLaunched(ref application)
+            | Event::ApplicationTerminated(ref application)
+            | Event::ApplicationActivated(ref application)
+            | Event::ApplicationDeactivated(ref application)
+            | Event::ApplicationHidden(ref application)
+            | Event::ApplicationUnhidden(ref application) => Subject {
+                application: application.name().ok(),
+                window: None,
+                display: system.cursor_display().ok(),
+            },
+            Event::WindowCreated(ref window)
+            | Event::WindowDestroyed(ref window)
+            | Event::WindowFocused(ref window)
+            | Event::WindowMoved(ref window)
+            | Event::WindowResized(ref window)
+            | Event::WindowMinimized(ref window)
+            | Event::WindowDeminimized(ref window)
+            | Event::WindowSheetCreated(ref window)
+            | Event::WindowTitleChanged(ref window) => Subject {
+                application: window.owner().and_then(|owner| owner.name()).ok(),
+                window: Some(window),
+                display: system.window_display(window).ok(),
+            },
+            Event::DisplayAdded(display)
+            | Event::DisplayRemoved(display)
+            | Event::DisplayMoved(display)
+            | Event::DisplayResized(display) => Subject {
+                application: None,
+                window: None,
+                display: Some(display),
+            },
+            _ => Subject {
+                application: None,
+                window: None,
+                display: system.cursor_display().ok(),
+            },
+        }
+    }
+}
+
+/// A list of predicates, which must all match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter(pub Vec<Predicate>);
+
+impl Filter {
+    /// Check whether the filter matches everything.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Check whether an event matches the filter.
+    pub fn matches<S: WindowSystem + ?Sized>(&self, event: &Event, system: &S) -> bool {
+        if self.is_empty() {
+            return true;
+        }
+        let subject = Subject::of(event, system);
+        self.0
+            .iter()
+            .all(|predicate| predicate.matches(&subject, system))
+    }
+}
+
+impl FromStr for Filter {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<Filter, ChunkWMError> {
+        let mut predicates = Vec::new();
+        let mut chars = s.trim().chars().peekable();
+        while chars.peek().is_some() {
+            let negated = chars.peek() == Some(&'!');
+            if negated {
+                chars.next();
+            }
+            let key: String = chars.by_ref().take_while(|&c| c != '=').collect();
+
+            let mut values = Vec::new();
+            loop {
+                values.push(parse_value(&mut chars)?);
+                match chars.next() {
+                    Some(',') => continue,
+                    Some(c) if c.is_whitespace() => break,
+                    None => break,
+                    Some(_) => return Err(ChunkWMError::ParseError("unexpected character")),
+                }
+            }
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+
+            let predicate = parse_predicate(&key, values)?;
+            predicates.push(if negated {
+                Predicate::Not(Box::new(predicate))
+            } else {
+                predicate
+            });
+        }
+        Ok(Filter(predicates))
+    }
+}
+
+/// Parse a value, which is quoted or ends at a comma or whitespace.
+fn parse_value<I: Iterator<Item = char>>(
+    chars: &mut ::std::iter::Peekable<I>,
+) -> Result<String, ChunkWMError> {
+    let mut value = String::new();
+    if chars.peek() == Some(&'"') {
+        chars.next();
+        loop {
+            match chars.next() {
+                Some('"') => break,
+                Some('\\') => match chars.next() {
+                    Some(c) => value.push(c),
+                    None => return Err(ChunkWMError::ParseError("unterminated quoted value")),
+                },
+                Some(c) => value.push(c),
+                None => return Err(ChunkWMError::ParseError("unterminated quoted value")),
+            }
+        }
+        return Ok(value);
+    }
+    while let Some(&c) = chars.peek() {
+        if c == ',' || c.is_whitespace() {
+            break;
+        }
+        value.push(c);
+        chars.next();
+    }
+    if value.is_empty() {
+        return Err(ChunkWMError::ParseError("expected a value"));
+    }
+    Ok(value)
+}
+
+fn parse_predicate(key: &str, values: Vec<String>) -> Result<Predicate, ChunkWMError> {
+    fn each<T, F>(values: Vec<String>, parse: F) -> Result<Vec<T>, ChunkWMError>
+    where
+        F: Fn(&str) -> Option<T>,
+    {
+        values
+            .iter()
+            .map(|value| parse(value).ok_or(ChunkWMError::ParseError("invalid filter value")))
+            .collect()
+    }
+
+    match key {
+        "app" => Ok(Predicate::Application(values)),
+        "role" => Ok(Predicate::Role(values)),
+        "subrole" => Ok(Predicate::Subrole(values)),
+        "flag" => each(values, |value| {
+            FLAGS
+                .iter()
+                .find(|&&(_, name)| name == value)
+                .map(|&(flag, _)| flag)
+        })
+        .map(Predicate::Flag),
+        "display" => each(values, |value| match value.parse() {
+            Ok(number) => Some(DisplayMatch::Number(number)),
+            Err(_) => value.parse().ok().map(DisplayMatch::Uuid),
+        })
+        .map(Predicate::Display),
+        "space" => each(values, |value| {
+            SPACE_TYPES
+                .iter()
+                .find(|&&(_, name)| name == value)
+                .map(|&(space_type, _)| space_type)
+        })
+        .map(Predicate::Space),
+        _ => Err(ChunkWMError::ParseError("unknown filter predicate")),
+    }
+}
+
+const FLAGS: [(WindowFlag, &str); 8] = [
+    (WindowFlag::InitMinimized, "init_minimized"),
+    (WindowFlag::Movable, "movable"),
+    (WindowFlag::Resizable, "resizable"),
+    (WindowFlag::Minimized, "minimized"),
+    (WindowFlag::Float, "float"),
+    (WindowFlag::Sticky, "sticky"),
+    (WindowFlag::Invalid, "invalid"),
+    (WindowFlag::ForeTile, "fore_tile"),
+];
+
+const SPACE_TYPES: [(SpaceType, &str); 4] = [
+    (SpaceType::User, "user"),
+    (SpaceType::Fullscreen, "fullscreen"),
+    (SpaceType::System, "system"),
+    (SpaceType::Unknown, "unknown"),
+];
+
+fn name_of<T: PartialEq>(names: &[(T, &'static str)], value: &T) -> &'static str {
+    names
+        .iter()
+        .find(|&(v, _)| v == value)
+        .map(|&(_, name)| name)
+        .unwrap_or("")
+}
+
+/// Write a value, quoted when it contains whitespace, a comma or a quote.
+fn write_value(f: &mut fmt::Formatter, value: &str) -> fmt::Result {
+    if !value.is_empty()
+        && !value.starts_with('"')
+        && !value.contains(|c: char| c == ',' || c.is_whitespace())
+    {
+        return write!(f, "{}", value);
+    }
+    write!(f, "\"")?;
+    for c in value.chars() {
+        if c == '"' || c == '\\' {
+            write!(f, "\\")?;
+        }
+        write!(f, "{}", c)?;
+    }
+    write!(f, "\"")
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (key, values): (&str, Vec<String>) = match *self {
+            Predicate::Application(ref names) => ("app", names.clone()),
+            Predicate::Role(ref roles) => ("role", roles.clone()),
+            Predicate::Subrole(ref subroles) => ("subrole", subroles.clone()),
+            Predicate::Flag(ref flags) => (
+                "flag",
+                flags
+                    .iter()
+                    .map(|flag| name_of(&FLAGS, flag).to_owned())
+                    .collect(),
+            ),
+            Predicate::Display(ref displays) => (
+                "display",
+                displays
+                    .iter()
+                    .map(|display| match *display {
+                        DisplayMatch::Number(number) => number.to_string(),
+                        DisplayMatch::Uuid(ref uuid) => uuid.to_string(),
+                    })
+                    .collect(),
+            ),
+            Predicate::Space(ref types) => (
+                "space",
+                types
+                    .iter()
+                    .map(|space_type| name_of(&SPACE_TYPES, space_type).to_owned())
+                    .collect(),
+            ),
+            Predicate::Not(ref predicate) => return write!(f, "!{}", predicate),
+        };
+        write!(f, "{}=", key)?;
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write_value(f, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, predicate) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", predicate)?;
+        }
+        Ok(())
+    }
+}
+
+/// The subscriptions and the names used in the `filter_<subscription>` keys.
+const SUBSCRIPTIONS: [(Subscription, &str); 21] = [
+    (Subscription::ApplicationLaunched, "application_launched"),
+    (
+        Subscription::ApplicationTerminated,
+        "application_terminated",
+    ),
+    (Subscription::ApplicationActivated, "application_activated"),
+    (
+        Subscription::ApplicationDeactivated,
+        "application_deactivated",
+    ),
+    (Subscription::ApplicationHidden, "application_hidden"),
+    (Subscription::ApplicationUnhidden, "application_unhidden"),
+    (Subscription::SpaceChanged, "space_changed"),
+    (Subscription::DisplayChanged, "display_changed"),
+    (Subscription::DisplayAdded, "display_added"),
+    (Subscription::DisplayRemoved, "display_removed"),
+    (Subscription::DisplayMoved, "display_moved"),
+    (Subscription::DisplayResized, "display_resized"),
+    (Subscription::WindowCreated, "window_created"),
+    (Subscription::WindowDestroyed, "window_destroyed"),
+    (Subscription::WindowFocused, "window_focused"),
+    (Subscription::WindowMoved, "window_moved"),
+    (Subscription::WindowResized, "window_resized"),
+    (Subscription::WindowMinimized, "window_minimized"),
+    (Subscription::WindowDeminimized, "window_deminimized"),
+    (Subscription::WindowSheetCreated, "window_sheet_created"),
+    (Subscription::WindowTitleChanged, "window_title_changed"),
+];
+
+/// The filters for all events and for the events of each subscription.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EventFilters {
+    all: Filter,
+    subscriptions: Vec<(Subscription, Filter)>,
+    /// The values of the `CVar`s when they were last read by `refresh`.
+    cvars: BTreeMap<String, String>,
+}
+
+impl EventFilters {
+    /// Create filters that match every event.
+    pub fn new() -> EventFilters {
+        EventFilters::default()
+    }
+
+    /// Get the filter for all events.
+    pub fn all(&self) -> &Filter {
+        &self.all
+    }
+
+    /// Set the filter for all events.
+    pub fn set_all(&mut self, filter: Filter) {
+        self.all = filter;
+    }
+
+    /// Get the filter for the events of a subscription.
+    pub fn filter(&self, subscription: Subscription) -> Option<&Filter> {
+        self.subscriptions
+            .iter()
+            .find(|&&(s, _)| s == subscription)
+            .map(|(_, filter)| filter)
+    }
+
+    /// Set the filter for the events of a subscription. An empty filter removes it.
+    pub fn set(&mut self, subscription: Subscription, filter: Filter) {
+        self.subscriptions.retain(|&(s, _)| s != subscription);
+        if !filter.is_empty() {
+            self.subscriptions.push((subscription, filter));
+        }
+    }
+
+    /// Check whether an event passes the filter for all events and the filter of its
+    /// subscription. Events without a subscription always pass.
+    pub fn matches<S: WindowSystem + ?Sized>(&self, event: &Event, system: &S) -> bool {
+        let subscription = match event.subscription() {
+            Some(subscription) => subscription,
+            None => return true,
+        };
+        self.all.matches(event, system)
+            && self
+                .filter(subscription)
+                .is_none_or(|filter| filter.matches(event, system))
+    }
+
+    /// Read the filters from the `CVar`s `<prefix>filter` and `<prefix>filter_<subscription>`
+    /// that changed since the last refresh, and keep the other filters. Invalid values are logged
+    /// once and ignored. Returns whether a filter changed.
+    pub fn refresh(&mut self, prefix: &str, api: &API) -> bool {
+        let cvar = config::cvar_lookup(api);
+        self.refresh_with(prefix, &cvar, &mut |message| {
+            api.log(LogLevel::Error, message)
+        })
+    }
+
+    fn refresh_with(
+        &mut self,
+        prefix: &str,
+        cvar: &dyn Fn(&str) -> Option<String>,
+        log: &mut dyn FnMut(String),
+    ) -> bool {
+        let keys = iter::once((None, "filter".to_owned())).chain(
+            SUBSCRIPTIONS
+                .iter()
+                .map(|&(subscription, name)| (Some(subscription), format!("filter_{}", name))),
+        );
+
+        let mut changed = false;
+        for (subscription, key) in keys {
+            let name = format!("{}{}", prefix, key);
+            let value = match cvar(&name) {
+                Some(value) => value,
+                None => continue,
+            };
+            if self.cvars.get(&name) == Some(&value) {
+                continue;
+            }
+            self.cvars.insert(name.clone(), value.clone());
+
+            let filter = match value.parse::<Filter>() {
+                Ok(filter) => filter,
+                Err(_) => {
+                    log(format!("invalid value `{}` for CVar `{}`", value, name));
+                    continue;
+                }
+            };
+            let current = match subscription {
+                Some(subscription) => self.filter(subscription).cloned().unwrap_or_default(),
+                None => self.all.clone(),
+            };
+            if filter != current {
+                match subscription {
+                    Some(subscription) => self.set(subscription, filter),
+                    None => self.all = filter,
+                }
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+impl FromConfig for EventFilters {
+    fn from_config(values: &ConfigValues) -> EventFilters {
+        let mut filters = EventFilters::new();
+        filters.all = values.get("filter", Filter::default());
+        for &(subscription, name) in SUBSCRIPTIONS.iter() {
+            if let Some(filter) = values.try_get(&format!("filter_{}", name)) {
+                filters.set(subscription, filter);
+            }
+        }
+        filters
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::{read_settings, ConfigFile};
+    use simulator::{Simulator, WindowSpec};
+    use testing::rect;
+    use timer::TimerId;
+
+    fn filter(s: &str) -> Filter {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_format() {
+        let parsed =
+            filter(r#"app=Terminal,"Google Chrome" !flag=float,sticky space=user display=2"#);
+        assert_eq!(
+            parsed,
+            Filter(vec![
+                Predicate::Application(vec!["Terminal".into(), "Google Chrome".into()]),
+                Predicate::Not(Box::new(Predicate::Flag(vec![
+                    WindowFlag::Float,
+                    WindowFlag::Sticky,
+                ]))),
+                Predicate::Space(vec![SpaceType::User]),
+                Predicate::Display(vec![DisplayMatch::Number(2)]),
+            ])
+        );
+        assert_eq!(
+            parsed.to_string(),
+            r#"app=Terminal,"Google Chrome" !flag=float,sticky space=user display=2"#
+        );
+        assert_eq!(filter(&parsed.to_string()), parsed);
+        assert_eq!(
+            filter("display=00000000-0000-0000-0000-000000000002"),
+            Filter(vec![Predicate::Display(vec![DisplayMatch::Uuid(
+                "00000000-0000-0000-0000-000000000002".parse().unwrap()
+            )])])
+        );
+        assert_eq!(filter("  "), Filter::default());
+
+        assert!("app".parse::<Filter>().is_err());
+        assert!("app=".parse::<Filter>().is_err());
+        assert!("app=\"Terminal".parse::<Filter>().is_err());
+        assert!("colour=red".parse::<Filter>().is_err());
+        assert!("flag=floating".parse::<Filter>().is_err());
+        assert!("space=user,".parse::<Filter>().is_err());
+    }
+
+    #[test]
+    fn matches_events() {
+        let simulator = Simulator::new();
+        let second = simulator.add_display(rect(1920.0, 0.0, 1920.0, 1080.0));
+        let terminal = simulator.launch(100, "Terminal");
+        simulator.launch(200, "Finder");
+        let vim = simulator
+            .create_window(100, WindowSpec::new("vim", rect(0.0, 0.0, 800.0, 600.0)))
+            .unwrap();
+        let mut spec = WindowSpec::new("Copy", rect(2000.0, 0.0, 400.0, 300.0));
+        spec.subrole = "AXDialog".to_owned();
+        spec.flags = WindowFlag::Float.bit();
+        let dialog = simulator.create_window(200, spec).unwrap();
+        simulator.take_events();
+
+        let created =
+            |window: &Window| Event::WindowCreated(simulator.window(window.id().unwrap()).unwrap());
+        let matches = |s: &str, event: &Event| filter(s).matches(event, &simulator);
+
+        assert!(matches("app=Terminal", &created(&vim)));
+        assert!(!matches("app=Terminal", &created(&dialog)));
+        assert!(matches("app=Terminal,Finder", &created(&dialog)));
+        assert!(matches(
+            "role=AXWindow subrole=AXStandardWindow",
+            &created(&vim)
+        ));
+        assert!(!matches("subrole=AXStandardWindow", &created(&dialog)));
+        assert!(matches("flag=float", &created(&dialog)));
+        assert!(matches("!flag=float", &created(&vim)));
+        assert!(matches("display=1", &created(&vim)));
+        assert!(matches("display=2", &created(&dialog)));
+        let uuid = simulator.display_identifier(second).unwrap();
+        assert!(matches(&format!("display={}", uuid), &created(&dialog)));
+        assert!(matches("display=2", &Event::DisplayResized(second)));
+        assert!(matches("space=user", &created(&vim)));
+        assert!(matches("", &created(&vim)));
+
+        // Window predicates don't match events without a window, their negations do.
+        let launched = Event::ApplicationLaunched(terminal);
+        assert!(matches("app=Terminal", &launched));
+        assert!(!matches("role=AXWindow", &launched));
+        assert!(matches("!flag=float", &launched));
+        assert!(matches("display=1 space=user", &launched));
+
+        simulator.set_fullscreen(vim.id().unwrap(), true).unwrap();
+        assert!(matches("space=fullscreen", &created(&vim)));
+        assert!(!matches("space=user", &created(&vim)));
+
+        simulator.destroy_window(dialog.id().unwrap()).unwrap();
+        assert!(!matches(
+            "subrole=AXDialog",
+            &Event::WindowDestroyed(dialog)
+        ));
+    }
+
+    #[test]
+    fn subscriptions_and_cvars() {
+        let simulator = Simulator::new();
+        simulator.launch(100, "Terminal");
+        simulator.launch(200, "Finder");
+        let vim = simulator
+            .create_window(100, WindowSpec::new("vim", rect(0.0, 0.0, 800.0, 600.0)))
+            .unwrap();
+        let ls = simulator
+            .create_window(200, WindowSpec::new("ls", rect(0.0, 0.0, 800.0, 600.0)))
+            .unwrap();
+        let (vim, ls) = (vim.id().unwrap(), ls.id().unwrap());
+        let window = |id| simulator.window(id).unwrap();
+
+        let (file, _) =
+            ConfigFile::parse("filter = space=user\nfilter_window_focused = app=Terminal\n");
+        let cvar = |_: &str| None;
+        let (mut filters, errors) = read_settings::<EventFilters>(&file, "plugin_", &cvar);
+        assert!(errors.is_empty());
+        assert_eq!(
+            filters.filter(Subscription::WindowFocused),
+            Some(&filter("app=Terminal"))
+        );
+        assert_eq!(filters.filter(Subscription::WindowMoved), None);
+
+        assert!(filters.matches(&Event::WindowFocused(window(vim)), &simulator));
+        assert!(!filters.matches(&Event::WindowFocused(window(ls)), &simulator));
+        assert!(filters.matches(&Event::WindowMoved(window(ls)), &simulator));
+        assert!(filters.matches(&Event::Timer(TimerId(1)), &simulator));
+
+        let api = simulator.api();
+        assert!(!filters.refresh("plugin_", &api));
+        simulator.set_cvar("plugin_filter_window_focused", "app=Finder");
+        simulator.set_cvar("plugin_filter_window_moved", "!app=Finder");
+        simulator.set_cvar("plugin_filter", "space=");
+        assert!(filters.refresh("plugin_", &api));
+        assert!(!filters.refresh("plugin_", &api));
+        #[cfg(not(feature = "api-7"))]
+        assert_eq!(simulator.take_log().len(), 1);
+        assert_eq!(filters.all(), &filter("space=user"));
+        assert!(!filters.matches(&Event::WindowFocused(window(vim)), &simulator));
+        assert!(filters.matches(&Event::WindowFocused(window(ls)), &simulator));
+        assert!(!filters.matches(&Event::WindowMoved(window(ls)), &simulator));
+
+        simulator.set_cvar("plugin_filter_window_focused", "");
+        assert!(filters.refresh("plugin_", &api));
+        assert_eq!(filters.filter(Subscription::WindowFocused), None);
+        assert!(filters.matches(&Event::WindowFocused(window(vim)), &simulator));
+    }
+}
@@ -0,0 +1,519 @@
+//! The `focus` module contains a `FocusHistory`, which keeps the most recently used (MRU) order
+//! of the windows from `WindowFocused` events, and daemon commands to move through it.
+//!
+//! ```sh
+//! chunkc plugin::focus --previous
+//! chunkc plugin::focus --forward --space
+//! chunkc plugin::focus --backward --display
+//! chunkc plugin::focus --list
+//! ```
+//!
+//! `--previous` focuses the window that was focused before the focused window; repeating it jumps
+//! back and forth between the two. `--forward` and `--backward` cycle through the windows like
+//! alt-tab: the order is kept while cycling, so repeating `--forward` reaches older and older
+//! windows. The cycle ends when another window is focused, or when no cycle command is given for
+//! the cycle timeout (1 second by default); the window it ended on then becomes the most recent.
+//!
+//! Every command takes an optional scope: `--display` and `--space` only use the windows that
+//! were last focused on the display or the space of the focused window. Destroyed and minimized
+//! windows, and the windows of terminated applications, are removed from the history.
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::focus::FocusHistory;
+//! use chunkwm::system::WindowSystem;
+//!
+//! fn handle<S: WindowSystem>(
+//!     history: &mut FocusHistory<S>,
+//!     event: &Event,
+//! ) -> Result<(), ChunkWMError> {
+//!     if history.handle(event)? {
+//!         return Ok(());
+//!     }
+//!     // handle other events and commands
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+
+use event::{DisplayID, Event};
+use raw::{CGSSpaceID, PID};
+use std::str::FromStr;
+use std::time::Duration;
+use system::WindowSystem;
+use timer::{Clock, SystemClock};
+use window::Window;
+use ChunkWMError;
+
+/// The windows a focus command chooses from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    /// All windows. This is the default.
+    All,
+    /// The windows on the display of the focused window. Argument: `--display`.
+    Display,
+    /// The windows on the space of the focused window. Argument: `--space`.
+    Space,
+}
+
+/// A focus command.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FocusCommand {
+    /// Focus the previously focused window. Argument: `--previous`.
+    Previous(Scope),
+    /// Cycle to the next older window. Argument: `--forward`.
+    Forward(Scope),
+    /// Cycle to the next newer window, starting at the oldest. Argument: `--backward`.
+    Backward(Scope),
+    /// List the ids of the windows, the most recently focused first. Argument: `--list`.
+    List(Scope),
+}
+
+impl FromStr for FocusCommand {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<FocusCommand, ChunkWMError> {
+        let mut words = s.split_whitespace();
+        let command = words.next();
+        let scope = match words.next() {
+            None => Scope::All,
+            Some("--display") => Scope::Display,
+            Some("--space") => Scope::Space,
+            Some(_) => return Err(ChunkWMError::ParseError("unknown focus scope")),
+        };
+        if words.next().is_some() {
+            return Err(ChunkWMError::ParseError("too many arguments"));
+        }
+        match command {
+            Some("--previous") => Ok(FocusCommand::Previous(scope)),
+            Some("--forward") => Ok(FocusCommand::Forward(scope)),
+            Some("--backward") => Ok(FocusCommand::Backward(scope)),
+            Some("--list") => Ok(FocusCommand::List(scope)),
+            _ => Err(ChunkWMError::ParseError("unknown focus command")),
+        }
+    }
+}
+
+/// A window in the history.
+struct Entry {
+    id: u32,
+    owner: PID,
+    display: Option<DisplayID>,
+    space: Option<CGSSpaceID>,
+}
+
+/// A cycle through the windows that were in the history when it started.
+struct Cycle {
+    order: Vec<u32>,
+    position: usize,
+    /// The window the cycle focused last.
+    target: u32,
+    /// When the last cycle command was given.
+    last: Duration,
+}
+
+/// The most recently used order of the focused windows.
+pub struct FocusHistory<S: WindowSystem, C: Clock = SystemClock> {
+    system: S,
+    clock: C,
+    command: &'static str,
+    cycle_timeout: Duration,
+    /// The most recently focused window first.
+    entries: Vec<Entry>,
+    cycle: Option<Cycle>,
+}
+
+impl<S: WindowSystem> FocusHistory<S> {
+    /// Create a new `FocusHistory`, answering the `focus` command.
+    pub fn new(system: S) -> FocusHistory<S> {
+        FocusHistory::with_clock(system, SystemClock::new())
+    }
+}
+
+impl<S: WindowSystem, C: Clock> FocusHistory<S, C> {
+    /// Create a new `FocusHistory` that uses a different clock for the cycle timeout.
+    pub fn with_clock(system: S, clock: C) -> FocusHistory<S, C> {
+        FocusHistory {
+            system,
+            clock,
+            command: "focus",
+            cycle_timeout: Duration::from_secs(1),
+            entries: Vec::new(),
+            cycle: None,
+        }
+    }
+
+    /// Answer a different command than `focus`.
+    pub fn with_command(mut self, command: &'static str) -> FocusHistory<S, C> {
+        self.command = command;
+        self
+    }
+
+    /// End a cycle when no cycle command is given for `timeout`.
+    pub fn with_cycle_timeout(mut self, timeout: Duration) -> FocusHistory<S, C> {
+        self.cycle_timeout = timeout;
+        self
+    }
+
+    /// Get the window system.
+    pub fn system(&self) -> &S {
+        &self.system
+    }
+
+    /// Get the window system mutably.
+    pub fn system_mut(&mut self) -> &mut S {
+        &mut self.system
+    }
+
+    /// Get the ids of all windows, the most recently focused first. While cycling, this is the
+    /// order from before the cycle.
+    pub fn stack(&self) -> Vec<u32> {
+        self.entries.iter().map(|entry| entry.id).collect()
+    }
+
+    /// Get the ids of the windows that were last focused on a display.
+    pub fn stack_on_display(&self, display: DisplayID) -> Vec<u32> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.display == Some(display))
+            .map(|entry| entry.id)
+            .collect()
+    }
+
+    /// Get the ids of the windows that were last focused on a space.
+    pub fn stack_on_space(&self, space: CGSSpaceID) -> Vec<u32> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.space == Some(space))
+            .map(|entry| entry.id)
+            .collect()
+    }
+
+    /// Update the history with an event, and run the focus command. The reply to the command is
+    /// sent to the client.
+    ///
+    /// Returns `Ok(true)` when the event was the focus command.
+    pub fn handle(&mut self, event: &Event) -> Result<bool, ChunkWMError> {
+        match *event {
+            Event::DaemonCommand(ref payload) => {
+                let command = self.command;
+                return payload.answer(command, |command| self.run(command));
+            }
+            Event::WindowFocused(ref window) => self.focused(window)?,
+            Event::WindowMoved(ref window) => {
+                let id = window.id()?;
+                let (display, space) = self.location(window);
+                if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+                    if entry.display != display {
+                        entry.display = display;
+                        entry.space = space;
+                    }
+                }
+            }
+            Event::WindowDestroyed(ref window) | Event::WindowMinimized(ref window) => {
+                let id = window.id()?;
+                self.entries.retain(|entry| entry.id != id);
+            }
+            Event::ApplicationTerminated(ref application) => {
+                let pid = application.pid()?;
+                self.entries.retain(|entry| entry.owner != pid);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Run a focus command, and get the reply.
+    pub fn run(&mut self, command: FocusCommand) -> Result<String, ChunkWMError> {
+        if self.cycle.is_some() && !self.cycling(command) {
+            self.end_cycle();
+        }
+        match command {
+            FocusCommand::Previous(scope) => {
+                let id = self
+                    .scoped(scope)
+                    .get(1)
+                    .cloned()
+                    .ok_or(ChunkWMError::Internal("there is no previous window"))?;
+                self.focus(id).map(|_| String::new())
+            }
+            FocusCommand::Forward(scope) => self.step(scope, true).map(|_| String::new()),
+            FocusCommand::Backward(scope) => self.step(scope, false).map(|_| String::new()),
+            FocusCommand::List(scope) => Ok(self
+                .scoped(scope)
+                .iter()
+                .map(|id| format!("{}\n", id))
+                .collect()),
+        }
+    }
+
+    /// Check whether a command continues the current cycle.
+    fn cycling(&self, command: FocusCommand) -> bool {
+        let recent = self
+            .cycle
+            .as_ref()
+            .is_some_and(|cycle| self.clock.now() < cycle.last + self.cycle_timeout);
+        match command {
+            FocusCommand::Forward(_) | FocusCommand::Backward(_) => recent,
+            FocusCommand::Previous(_) | FocusCommand::List(_) => false,
+        }
+    }
+
+    /// Move the current cycle one step, or start a cycle.
+    fn step(&mut self, scope: Scope, forward: bool) -> Result<(), ChunkWMError> {
+        let mut cycle = match self.cycle.take() {
+            Some(cycle) => cycle,
+            None => {
+                let order = self.scoped(scope);
+                let target = match order.first() {
+                    Some(&id) => id,
+                    None => return Err(ChunkWMError::Internal("there are no windows to cycle")),
+                };
+                Cycle {
+                    order,
+                    position: 0,
+                    target,
+                    last: self.clock.now(),
+                }
+            }
+        };
+
+        // Skip the windows that left the history since the cycle started.
+        let len = cycle.order.len();
+        for _ in 0..len {
+            cycle.position = if forward {
+                (cycle.position + 1) % len
+            } else {
+                (cycle.position + len - 1) % len
+            };
+            if self.position(cycle.order[cycle.position]).is_some() {
+                break;
+            }
+        }
+        cycle.target = cycle.order[cycle.position];
+        cycle.last = self.clock.now();
+        let target = cycle.target;
+        self.cycle = Some(cycle);
+        self.focus(target)
+    }
+
+    /// End the current cycle, making the window it ended on the most recent.
+    fn end_cycle(&mut self) {
+        if let Some(cycle) = self.cycle.take() {
+            self.promote(cycle.target);
+        }
+    }
+
+    fn focused(&mut self, window: &Window) -> Result<(), ChunkWMError> {
+        let id = window.id()?;
+        let in_cycle = self.cycle.as_ref().map(|cycle| cycle.target) == Some(id);
+        if !in_cycle {
+            self.end_cycle();
+        }
+
+        let (display, space) = self.location(window);
+        match self.position(id) {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.display = display;
+                entry.space = space;
+            }
+            None => self.entries.insert(
+                0,
+                Entry {
+                    id,
+                    owner: window.owner()?.pid()?,
+                    display,
+                    space,
+                },
+            ),
+        }
+        if !in_cycle {
+            self.promote(id);
+        }
+        Ok(())
+    }
+
+    /// The display and space of a window. A window that gets focus is on the active space of its
+    /// display.
+    fn location(&self, window: &Window) -> (Option<DisplayID>, Option<CGSSpaceID>) {
+        let display = self.system.window_display(window).ok();
+        let space = display.and_then(|display| self.system.active_space(display).ok());
+        (display, space)
+    }
+
+    fn position(&self, id: u32) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    fn promote(&mut self, id: u32) {
+        if let Some(index) = self.position(id) {
+            let entry = self.entries.remove(index);
+            self.entries.insert(0, entry);
+        }
+    }
+
+    /// The ids in a scope of the focused window, which is the most recent window.
+    fn scoped(&self, scope: Scope) -> Vec<u32> {
+        let current = match self.entries.first() {
+            Some(current) => current,
+            None => return Vec::new(),
+        };
+        match scope {
+            Scope::All => self.stack(),
+            Scope::Display => current
+                .display
+                .map(|display| self.stack_on_display(display))
+                .unwrap_or_else(|| vec![current.id]),
+            Scope::Space => current
+                .space
+                .map(|space| self.stack_on_space(space))
+                .unwrap_or_else(|| vec![current.id]),
+        }
+    }
+
+    fn focus(&mut self, id: u32) -> Result<(), ChunkWMError> {
+        if self.position(id).is_none() {
+            return Err(ChunkWMError::Internal("the window is not in the history"));
+        }
+        let window = self.system.window(id)?;
+        self.system.focus(&window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simulator::{Simulator, WindowSpec};
+    use testing::{pump, rect};
+    use timer::ManualClock;
+
+    fn run(
+        simulator: &Simulator,
+        history: &mut FocusHistory<Simulator, ManualClock>,
+        command: &str,
+    ) -> Result<String, ChunkWMError> {
+        let reply = history.run(command.parse()?);
+        pump(simulator, history);
+        reply
+    }
+
+    /// Windows 1 to 4, focused in order, so that 4 is the most recent. Windows 1 and 2 are on a
+    /// second display.
+    fn setup() -> (Simulator, ManualClock, FocusHistory<Simulator, ManualClock>) {
+        let simulator = Simulator::new();
+        let clock = ManualClock::new();
+        let mut history = FocusHistory::with_clock(simulator.clone(), clock.clone());
+        simulator.add_display(rect(1920.0, 0.0, 1920.0, 1080.0));
+        simulator.launch(1, "Terminal");
+        for id in 1..5 {
+            let x = if id <= 2 { 2000.0 } else { 0.0 };
+            let spec = WindowSpec::new(format!("{}", id), rect(x, 0.0, 100.0, 100.0));
+            simulator.create_window(1, spec).unwrap();
+            simulator.focus_window(id).unwrap();
+        }
+        pump(&simulator, &mut history);
+        (simulator, clock, history)
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            "--previous".parse::<FocusCommand>().unwrap(),
+            FocusCommand::Previous(Scope::All)
+        );
+        assert_eq!(
+            "--forward --space".parse::<FocusCommand>().unwrap(),
+            FocusCommand::Forward(Scope::Space)
+        );
+        assert_eq!(
+            "--list --display".parse::<FocusCommand>().unwrap(),
+            FocusCommand::List(Scope::Display)
+        );
+        assert!("--previous --window".parse::<FocusCommand>().is_err());
+        assert!("--next".parse::<FocusCommand>().is_err());
+    }
+
+    #[test]
+    fn previous_and_scopes() {
+        let (simulator, _, mut history) = setup();
+        assert_eq!(history.stack(), vec![4, 3, 2, 1]);
+        assert_eq!(
+            run(&simulator, &mut history, "--list --display").unwrap(),
+            "4\n3\n"
+        );
+
+        run(&simulator, &mut history, "--previous").unwrap();
+        assert_eq!(history.stack(), vec![3, 4, 2, 1]);
+        run(&simulator, &mut history, "--previous").unwrap();
+        assert_eq!(history.stack(), vec![4, 3, 2, 1]);
+
+        // Moving a window to the other display moves it to the other display's stack.
+        simulator
+            .set_window_frame(3, rect(2000.0, 0.0, 100.0, 100.0))
+            .unwrap();
+        pump(&simulator, &mut history);
+        let second = simulator.displays().unwrap()[1];
+        assert_eq!(history.stack_on_display(second), vec![3, 2, 1]);
+        let space = simulator.active_space(second).unwrap();
+        assert_eq!(history.stack_on_space(space), vec![3, 2, 1]);
+
+        simulator.focus_window(2).unwrap();
+        pump(&simulator, &mut history);
+        run(&simulator, &mut history, "--previous --space").unwrap();
+        assert_eq!(simulator.focused_window_id(), Some(3));
+    }
+
+    #[test]
+    fn cycle() {
+        let (simulator, clock, mut history) = setup();
+
+        // The order is kept while cycling.
+        run(&simulator, &mut history, "--forward").unwrap();
+        run(&simulator, &mut history, "--forward").unwrap();
+        assert_eq!(simulator.focused_window_id(), Some(2));
+        assert_eq!(history.stack(), vec![4, 3, 2, 1]);
+        run(&simulator, &mut history, "--backward").unwrap();
+        assert_eq!(simulator.focused_window_id(), Some(3));
+
+        // After the timeout, the window the cycle ended on is the most recent.
+        clock.advance(Duration::from_secs(2));
+        run(&simulator, &mut history, "--forward").unwrap();
+        assert_eq!(simulator.focused_window_id(), Some(4));
+        assert_eq!(history.stack(), vec![3, 4, 2, 1]);
+
+        // Focusing another window ends the cycle too.
+        simulator.focus_window(1).unwrap();
+        pump(&simulator, &mut history);
+        assert_eq!(history.stack(), vec![1, 4, 3, 2]);
+
+        // Backward starts at the oldest window, within the scope.
+        run(&simulator, &mut history, "--backward --display").unwrap();
+        assert_eq!(simulator.focused_window_id(), Some(2));
+        run(&simulator, &mut history, "--backward --display").unwrap();
+        assert_eq!(simulator.focused_window_id(), Some(1));
+    }
+
+    #[test]
+    fn prune() {
+        let (simulator, _, mut history) = setup();
+        simulator.set_window_minimized(3, true).unwrap();
+        simulator.destroy_window(4).unwrap();
+        pump(&simulator, &mut history);
+        assert_eq!(history.stack(), vec![2, 1]);
+
+        // A window that leaves the history during a cycle is skipped.
+        run(&simulator, &mut history, "--forward").unwrap();
+        simulator.destroy_window(1).unwrap();
+        pump(&simulator, &mut history);
+        run(&simulator, &mut history, "--forward").unwrap();
+        assert_eq!(simulator.focused_window_id(), Some(2));
+
+        simulator.terminate(1).unwrap();
+        pump(&simulator, &mut history);
+        assert!(history.stack().is_empty());
+        assert!(run(&simulator, &mut history, "--previous").is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//! The `geometry` module contains the point, size and rectangle types, and some helpers for working
+//! with them.
+//!
+//! On macOS these are the types from `core-graphics`. Other targets get stand-ins with the same
+//! layout, fields and constructors, so that code using them builds everywhere.
+
+#[cfg(target_os = "macos")]
+pub use core_graphics::base::CGFloat;
+#[cfg(target_os = "macos")]
+pub use core_graphics::geometry::{CGPoint, CGRect, CGSize};
+
+#[cfg(not(target_os = "macos"))]
+pub use self::portable::*;
+
+#[cfg(feature = "serde")]
+pub use self::serde_impls::*;
+
+/// Check whether a rectangle contains a point. The left and top edges are inclusive, the right and
+/// bottom edges are exclusive, so that adjacent rectangles never both contain a point.
+pub fn contains(rect: &CGRect, point: &CGPoint) -> bool {
+    point.x >= rect.origin.x
+        && point.y >= rect.origin.y
+        && point.x < rect.origin.x + rect.size.width
+        && point.y < rect.origin.y + rect.size.height
+}
+
+/// Get the center of a rectangle.
+pub fn center(rect: &CGRect) -> CGPoint {
+    CGPoint::new(
+        rect.origin.x + rect.size.width / 2.0,
+        rect.origin.y + rect.size.height / 2.0,
+    )
+}
+
+/// The geometry types for other targets than macOS.
+#[cfg(not(target_os = "macos"))]
+mod portable {
+    /// The floating point type used for coordinates.
+    #[cfg(target_pointer_width = "64")]
+    pub type CGFloat = f64;
+    /// The floating point type used for coordinates.
+    #[cfg(not(target_pointer_width = "64"))]
+    pub type CGFloat = f32;
+
+    /// A point.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct CGPoint {
+        pub x: CGFloat,
+        pub y: CGFloat,
+    }
+
+    impl CGPoint {
+        pub fn new(x: CGFloat, y: CGFloat) -> CGPoint {
+            CGPoint { x, y }
+        }
+    }
+
+    /// A size.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct CGSize {
+        pub width: CGFloat,
+        pub height: CGFloat,
+    }
+
+    impl CGSize {
+        pub fn new(width: CGFloat, height: CGFloat) -> CGSize {
+            CGSize { width, height }
+        }
+    }
+
+    /// A rectangle.
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct CGRect {
+        pub origin: CGPoint,
+        pub size: CGSize,
+    }
+
+    impl CGRect {
+        pub fn new(origin: &CGPoint, size: &CGSize) -> CGRect {
+            CGRect {
+                origin: *origin,
+                size: *size,
+            }
+        }
+    }
+}
+
+/// The geometry types are defined in `core-graphics`, so they can't implement `Serialize` and
+/// `Deserialize` themselves. Use these definitions with `#[serde(with = "...")]` instead.
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use super::*;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    /// The serde definition of `CGPoint`.
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "CGPoint")]
+    pub struct CGPointDef {
+        pub x: CGFloat,
+        pub y: CGFloat,
+    }
+
+    /// The serde definition of `CGSize`.
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "CGSize")]
+    pub struct CGSizeDef {
+        pub width: CGFloat,
+        pub height: CGFloat,
+    }
+
+    /// The serde definition of `CGRect`.
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "CGRect")]
+    pub struct CGRectDef {
+        #[serde(with = "CGPointDef")]
+        pub origin: CGPoint,
+        #[serde(with = "CGSizeDef")]
+        pub size: CGSize,
+    }
+
+    /// The serde definition of `Option<CGRect>`, for use with `#[serde(with = "OptionCGRectDef")]`.
+    pub struct OptionCGRectDef;
+
+    #[derive(Serialize, Deserialize)]
+    struct RectHelper(#[serde(with = "CGRectDef")] CGRect);
+
+    impl OptionCGRectDef {
+        pub fn serialize<S: Serializer>(rect: &Option<CGRect>, s: S) -> Result<S::Ok, S::Error> {
+            rect.map(RectHelper).serialize(s)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<CGRect>, D::Error> {
+            Ok(Option::<RectHelper>::deserialize(d)?.map(|helper| helper.0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing::rect;
+
+    #[test]
+    fn contains_edges() {
+        let r = rect(10.0, 10.0, 100.0, 50.0);
+        assert!(contains(&r, &CGPoint::new(10.0, 10.0)));
+        assert!(contains(&r, &CGPoint::new(109.9, 59.9)));
+        assert!(!contains(&r, &CGPoint::new(110.0, 30.0)));
+        assert!(!contains(&r, &CGPoint::new(50.0, 60.0)));
+        assert!(!contains(&r, &CGPoint::new(9.0, 30.0)));
+    }
+
+    #[test]
+    fn center_of_rect() {
+        let c = center(&rect(10.0, 20.0, 100.0, 50.0));
+        assert_eq!((c.x, c.y), (60.0, 45.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_rect() {
+        #[derive(Serialize, Deserialize)]
+        struct Frame {
+            #[serde(with = "CGRectDef")]
+            rect: CGRect,
+            #[serde(with = "OptionCGRectDef")]
+            maybe: Option<CGRect>,
+        }
+
+        let frame = Frame {
+            rect: rect(1.0, 2.0, 3.0, 4.0),
+            maybe: None,
+        };
+        let json = ::serde_json::to_string(&frame).unwrap();
+        assert_eq!(
+            json,
+            r#"{"rect":{"origin":{"x":1.0,"y":2.0},"size":{"width":3.0,"height":4.0}},"maybe":null}"#
+        );
+
+        let frame: Frame = ::serde_json::from_str(&json).unwrap();
+        assert_eq!(frame.rect.size.height, 4.0);
+        assert!(frame.maybe.is_none());
+    }
+}
@@ -0,0 +1,522 @@
+//! The `labels` module contains `SpaceLabels`, which gives desktops names like `code`, `web` or
+//! `chat`, so that commands and scripts don't have to know desktop numbers.
+//!
+//! Labels are configured with the desktop number they start on:
+//!
+//! ```text
+//! labels = code:1 web:2 chat:3
+//! ```
+//!
+//! A label then sticks to the space it was put on: when the desktops are reordered, the label
+//! moves with the space and resolves to its new desktop number. When the space goes away, e.g.
+//! because its display was disconnected, the label goes back to its configured desktop number as
+//! soon as that desktop exists and has no other label. A desktop has at most one label.
+//!
+//! Labels can be listed, resolved, set and removed with a daemon command. `--set` without a
+//! desktop number labels the active desktop of the display under the cursor.
+//!
+//! ```sh
+//! chunkc plugin::label --list
+//! chunkc plugin::label --resolve code
+//! chunkc plugin::label --set music 4
+//! chunkc plugin::label --remove music
+//! ```
+//!
+//! Plugins resolve labels in their own commands with [`SpaceLabels::space`] and
+//! [`SpaceLabels::resolve_message`], and report them in queries with
+//! `WorkspaceModel::set_space_labels`.
+//!
+//! [`SpaceLabels::space`]: ./struct.SpaceLabels.html#method.space
+//! [`SpaceLabels::resolve_message`]: ./struct.SpaceLabels.html#method.resolve_message
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::labels::SpaceLabels;
+//! use chunkwm::system::WindowSystem;
+//! use chunkwm::workspace::WorkspaceModel;
+//!
+//! fn handle<S: WindowSystem>(
+//!     labels: &mut SpaceLabels<S>,
+//!     model: &mut WorkspaceModel,
+//!     event: &Event,
+//! ) -> Result<(), ChunkWMError> {
+//!     let handled = labels.handle(event)?;
+//!     model.set_space_labels(labels.labels());
+//!     if handled {
+//!         return Ok(());
+//!     }
+//!     // handle other events and commands
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+
+use config::{ConfigValues, FromConfig};
+use event::Event;
+use query::or_dash;
+use raw::CGSSpaceID;
+use std::fmt;
+use std::str::FromStr;
+use system::{cursor_or_first_display, WindowSystem};
+use ChunkWMError;
+
+/// Labels and the desktop numbers they start on, e.g. `code:1 web:2 chat:3`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DesktopLabels(pub Vec<(String, u32)>);
+
+impl FromStr for DesktopLabels {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<DesktopLabels, ChunkWMError> {
+        let mut labels: Vec<(String, u32)> = Vec::new();
+        for word in s.split_whitespace() {
+            let colon = word
+                .rfind(':')
+                .ok_or(ChunkWMError::ParseError("expected `label:desktop`"))?;
+            let label = check_label(&word[..colon])?;
+            let desktop = word[colon + 1..]
+                .parse()
+                .map_err(|_| ChunkWMError::ParseError("invalid desktop number"))?;
+            if labels.iter().any(|(other, _)| *other == label) {
+                return Err(ChunkWMError::ParseError("a label is given twice"));
+            }
+            labels.push((label, desktop));
+        }
+        Ok(DesktopLabels(labels))
+    }
+}
+
+impl fmt::Display for DesktopLabels {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (label, desktop)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}:{}", label, desktop)?;
+        }
+        Ok(())
+    }
+}
+
+/// The settings of `SpaceLabels`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LabelSettings {
+    /// The labels, and the desktop numbers they start on. Key: `labels`.
+    pub labels: DesktopLabels,
+}
+
+impl FromConfig for LabelSettings {
+    fn from_config(values: &ConfigValues) -> LabelSettings {
+        let default = LabelSettings::default();
+        LabelSettings {
+            labels: values.get("labels", default.labels),
+        }
+    }
+}
+
+/// A label command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelCommand {
+    /// List the labels, with their desktop numbers and spaces. Argument: `--list`.
+    List,
+    /// Get the desktop number of a label. Argument: `--resolve <label>`.
+    Resolve(String),
+    /// Label a desktop, the active desktop of the display under the cursor when no number is
+    /// given. Argument: `--set <label> [desktop]`.
+    Set(String, Option<u32>),
+    /// Remove a label. Argument: `--remove <label>`.
+    Remove(String),
+}
+
+impl FromStr for LabelCommand {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<LabelCommand, ChunkWMError> {
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words.as_slice() {
+            ["--list"] => Ok(LabelCommand::List),
+            ["--resolve", label] => Ok(LabelCommand::Resolve((*label).to_owned())),
+            ["--set", label] => Ok(LabelCommand::Set(check_label(label)?, None)),
+            ["--set", label, desktop] => {
+                let desktop = desktop
+                    .parse()
+                    .map_err(|_| ChunkWMError::ParseError("invalid desktop number"))?;
+                Ok(LabelCommand::Set(check_label(label)?, Some(desktop)))
+            }
+            ["--remove", label] => Ok(LabelCommand::Remove((*label).to_owned())),
+            _ => Err(ChunkWMError::ParseError("unknown label command")),
+        }
+    }
+}
+
+/// A label.
+struct Label {
+    name: String,
+    /// The desktop number the label goes back to when its space is gone.
+    desktop: Option<u32>,
+    space: Option<CGSSpaceID>,
+}
+
+/// Keeps labels on spaces, and resolves them to spaces and desktop numbers.
+pub struct SpaceLabels<S: WindowSystem> {
+    system: S,
+    settings: LabelSettings,
+    command: &'static str,
+    labels: Vec<Label>,
+}
+
+impl<S: WindowSystem> SpaceLabels<S> {
+    /// Create new `SpaceLabels`, answering the `label` command. The labels are put on their
+    /// desktops right away.
+    pub fn new(system: S, settings: LabelSettings) -> SpaceLabels<S> {
+        let mut labels = SpaceLabels {
+            system,
+            settings: LabelSettings::default(),
+            command: "label",
+            labels: Vec::new(),
+        };
+        labels.set_settings(settings);
+        labels
+    }
+
+    /// Answer a different command than `label`.
+    pub fn with_command(mut self, command: &'static str) -> SpaceLabels<S> {
+        self.command = command;
+        self
+    }
+
+    /// Get the window system.
+    pub fn system(&self) -> &S {
+        &self.system
+    }
+
+    /// Get the window system mutably.
+    pub fn system_mut(&mut self) -> &mut S {
+        &mut self.system
+    }
+
+    /// Get the settings.
+    pub fn settings(&self) -> &LabelSettings {
+        &self.settings
+    }
+
+    /// Change the settings. The labels set with commands are dropped, and the configured labels
+    /// are put on their desktops again.
+    pub fn set_settings(&mut self, settings: LabelSettings) {
+        self.labels = settings
+            .labels
+            .0
+            .iter()
+            .map(|(name, desktop)| Label {
+                name: name.clone(),
+                desktop: Some(*desktop),
+                space: None,
+            })
+            .collect();
+        self.settings = settings;
+        // Labels that cannot be placed now are placed by the next display or space event.
+        let _ = self.refresh();
+    }
+
+    /// Get the names of the labels, in the order they were configured or set.
+    pub fn names(&self) -> Vec<&str> {
+        self.labels
+            .iter()
+            .map(|label| label.name.as_str())
+            .collect()
+    }
+
+    /// Get the labels that are on a space, with their spaces.
+    pub fn labels(&self) -> Vec<(CGSSpaceID, &str)> {
+        self.labels
+            .iter()
+            .filter_map(|label| label.space.map(|space| (space, label.name.as_str())))
+            .collect()
+    }
+
+    /// Get the label of a space.
+    pub fn label(&self, space: CGSSpaceID) -> Option<&str> {
+        self.labels
+            .iter()
+            .find(|label| label.space == Some(space))
+            .map(|label| label.name.as_str())
+    }
+
+    /// Get the space of a label, or of a desktop number.
+    pub fn space(&self, name: &str) -> Result<CGSSpaceID, ChunkWMError> {
+        if let Some(label) = self.find(name) {
+            return self.labels[label]
+                .space
+                .ok_or(ChunkWMError::Internal("the label is not on a desktop"));
+        }
+        match name.parse() {
+            Ok(desktop) => self.system.desktop_space(desktop),
+            Err(_) => Err(ChunkWMError::Internal("no such label")),
+        }
+    }
+
+    /// Get the desktop number of a label, or check a desktop number.
+    pub fn desktop(&self, name: &str) -> Result<u32, ChunkWMError> {
+        let space = self.space(name)?;
+        self.system.desktop_id(space)
+    }
+
+    /// Replace the labels in a command message by their desktop numbers, e.g. to pass
+    /// `--focus code` on to a plugin that only knows numbers. Words that are not labels, or
+    /// labels that are not on a desktop, are kept.
+    pub fn resolve_message(&self, message: &str) -> String {
+        message
+            .split_whitespace()
+            .map(|word| match self.find(word) {
+                Some(_) => self
+                    .desktop(word)
+                    .map(|desktop| desktop.to_string())
+                    .unwrap_or_else(|_| word.to_owned()),
+                None => word.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Run the label command, and keep the labels on their spaces when displays or spaces
+    /// change. The reply to the command is sent to the client.
+    ///
+    /// Returns `Ok(true)` when the event was the label command.
+    pub fn handle(&mut self, event: &Event) -> Result<bool, ChunkWMError> {
+        match *event {
+            Event::DaemonCommand(ref payload) => {
+                let command = self.command;
+                return payload.answer(command, |command| self.run(command));
+            }
+            Event::DisplayAdded(_)
+            | Event::DisplayRemoved(_)
+            | Event::DisplayChanged
+            | Event::SpaceChanged => self.refresh()?,
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Run a label command, and get the reply.
+    pub fn run(&mut self, command: LabelCommand) -> Result<String, ChunkWMError> {
+        match command {
+            LabelCommand::List => Ok(self.list()),
+            LabelCommand::Resolve(name) => {
+                self.desktop(&name).map(|desktop| format!("{}\n", desktop))
+            }
+            LabelCommand::Set(name, desktop) => self.set(&name, desktop).map(|_| String::new()),
+            LabelCommand::Remove(name) => match self.find(&name) {
+                Some(index) => {
+                    self.labels.remove(index);
+                    Ok(String::new())
+                }
+                None => Err(ChunkWMError::Internal("no such label")),
+            },
+        }
+    }
+
+    /// Put a label on a desktop, the active desktop of the display under the cursor when no
+    /// number is given. The label the desktop had is removed.
+    pub fn set(&mut self, name: &str, desktop: Option<u32>) -> Result<(), ChunkWMError> {
+        let name = check_label(name)?;
+        let space = match desktop {
+            Some(desktop) => self.system.desktop_space(desktop)?,
+            None => self
+                .system
+                .active_space(cursor_or_first_display(&self.system)?)?,
+        };
+        let desktop = match desktop {
+            Some(desktop) => Some(desktop),
+            None => self.system.desktop_id(space).ok(),
+        };
+
+        self.labels
+            .retain(|label| label.name != name && label.space != Some(space));
+        self.labels.push(Label {
+            name,
+            desktop,
+            space: Some(space),
+        });
+        Ok(())
+    }
+
+    /// Take labels off spaces that are gone, and put labels that are not on a space on their
+    /// desktops, if those exist and have no label.
+    pub fn refresh(&mut self) -> Result<(), ChunkWMError> {
+        let mut spaces = Vec::new();
+        for display in self.system.displays()? {
+            spaces.extend(self.system.spaces(display)?);
+        }
+
+        for label in &mut self.labels {
+            if label.space.is_some_and(|space| !spaces.contains(&space)) {
+                label.space = None;
+            }
+        }
+        for index in 0..self.labels.len() {
+            let desktop = match self.labels[index] {
+                Label {
+                    space: None,
+                    desktop: Some(desktop),
+                    ..
+                } => desktop,
+                _ => continue,
+            };
+            if let Ok(space) = self.system.desktop_space(desktop) {
+                if self.label(space).is_none() {
+                    self.labels[index].space = Some(space);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn list(&self) -> String {
+        self.labels
+            .iter()
+            .map(|label| {
+                let desktop = label
+                    .space
+                    .and_then(|space| self.system.desktop_id(space).ok());
+                format!(
+                    "{}\t{}\t{}\n",
+                    label.name,
+                    or_dash(desktop),
+                    or_dash(label.space)
+                )
+            })
+            .collect()
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.labels.iter().position(|label| label.name == name)
+    }
+}
+
+/// Check that a label can be told apart from a desktop number and from other words.
+fn check_label(label: &str) -> Result<String, ChunkWMError> {
+    if label.is_empty()
+        || label.chars().all(|c| c.is_ascii_digit())
+        || label.chars().any(|c| c.is_whitespace() || c == ':')
+    {
+        Err(ChunkWMError::ParseError("invalid label"))
+    } else {
+        Ok(label.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::SpaceType;
+    use simulator::Simulator;
+    use testing::{pump, rect};
+
+    #[test]
+    fn parse_settings_and_commands() {
+        use self::LabelCommand::*;
+
+        let labels = "code:1 web:2".parse::<DesktopLabels>().unwrap();
+        assert_eq!(
+            labels,
+            DesktopLabels(vec![("code".into(), 1), ("web".into(), 2)])
+        );
+        assert_eq!(labels.to_string(), "code:1 web:2");
+        assert_eq!(
+            "".parse::<DesktopLabels>().unwrap(),
+            DesktopLabels::default()
+        );
+        assert!("code".parse::<DesktopLabels>().is_err());
+        assert!("3:1".parse::<DesktopLabels>().is_err());
+        assert!("code:x".parse::<DesktopLabels>().is_err());
+        assert!("code:1 code:2".parse::<DesktopLabels>().is_err());
+
+        assert_eq!("--list".parse::<LabelCommand>().unwrap(), List);
+        assert_eq!(
+            "--resolve code".parse::<LabelCommand>().unwrap(),
+            Resolve("code".into())
+        );
+        assert_eq!(
+            "--set music 4".parse::<LabelCommand>().unwrap(),
+            Set("music".into(), Some(4))
+        );
+        assert_eq!(
+            "--set music".parse::<LabelCommand>().unwrap(),
+            Set("music".into(), None)
+        );
+        assert!("--set 4".parse::<LabelCommand>().is_err());
+        assert!("--remove".parse::<LabelCommand>().is_err());
+    }
+
+    #[test]
+    fn labels_follow_spaces() {
+        let simulator = Simulator::new();
+        let second = simulator.add_space(1, SpaceType::User).unwrap();
+        simulator.add_space(1, SpaceType::User).unwrap();
+        let settings = LabelSettings {
+            labels: "code:1 web:2 chat:4".parse().unwrap(),
+        };
+        let mut labels = SpaceLabels::new(simulator.clone(), settings);
+        assert_eq!(labels.labels(), vec![(1, "code"), (second, "web")]);
+        assert!(labels.space("chat").is_err());
+
+        // Desktop 4 appears with the second display.
+        let display = simulator.add_display(rect(1920.0, 0.0, 1280.0, 800.0));
+        pump(&simulator, &mut labels);
+        let external = simulator.active_space(display).unwrap();
+        assert_eq!(labels.space("chat").unwrap(), external);
+
+        // Reordering desktops moves the labels with their spaces.
+        simulator.move_space(1, 2).unwrap();
+        assert_eq!(labels.desktop("code").unwrap(), 3);
+        assert_eq!(labels.desktop("web").unwrap(), 1);
+        assert_eq!(labels.desktop("2").unwrap(), 2);
+        assert_eq!(
+            labels.resolve_message("--focus code --swap chat"),
+            "--focus 3 --swap 4"
+        );
+        assert_eq!(
+            labels.run(LabelCommand::List).unwrap(),
+            format!("code\t3\t1\nweb\t1\t{}\nchat\t4\t{}\n", second, external)
+        );
+
+        // The label of a removed space waits for its desktop.
+        simulator.remove_display(display).unwrap();
+        pump(&simulator, &mut labels);
+        assert_eq!(labels.label(external), None);
+        assert_eq!(
+            labels.run(LabelCommand::List).unwrap().lines().last(),
+            Some("chat\t-\t-")
+        );
+        assert_eq!(labels.resolve_message("--focus chat"), "--focus chat");
+    }
+
+    #[test]
+    fn set_and_remove() {
+        let simulator = Simulator::new();
+        let second = simulator.add_space(1, SpaceType::User).unwrap();
+        let settings = LabelSettings {
+            labels: "code:1".parse().unwrap(),
+        };
+        let mut labels = SpaceLabels::new(simulator.clone(), settings);
+
+        labels.run("--set web 2".parse().unwrap()).unwrap();
+        assert_eq!(labels.space("web").unwrap(), second);
+        assert!(labels.run("--set mail 9".parse().unwrap()).is_err());
+
+        // Labelling the active desktop replaces its label.
+        labels.run("--set chat".parse().unwrap()).unwrap();
+        assert_eq!(labels.names(), vec!["web", "chat"]);
+        assert_eq!(labels.label(1), Some("chat"));
+
+        labels.run("--remove web".parse().unwrap()).unwrap();
+        assert_eq!(labels.names(), vec!["chat"]);
+        assert!(labels.run("--remove web".parse().unwrap()).is_err());
+        assert_eq!(
+            labels.run("--resolve chat".parse().unwrap()).unwrap(),
+            "1\n"
+        );
+    }
+}
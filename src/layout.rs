@@ -0,0 +1,621 @@
+//! The `layout` module contains a `LayoutManager`, which remembers where windows are for every
+//! set of connected displays, and puts them back when the same set of displays is connected
+//! again, e.g. when a laptop is docked.
+//!
+//! Displays are told apart by their `DisplayUuid`, which stays the same when a display is
+//! reconnected. For the connected displays, the manager records the frame of every standard
+//! window, relative to the origin of its display, and the space it is on when that is the active
+//! space of its display. When a display is added or removed and the new set of displays has a
+//! saved layout, the windows are moved back; otherwise the current layout is saved for the set.
+//!
+//! Windows are matched by id, or by application and title when the window was opened again, so
+//! layouts survive restarting chunkwm. They are kept in a file, which is written when the set of
+//! displays changes and when `LayoutManager::save` is called. Every line of the file has fields
+//! separated by tabs, shown as spaces here:
+//!
+//! ```text
+//! displays 37D8832A-2D66-02CA-B9F7-8F30A301B230 A8F1C6B4-3C2E-4E4B-9B3E-2F1B2D0C3A11
+//! window 37D8832A-2D66-02CA-B9F7-8F30A301B230 0 25 800 600 3 42 Terminal shell
+//! ```
+//!
+//! Tabs, newlines and backslashes in the fields are escaped with a backslash. A `window` line has
+//! the display, the frame (x, y, width, height), the space (`-` when it is unknown), the window
+//! id, the application and the title.
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::layout::LayoutManager;
+//! use chunkwm::system::WindowSystem;
+//!
+//! fn handle<S: WindowSystem>(
+//!     layouts: &mut LayoutManager<S>,
+//!     event: &Event,
+//! ) -> Result<(), ChunkWMError> {
+//!     layouts.handle(event)?;
+//!     // handle other events
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+
+use animation::apply_frame;
+use display::DisplayUuid;
+use event::{DisplayID, Event};
+use files::{escape, unescape, write_atomically};
+use geometry::{CGFloat, CGPoint, CGRect, CGSize};
+use raw::CGSSpaceID;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use system::WindowSystem;
+use window::Window;
+use ChunkWMError;
+
+/// Where a window is in a layout.
+#[derive(Debug, Clone)]
+pub struct WindowLayout {
+    /// The id of the window.
+    pub id: u32,
+    /// The name of the application of the window.
+    pub application: String,
+    /// The title of the window.
+    pub title: String,
+    /// The UUID of the display the window is on.
+    pub display: DisplayUuid,
+    /// The frame of the window, relative to the origin of the display.
+    pub frame: CGRect,
+    /// The space the window is on, if it is known.
+    pub space: Option<CGSSpaceID>,
+}
+
+/// The saved layouts, by set of display UUIDs.
+#[derive(Debug, Clone, Default)]
+pub struct Layouts {
+    layouts: BTreeMap<Vec<DisplayUuid>, Vec<WindowLayout>>,
+}
+
+impl Layouts {
+    /// Create empty layouts.
+    pub fn new() -> Layouts {
+        Layouts::default()
+    }
+
+    /// Read layouts from a file. A missing file has no layouts.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Layouts, ChunkWMError> {
+        match fs::read_to_string(path) {
+            Ok(source) => source.parse(),
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(Layouts::new()),
+            Err(_) => Err(ChunkWMError::Internal("could not read the layout file")),
+        }
+    }
+
+    /// Write the layouts to a file. The file is replaced at once, so that it is never read half
+    /// written.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ChunkWMError> {
+        write_atomically(path.as_ref(), &self.to_string())
+            .map_err(|_| ChunkWMError::Internal("could not write the layout file"))
+    }
+
+    /// Get the layout of a set of displays. The order of the UUIDs doesn't matter.
+    pub fn get(&self, displays: &[DisplayUuid]) -> Option<&[WindowLayout]> {
+        self.layouts
+            .get(&display_set(displays.to_vec()))
+            .map(Vec::as_slice)
+    }
+
+    /// Set the layout of a set of displays.
+    pub fn set(&mut self, displays: Vec<DisplayUuid>, windows: Vec<WindowLayout>) {
+        self.layouts.insert(display_set(displays), windows);
+    }
+
+    /// Get the sets of displays with a layout, with sorted UUIDs.
+    pub fn display_sets(&self) -> Vec<&[DisplayUuid]> {
+        self.layouts.keys().map(Vec::as_slice).collect()
+    }
+
+    /// Record where a window is in the layout of a set of displays.
+    fn record(&mut self, displays: &[DisplayUuid], window: WindowLayout) {
+        let windows = self.layouts.entry(displays.to_vec()).or_default();
+        match windows.iter_mut().find(|saved| saved.id == window.id) {
+            Some(saved) => *saved = window,
+            None => windows.push(window),
+        }
+    }
+
+    /// Remove the windows a function matches from the layout of a set of displays.
+    fn forget<F: Fn(&WindowLayout) -> bool>(&mut self, displays: &[DisplayUuid], matches: F) {
+        if let Some(windows) = self.layouts.get_mut(displays) {
+            windows.retain(|window| !matches(window));
+        }
+    }
+}
+
+impl FromStr for Layouts {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<Layouts, ChunkWMError> {
+        let mut layouts = Layouts::new();
+        let mut displays: Option<Vec<DisplayUuid>> = None;
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let fields = line
+                .split('\t')
+                .map(unescape)
+                .collect::<Result<Vec<_>, _>>()?;
+            match fields[0].as_str() {
+                "displays" => {
+                    let set = fields[1..]
+                        .iter()
+                        .map(|field| field.parse())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let set = display_set(set);
+                    layouts.layouts.entry(set.clone()).or_default();
+                    displays = Some(set);
+                }
+                "window" => {
+                    let set = displays
+                        .as_ref()
+                        .ok_or(ChunkWMError::ParseError("window before displays"))?;
+                    let window = parse_window(&fields[1..])?;
+                    layouts.record(set, window);
+                }
+                _ => return Err(ChunkWMError::ParseError("unknown layout line")),
+            }
+        }
+        Ok(layouts)
+    }
+}
+
+impl fmt::Display for Layouts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (displays, windows) in &self.layouts {
+            write!(f, "displays")?;
+            for display in displays {
+                write!(f, "\t{}", display)?;
+            }
+            writeln!(f)?;
+            for window in windows {
+                let space = window
+                    .space
+                    .map(|space| space.to_string())
+                    .unwrap_or_else(|| "-".to_owned());
+                writeln!(
+                    f,
+                    "window\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    window.display,
+                    window.frame.origin.x,
+                    window.frame.origin.y,
+                    window.frame.size.width,
+                    window.frame.size.height,
+                    space,
+                    window.id,
+                    escape(&window.application),
+                    escape(&window.title)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Remembers the layout of the windows for every set of displays, and restores it when a set of
+/// displays is connected again.
+pub struct LayoutManager<S: WindowSystem> {
+    system: S,
+    path: PathBuf,
+    layouts: Layouts,
+    displays: Vec<DisplayUuid>,
+}
+
+impl<S: WindowSystem> LayoutManager<S> {
+    /// Load the layouts from a file, which doesn't have to exist. The windows are not moved; when
+    /// the connected displays have no layout yet, the current layout is recorded.
+    pub fn load<P: Into<PathBuf>>(system: S, path: P) -> Result<LayoutManager<S>, ChunkWMError> {
+        let path = path.into();
+        let layouts = Layouts::load(&path)?;
+        let mut manager = LayoutManager {
+            system,
+            path,
+            layouts,
+            displays: Vec::new(),
+        };
+        manager.displays = manager.connected_displays()?;
+        if manager.layouts.get(&manager.displays).is_none() {
+            manager.snapshot()?;
+        }
+        Ok(manager)
+    }
+
+    /// Get the window system.
+    pub fn system(&self) -> &S {
+        &self.system
+    }
+
+    /// Get the window system mutably.
+    pub fn system_mut(&mut self) -> &mut S {
+        &mut self.system
+    }
+
+    /// Get the path of the layout file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the saved layouts.
+    pub fn layouts(&self) -> &Layouts {
+        &self.layouts
+    }
+
+    /// Get the UUIDs of the connected displays, sorted.
+    pub fn display_set(&self) -> &[DisplayUuid] {
+        &self.displays
+    }
+
+    /// Update the layout of the connected displays with an event. When a display is added or
+    /// removed, the layout of the new set of displays is restored, or saved if there is none, and
+    /// the layout file is written.
+    pub fn handle(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        match *event {
+            Event::WindowCreated(ref window)
+            | Event::WindowMoved(ref window)
+            | Event::WindowResized(ref window)
+            | Event::WindowTitleChanged(ref window)
+            | Event::WindowDeminimized(ref window) => self.record(window)?,
+            Event::WindowDestroyed(ref window) => {
+                let id = window.id()?;
+                self.layouts.forget(&self.displays, |saved| saved.id == id);
+            }
+            Event::ApplicationTerminated(ref application) => {
+                let name = application.name()?;
+                self.layouts
+                    .forget(&self.displays, |saved| saved.application == name);
+            }
+            Event::DisplayAdded(_) | Event::DisplayRemoved(_) => {
+                self.displays = self.connected_displays()?;
+                if self.layouts.get(&self.displays).is_some() {
+                    self.restore()?;
+                } else {
+                    self.snapshot()?;
+                }
+                self.save()?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Write the layouts to the layout file.
+    pub fn save(&self) -> Result<(), ChunkWMError> {
+        self.layouts.save(&self.path)
+    }
+
+    /// Move the windows to where they are in the layout of the connected displays. Windows that
+    /// are not in the layout are left alone.
+    pub fn restore(&mut self) -> Result<(), ChunkWMError> {
+        let mut saved = match self.layouts.get(&self.displays) {
+            Some(windows) => windows.to_vec(),
+            None => return Ok(()),
+        };
+        let windows = self.windows()?;
+
+        // Windows that kept their id go first, so that another window with the same title
+        // doesn't take their place.
+        let mut matched = Vec::new();
+        let mut unmatched = Vec::new();
+        for window in windows {
+            let id = window.id()?;
+            match saved.iter().position(|layout| layout.id == id) {
+                Some(index) => matched.push((window, saved.remove(index))),
+                None => unmatched.push(window),
+            }
+        }
+        for window in unmatched {
+            let (application, title) = (window.owner()?.name()?, window.name()?);
+            let index = saved
+                .iter()
+                .position(|layout| layout.application == application && layout.title == title);
+            if let Some(index) = index {
+                matched.push((window, saved.remove(index)));
+            }
+        }
+
+        for (window, layout) in matched {
+            self.place(&window, &layout)?;
+        }
+        Ok(())
+    }
+
+    /// Save the current layout of the windows as the layout of the connected displays.
+    fn snapshot(&mut self) -> Result<(), ChunkWMError> {
+        let mut layouts = Vec::new();
+        for window in self.windows()? {
+            if let Some(layout) = self.window_layout(&window)? {
+                layouts.push(layout);
+            }
+        }
+        self.layouts.set(self.displays.clone(), layouts);
+        Ok(())
+    }
+
+    fn record(&mut self, window: &Window) -> Result<(), ChunkWMError> {
+        if !self.system.is_standard(window)? {
+            return Ok(());
+        }
+        if let Some(layout) = self.window_layout(window)? {
+            self.layouts.record(&self.displays, layout);
+        }
+        Ok(())
+    }
+
+    /// Move a window to its frame and space in a layout, if its display is connected.
+    fn place(&mut self, window: &Window, layout: &WindowLayout) -> Result<(), ChunkWMError> {
+        let display = match self.display_with_uuid(&layout.display)? {
+            Some(display) => display,
+            None => return Ok(()),
+        };
+        let bounds = self.system.display_bounds(display)?;
+        let frame = CGRect::new(
+            &CGPoint::new(
+                bounds.origin.x + layout.frame.origin.x,
+                bounds.origin.y + layout.frame.origin.y,
+            ),
+            &layout.frame.size,
+        );
+        apply_frame(&mut self.system, window, &frame)?;
+        if let Some(space) = layout.space {
+            // The space may be gone.
+            if !self.system.space_has_window(space, window).unwrap_or(true) {
+                let _ = self.system.move_to_space(window, space);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get where a window is, or `None` when it is not on a connected display.
+    fn window_layout(&self, window: &Window) -> Result<Option<WindowLayout>, ChunkWMError> {
+        let display = match self.system.window_display(window) {
+            Ok(display) => display,
+            Err(_) => return Ok(None),
+        };
+        let bounds = self.system.display_bounds(display)?;
+        let frame = window.frame()?;
+        let active_space = self.system.active_space(display)?;
+        let space = if self.system.space_has_window(active_space, window)? {
+            Some(active_space)
+        } else {
+            None
+        };
+        Ok(Some(WindowLayout {
+            id: window.id()?,
+            application: window.owner()?.name()?,
+            title: window.name()?,
+            display: self.system.display_identifier(display)?,
+            frame: CGRect::new(
+                &CGPoint::new(
+                    frame.origin.x - bounds.origin.x,
+                    frame.origin.y - bounds.origin.y,
+                ),
+                &frame.size,
+            ),
+            space,
+        }))
+    }
+
+    /// The standard windows of all applications.
+    fn windows(&self) -> Result<Vec<Window>, ChunkWMError> {
+        let mut windows = Vec::new();
+        for application in self.system.applications()? {
+            for window in self.system.windows(&application)? {
+                if self.system.is_standard(&window)? {
+                    windows.push(window);
+                }
+            }
+        }
+        Ok(windows)
+    }
+
+    fn connected_displays(&self) -> Result<Vec<DisplayUuid>, ChunkWMError> {
+        let uuids = self
+            .system
+            .displays()?
+            .into_iter()
+            .map(|display| self.system.display_identifier(display))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(display_set(uuids))
+    }
+
+    fn display_with_uuid(&self, uuid: &DisplayUuid) -> Result<Option<DisplayID>, ChunkWMError> {
+        for display in self.system.displays()? {
+            if self.system.display_identifier(display)? == *uuid {
+                return Ok(Some(display));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Sort and deduplicate display UUIDs, so that the same displays give the same set.
+fn display_set(mut displays: Vec<DisplayUuid>) -> Vec<DisplayUuid> {
+    displays.sort();
+    displays.dedup();
+    displays
+}
+
+fn parse_window(fields: &[String]) -> Result<WindowLayout, ChunkWMError> {
+    if fields.len() != 9 {
+        return Err(ChunkWMError::ParseError("wrong number of window fields"));
+    }
+    let number = |field: &String| {
+        field
+            .parse::<CGFloat>()
+            .map_err(|_| ChunkWMError::ParseError("invalid window frame"))
+    };
+    let space = match fields[5].as_str() {
+        "-" => None,
+        space => Some(
+            space
+                .parse()
+                .map_err(|_| ChunkWMError::ParseError("invalid window space"))?,
+        ),
+    };
+    Ok(WindowLayout {
+        display: fields[0].parse()?,
+        frame: CGRect::new(
+            &CGPoint::new(number(&fields[1])?, number(&fields[2])?),
+            &CGSize::new(number(&fields[3])?, number(&fields[4])?),
+        ),
+        space,
+        id: fields[6]
+            .parse()
+            .map_err(|_| ChunkWMError::ParseError("invalid window id"))?,
+        application: fields[7].clone(),
+        title: fields[8].clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simulator::{Simulator, WindowSpec};
+    use std::env;
+    use testing::{frame, pump, rect};
+
+    fn temporary_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("chunkwm-layout-{}-{}", name, std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn parse_and_format() {
+        const A: &str = "37D8832A-2D66-02CA-B9F7-8F30A301B230";
+        const B: &str = "A8F1C6B4-3C2E-4E4B-9B3E-2F1B2D0C3A11";
+        let source = format!(
+            "displays\t{b}\t{a}\n\
+             window\t{a}\t0\t25.5\t800\t600\t3\t42\tTerminal\tshell\\tone\n\
+             window\t{b}\t10\t10\t100\t100\t-\t43\tEditor\tnotes\n",
+            a = A,
+            b = B
+        );
+        let layouts = source.parse::<Layouts>().unwrap();
+        let displays = vec![A.parse().unwrap(), B.parse().unwrap()];
+        assert_eq!(layouts.display_sets(), vec![displays.as_slice()]);
+        let windows = layouts
+            .get(&[B.parse().unwrap(), A.parse().unwrap()])
+            .unwrap();
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].title, "shell\tone");
+        assert_eq!(windows[0].frame.origin.y, 25.5);
+        assert_eq!(windows[0].space, Some(3));
+        assert_eq!(windows[1].space, None);
+
+        let formatted = layouts.to_string();
+        assert!(formatted.starts_with(&format!("displays\t{}\t{}\n", A, B)));
+        assert_eq!(formatted.parse::<Layouts>().unwrap().to_string(), formatted);
+
+        let window = format!("window\t{}\t0\t0\t1\t1\t-\t1\ta\tb", A);
+        assert!(window.parse::<Layouts>().is_err());
+        let short = format!("displays\t{a}\nwindow\t{a}\t0", a = A);
+        assert!(short.parse::<Layouts>().is_err());
+        assert!(format!("displays\t{}\\x", A).parse::<Layouts>().is_err());
+        assert!("displays\tA".parse::<Layouts>().is_err());
+        assert!("layout".parse::<Layouts>().is_err());
+    }
+
+    #[test]
+    fn restores_when_displays_return() {
+        let path = temporary_path("restore");
+        let simulator = Simulator::new();
+        simulator.launch(1, "Terminal");
+        simulator.launch(2, "Editor");
+        simulator
+            .create_window(1, WindowSpec::new("shell", rect(0.0, 0.0, 400.0, 200.0)))
+            .unwrap();
+        simulator
+            .create_window(
+                2,
+                WindowSpec::new("notes", rect(100.0, 100.0, 800.0, 600.0)),
+            )
+            .unwrap();
+        simulator.take_events();
+        let mut manager = LayoutManager::load(simulator.clone(), &path).unwrap();
+
+        // Docked: the editor goes to the external display.
+        let external = simulator.add_display(rect(1920.0, 0.0, 2560.0, 1440.0));
+        let identifier = simulator.display_identifier(external).unwrap();
+        pump(&simulator, &mut manager);
+        assert_eq!(manager.display_set().len(), 2);
+        simulator
+            .set_window_frame(2, rect(2020.0, 50.0, 1200.0, 900.0))
+            .unwrap();
+        pump(&simulator, &mut manager);
+
+        // Undocked: the editor ends up on the laptop's display and is moved.
+        simulator.remove_display(external).unwrap();
+        pump(&simulator, &mut manager);
+        assert_eq!(frame(&simulator, 2).0, 100.0);
+        simulator
+            .set_window_frame(2, rect(0.0, 0.0, 1920.0, 1080.0))
+            .unwrap();
+        pump(&simulator, &mut manager);
+
+        // Docked again, with another display id.
+        let external =
+            simulator.add_display_with_identifier(rect(1920.0, 0.0, 2560.0, 1440.0), identifier);
+        pump(&simulator, &mut manager);
+        assert_eq!(frame(&simulator, 2), (2020.0, 50.0, 1200.0, 900.0));
+        assert_eq!(
+            simulator.window_space(2).unwrap(),
+            simulator.active_space(external).unwrap()
+        );
+        assert_eq!(frame(&simulator, 1), (0.0, 0.0, 400.0, 200.0));
+
+        // Undocked again.
+        simulator.remove_display(external).unwrap();
+        pump(&simulator, &mut manager);
+        assert_eq!(frame(&simulator, 2), (0.0, 0.0, 1920.0, 1080.0));
+        assert_eq!(Layouts::load(&path).unwrap().display_sets().len(), 2);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn survives_restarts() {
+        let path = temporary_path("restart");
+        let simulator = Simulator::new();
+        simulator.launch(1, "Terminal");
+        simulator
+            .create_window(1, WindowSpec::new("shell", rect(0.0, 0.0, 400.0, 200.0)))
+            .unwrap();
+        let external = simulator.add_display(rect(1920.0, 0.0, 1280.0, 800.0));
+        let identifier = simulator.display_identifier(external).unwrap();
+        simulator.take_events();
+        {
+            let mut manager = LayoutManager::load(simulator.clone(), &path).unwrap();
+            simulator
+                .set_window_frame(1, rect(2000.0, 100.0, 400.0, 200.0))
+                .unwrap();
+            pump(&simulator, &mut manager);
+            manager.save().unwrap();
+        }
+
+        // The terminal is restarted while undocked, and chunkwm too.
+        simulator.remove_display(external).unwrap();
+        simulator.terminate(1).unwrap();
+        simulator.launch(2, "Terminal");
+        simulator
+            .create_window(2, WindowSpec::new("shell", rect(0.0, 0.0, 400.0, 200.0)))
+            .unwrap();
+        simulator.take_events();
+        let mut manager = LayoutManager::load(simulator.clone(), &path).unwrap();
+        assert_eq!(frame(&simulator, 2).0, 0.0);
+
+        simulator.add_display_with_identifier(rect(1920.0, 0.0, 1280.0, 800.0), identifier);
+        pump(&simulator, &mut manager);
+        assert_eq!(frame(&simulator, 2), (2000.0, 100.0, 400.0, 200.0));
+        fs::remove_file(&path).unwrap();
+    }
+}
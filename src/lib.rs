@@ -8,8 +8,28 @@
 //! - Easy API.
 //! - `CVar` support.
 //! - Subscriptions.
+//! - Cursor tracking, with focus-follows-mouse and mouse-follows-focus helpers.
+//! - A workspace model of applications, windows, spaces and displays, maintained from events.
+//! - Standard query commands (`chunkc plugin::query --windows`), in plain text or JSON.
+//! - Configuration files with `CVar` overrides and live reloading.
+//! - Plugin metadata defaulting to the crate's name and version, and a `chunkwm-rs-version` command.
+//! - A chunkc client for sending commands to chunkwm, with a stand-in daemon for tests.
+//! - Animated window moves and resizes, with easing curves.
+//! - One-shot and repeating timers, delivered as events.
+//! - Per-window and per-application state that is cleaned up automatically.
+//! - A border manager that keeps borders around the focused window, or all visible windows.
+//! - A `WindowSystem` trait for the window operations, so that code using them can run against other backends than the window server.
+//! - A simulated window system, which emits the events chunkwm would, for developing and testing plugins without macOS.
+//! - An i3-style scratchpad, which hides marked windows until they are toggled onto the active display.
+//! - A most-recently-used focus history per display and space, with commands to focus the previous window and cycle alt-tab style.
+//! - Window layouts saved per set of connected displays, and restored when the displays are connected again.
+//! - Desktop labels like `code` and `web`, which follow their spaces and can be resolved in commands and queries.
+//! - A status file for status bars, with the desktops, window counts and focused window as JSON, written atomically and throttled.
+//! - Declarative event filters by application, window role, flag, display and space type, attached to subscriptions and changeable at runtime with `CVar`s.
+//! - Builds and tests on other platforms than macOS, without the `border` and `accessibility` features.
 //! - Border methods (feature `border`, see [Cargo features](#cargo-features)).
 //! - Accessibility methods (feature `accessibility`, see [Cargo features](#cargo-features)).
+//! - Serialization of events and snapshots (feature `serde`, see [Cargo features](#cargo-features)).
 //!
 //! ## Usage
 //!
@@ -20,7 +40,13 @@
 //! chunkwm = { git = "https://github.com/splintah/chunkwm-rs" }
 //! ```
 //!
-//! You then have to compile it as a `cdylib`, and use the `plugin.cpp` file from the [Rust plugin template](https://github.com/splintah/chunkwm-rs-template).
+//! Then compile your plugin as a `cdylib` and export it with `chunkwm_plugin!`; the resulting library can
+//! be loaded by chunkwm directly, no C/C++ code is needed. See `examples/plugin.rs` for a minimal plugin.
+//! `chunkwm::plugin::write_exports_header` generates a C header describing the exported symbols.
+//!
+//! A plugin exported this way answers `chunkc <plugin>::chunkwm-rs-version` with its name and version,
+//! the version of this crate and the API version; ask for the version with this command, rather than
+//! `<plugin>::version`, which is left to the plugin itself.
 //!
 //! If you want to get the `Makefile` template and a small Rust library template, see the [Rust plugin template](https://github.com/splintah/chunkwm-rs-template).
 //!
@@ -31,7 +57,10 @@
 //! - The major version corresponds to the major version of chunkwm;
 //! - the minor version corresponds to the minor version of chunkwm;
 //! - the patch version is the patch version of this particular crate, and thus isn't based on chunkwm's
-//! versioning.
+//!   versioning.
+//!
+//! 0.5.0 is an exception: it breaks the API of 0.4.x while chunkwm is still at 0.4, so the minor version
+//! was bumped ahead of chunkwm's. See the [changelog](https://github.com/splintah/chunkwm-rs/blob/master/CHANGELOG.md).
 //!
 //! You should be able to specify the version in `Cargo.toml` (replacing "MARJOR.MINOR.PATCH" with the desired version, of course):
 //!
@@ -49,7 +78,7 @@
 //!
 //! There are two features that toggle the compilation of the C/C++ library: `border` and `accessibility`:
 //!
-//! - The `border` features gives you access to the `chunkwm::common::border` path.
+//! - The `border` features gives you access to `Border` and `CocoaBackend` in the `chunkwm::common::border` path.
 //! - The `accessibility` features gives you access to the `chunkwm::common::accessibility` path, and enables some extra methods on `Window` and `Application`.
 //!
 //! To use these features:
@@ -59,15 +88,51 @@
 //! chunkwm = { git = "https://github.com/splintah/chunkwm-rs", features = ["border", "accessibility"] }
 //! ```
 //!
-//! You can, of course, only use the features you're interested in.
+//! You can, of course, only use the features you're interested in. Both features need macOS; on other
+//! platforms the crate builds without them, using stand-ins for the CoreGraphics geometry types.
+//!
+//! The `serde` feature implements `Serialize` and `Deserialize` for the owned data types (such as
+//! `WindowFlag`, `SpaceType`, the workspace snapshots and `EventSnapshot`):
+//!
+//! ```toml
+//! [dependencies]
+//! chunkwm = { git = "https://github.com/splintah/chunkwm-rs", features = ["serde"] }
+//! ```
+//!
+//! The target chunkwm plugin API version is selected with the `api-8` (the default) and `api-7`
+//! features. `api-7` takes precedence, so the default feature doesn't have to be disabled:
+//!
+//! ```toml
+//! [dependencies]
+//! chunkwm = { git = "https://github.com/splintah/chunkwm-rs", features = ["api-7"] }
+//! ```
+//!
+//! The layout of the raw types is tested against a C header (`chunkwm-lib/include/chunkwm.h`), which
+//! the tests compile with the system's C compiler.
 //!
 //! [Rust plugin template]: https://github.com/splintah/chunkwm-rs-template
 //! [chunkwm]: https://github.com/koekeishiya/chunkwm
 //! [travis]: https://travis-ci.org/splintah/chunkwm-rs
 //! [docs]: https://splintah.github.io/chunkwm-rs/master/chunkwm/
 
+#[cfg(not(any(feature = "api-7", feature = "api-8")))]
+compile_error!("select a chunkwm plugin API version with the `api-7` or `api-8` feature");
+
+#[cfg(all(
+    not(target_os = "macos"),
+    any(feature = "accessibility", feature = "border")
+))]
+compile_error!("the `accessibility` and `border` features are only available on macOS");
+
+#[cfg(target_os = "macos")]
 extern crate core_foundation;
+#[cfg(target_os = "macos")]
 extern crate core_graphics;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
 /// The error type that is used for functions that return a `Result`.
 #[derive(Debug)]
@@ -80,6 +145,9 @@ pub enum ChunkWMError {
     ParseError(&'static str),
     /// An error occured in a chunkwm function.
     Internal(&'static str),
+    /// The plugin instance cannot be used: it is not initialised, it is already handling a call,
+    /// or it panicked.
+    PluginUnavailable(&'static str),
 }
 
 use std::error::Error;
@@ -90,6 +158,19 @@ impl fmt::Display for ChunkWMError {
     }
 }
 
+impl ChunkWMError {
+    /// The message of the error, without the variant, e.g. for replies to daemon commands.
+    pub fn message(&self) -> &str {
+        match *self {
+            ChunkWMError::NullPointer => "the raw pointer is outlived",
+            ChunkWMError::CVarNotFound(message)
+            | ChunkWMError::ParseError(message)
+            | ChunkWMError::Internal(message)
+            | ChunkWMError::PluginUnavailable(message) => message,
+        }
+    }
+}
+
 impl Error for ChunkWMError {
     fn description(&self) -> &str {
         match *self {
@@ -97,18 +178,45 @@ impl Error for ChunkWMError {
             ChunkWMError::CVarNotFound(_) => "A `CVar` cannot be found.",
             ChunkWMError::ParseError(_) => "Something could not be parsed from a string.",
             ChunkWMError::Internal(_) => "An error occured in a chunkwm function.",
+            ChunkWMError::PluginUnavailable(_) => "The plugin instance cannot be used.",
         }
     }
 }
 
+pub mod animation;
+pub mod borders;
+pub mod client;
 pub mod common;
+pub mod config;
+pub mod filter;
+pub mod focus;
 
 mod bridge;
 pub use bridge::*;
 
+pub mod geometry;
+pub mod labels;
+pub mod layout;
+
 pub mod raw;
 
 #[macro_use]
 mod macros;
 
+pub mod plugin;
 pub mod prelude;
+
+pub mod query;
+pub mod scratchpad;
+pub mod simulator;
+pub mod state;
+pub mod status;
+pub mod system;
+pub mod timer;
+pub mod workspace;
+
+mod files;
+mod json;
+
+#[cfg(test)]
+mod testing;
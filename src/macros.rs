@@ -54,9 +54,33 @@ macro_rules! subscribe_all {
 
 /// The macro that exports the functions and values `ChunkWM` uses.
 ///
-/// **NOTE**: the file name, plugin name and plugin version **must**
-/// - be a byte literal, you can achieve this using `b"string"`;
-/// - contain a `'\0'` at the end.
+/// The macro generates every symbol chunkwm's plugin loader needs (see
+/// `chunkwm::plugin::EXPORTED_SYMBOLS`), so compiling the plugin as a `cdylib` yields a loadable
+/// plugin; no C/C++ code is needed. `chunkwm::plugin::exports_header` describes the exported ABI
+/// in C.
+///
+/// The file name, plugin name, plugin version and API version are optional and default to
+/// `file!()`, `CARGO_PKG_NAME`, `CARGO_PKG_VERSION` and `chunkwm::raw::API_VERSION`. When given,
+/// they must be in this order. The strings are normal string literals (or other constant
+/// expressions): the trailing `'\0'` is appended at compile time, and a string that contains a
+/// `'\0'` is rejected at compile time.
+///
+/// The macro also starts the plugin's timer service (see `chunkwm::timer`) before the plugin is
+/// created, so `new` can already schedule timers, and stops it when the plugin is deinitialised.
+///
+/// The plugin answers the `chunkwm-rs-version` daemon command
+/// (`chunkc plugin::chunkwm-rs-version`) with its name, its version, the version of this crate and
+/// the API version; the command is not passed on to `handle`. Other commands, including a
+/// plugin's own `version` command, are.
+///
+/// The plugin type must be `Send`: chunkwm calls the plugin from more than one thread, and timer
+/// events are delivered from the timer thread. The calls are made one at a time, so `handle` never
+/// runs concurrently with itself (see `chunkwm::plugin::PluginCell`). Keep window and application
+/// ids in the plugin, rather than the `Window` and `Application` handles of events.
+///
+/// The plugin is always subscribed to `WindowDestroyed` and `ApplicationTerminated`, so that its
+/// `tracked_state` is cleaned up. These events only reach `handle` if `subscribe` lists them, and
+/// events only reach `handle` if they pass the plugin's `filters`.
 ///
 /// See [this comment on GitHub](https://github.com/koekeishiya/chunkwm/issues/122#issuecomment-371910155)
 /// for more information.
@@ -69,9 +93,9 @@ macro_rules! subscribe_all {
 ///
 /// chunkwm_plugin!{
 ///     Plugin,
-///     file: b"file name\0",
-///     name: b"plugin name\0",
-///     version: b"plugin version\0"
+///     file: "chunkwm-rs-template/src/lib.rs",
+///     name: "rust_template",
+///     version: "0.1.0",
 /// }
 ///
 /// impl HandleEvent for Plugin {
@@ -83,24 +107,108 @@ macro_rules! subscribe_all {
 /// }
 /// # fn main() {}
 /// ```
+///
+/// Using the defaults:
+/// ```rust
+/// # #[macro_use] extern crate chunkwm;
+/// # use chunkwm::prelude::*;
+/// # pub struct Plugin;
+/// chunkwm_plugin!{ Plugin }
+/// # impl HandleEvent for Plugin {
+/// #     fn new(_: API) -> Self { Plugin }
+/// #     fn handle(&mut self, _: Event) -> Result<(), ChunkWMError> { Ok(()) }
+/// #     fn shutdown(&self) {}
+/// #     subscribe!();
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// A plugin that is not `Send` is rejected:
+/// ```compile_fail
+/// # #[macro_use] extern crate chunkwm;
+/// # use chunkwm::prelude::*;
+/// # use std::rc::Rc;
+/// pub struct Plugin(Rc<u32>);
+/// chunkwm_plugin!{ Plugin }
+/// # impl HandleEvent for Plugin {
+/// #     fn new(_: API) -> Self { Plugin(Rc::new(0)) }
+/// #     fn handle(&mut self, _: Event) -> Result<(), ChunkWMError> { Ok(()) }
+/// #     fn shutdown(&self) {}
+/// #     subscribe!();
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// Embedded NULs are rejected:
+/// ```compile_fail
+/// # #[macro_use] extern crate chunkwm;
+/// # use chunkwm::prelude::*;
+/// # pub struct Plugin;
+/// chunkwm_plugin!{ Plugin, name: "plugin\0name" }
+/// # impl HandleEvent for Plugin {
+/// #     fn new(_: API) -> Self { Plugin }
+/// #     fn handle(&mut self, _: Event) -> Result<(), ChunkWMError> { Ok(()) }
+/// #     fn shutdown(&self) {}
+/// #     subscribe!();
+/// # }
+/// # fn main() {}
+/// ```
 #[macro_export]
 macro_rules! chunkwm_plugin {
+    (@or [] $default:expr) => { $default };
+    (@or [$value:expr] $default:expr) => { $value };
+    {
+        $struct_ident:ident
+        $(, file: $file_name:expr)?
+        $(, name: $plugin_name:expr)?
+        $(, version: $plugin_version:expr)?
+        $(, api_version: $api_version:expr)?
+        $(,)?
+    } => {
+        chunkwm_plugin! {
+            @export $struct_ident,
+            file: chunkwm_plugin!(@or [$($file_name)?] file!()),
+            name: chunkwm_plugin!(@or [$($plugin_name)?] env!("CARGO_PKG_NAME")),
+            version: chunkwm_plugin!(@or [$($plugin_version)?] env!("CARGO_PKG_VERSION")),
+            api_version: chunkwm_plugin!(@or [$($api_version)?] chunkwm::raw::API_VERSION)
+        }
+    };
     {
-        $struct_ident:ident,
+        @export $struct_ident:ident,
         file: $file_name:expr,
         name: $plugin_name:expr,
-        version: $plugin_version:expr
+        version: $plugin_version:expr,
+        api_version: $api_version:expr
 } => {pub mod __export {
         extern crate chunkwm;
         use super::$struct_ident;
+        use chunkwm::plugin::PluginCell;
         use chunkwm::prelude::*;
         use chunkwm::raw::{ChunkWMPlugin, ChunkWMPluginDetails};
         use std::ffi;
-        use std::os::raw::{c_char, c_uint, c_void};
+        use std::os::raw::{c_char, c_int, c_uint, c_void};
+
+        const FILE_NAME: &str = $file_name;
+        const PLUGIN_NAME: &str = $plugin_name;
+        const PLUGIN_VERSION: &str = $plugin_version;
+        const API_VERSION: c_int = $api_version;
+
+        static FILE_NAME_C: [u8; FILE_NAME.len() + 1] =
+            chunkwm::plugin::nul_terminated(FILE_NAME);
+        static PLUGIN_NAME_C: [u8; PLUGIN_NAME.len() + 1] =
+            chunkwm::plugin::nul_terminated(PLUGIN_NAME);
+        static PLUGIN_VERSION_C: [u8; PLUGIN_VERSION.len() + 1] =
+            chunkwm::plugin::nul_terminated(PLUGIN_VERSION);
+
+        const METADATA: chunkwm::plugin::PluginMetadata = chunkwm::plugin::PluginMetadata {
+            name: PLUGIN_NAME,
+            version: PLUGIN_VERSION,
+            api_version: API_VERSION,
+        };
 
-        static mut PLUGIN: Option<$struct_ident> = None;
+        static PLUGIN: PluginCell<$struct_ident> = PluginCell::new();
 
-        pub extern "C" fn chunkwm_plugin_main(node: *const c_char, data: *mut c_void) -> bool {
+        extern "C" fn chunkwm_plugin_main(node: *const c_char, data: *mut c_void) -> bool {
             use chunkwm::event::DisplayID;
             let event = unsafe { ffi::CStr::from_ptr(node).to_string_lossy().into_owned() };
 
@@ -138,59 +246,94 @@ macro_rules! chunkwm_plugin {
                 },
                 "chunkwm_export_display_changed" => Event::DisplayChanged,
                 "chunkwm_export_space_changed" => Event::SpaceChanged,
-                "chunkwm_daemon_command" => Event::DaemonCommand(data.into()),
-                _ => Event::Other(event),
-            };
-
-            unsafe {
-                if let Some(ref mut plugin) = PLUGIN {
-                    match plugin.handle(event) {
-                        Ok(_) => true,
+                "chunkwm_daemon_command" => {
+                    let payload = Payload::from(data);
+                    match METADATA.handle_version_command(&payload) {
+                        Ok(true) => return true,
+                        Ok(false) => Event::DaemonCommand(payload),
                         Err(e) => {
                             eprintln!("Error: {}", e);
-                            false
+                            return false;
                         }
                     }
-                } else {
-                    eprintln!("Error: plugin was not initialised.");
-                    return false;
+                }
+                _ => Event::Other(event),
+            };
+
+            // The cleanup events are delivered even if the plugin didn't subscribe to them.
+            let subscribed = event
+                .subscription()
+                .map_or(true, |s| $struct_ident::subscribe().contains(&s));
+            let cleanup = chunkwm::state::Cleanup::from_event(&event);
+            let result = PLUGIN.with(|plugin| {
+                let passes = subscribed
+                    && plugin
+                        .filters()
+                        .map_or(true, |(filters, system)| filters.matches(&event, system));
+                let result = if passes { plugin.handle(event) } else { Ok(()) };
+                if let Some(cleanup) = cleanup {
+                    cleanup.apply(&mut plugin.tracked_state());
+                }
+                result
+            });
+            match result {
+                Ok(Ok(_)) => true,
+                Ok(Err(e)) | Err(e) => {
+                    eprintln!("Error: {}", e);
+                    false
                 }
             }
         }
 
-        pub extern "C" fn chunkwm_plugin_init(api: API) -> bool {
-            unsafe {
-                PLUGIN = Some($struct_ident::new(api));
+        extern "C" fn chunkwm_plugin_init(api: API) -> bool {
+            // Started first, so that `new` can schedule timers.
+            chunkwm::timer::start(|id| {
+                match PLUGIN.with(|plugin| plugin.handle(Event::Timer(id))) {
+                    Ok(Ok(_)) => {}
+                    Ok(Err(e)) | Err(e) => eprintln!("Error: {}", e),
+                }
+            });
+            match PLUGIN.init(|| $struct_ident::new(api)) {
+                Ok(_) => true,
+                Err(e) => {
+                    chunkwm::timer::stop();
+                    eprintln!("Error: {}", e);
+                    false
+                }
             }
-            true
         }
 
-        pub extern "C" fn chunkwm_plugin_deinit() {
-            unsafe {
-                if let Some(ref plugin) = PLUGIN {
-                    plugin.shutdown();
-                }
+        extern "C" fn chunkwm_plugin_deinit() {
+            chunkwm::timer::stop();
+            if let Err(e) = PLUGIN.deinit(|plugin| plugin.shutdown()) {
+                eprintln!("Error: {}", e);
             }
         }
 
-        pub unsafe extern "C" fn chunkwm_init_plugin_vtable(plugin: *mut ChunkWMPlugin) {
+        unsafe extern "C" fn chunkwm_init_plugin_vtable(plugin: *mut ChunkWMPlugin) {
             (*plugin).init = chunkwm_plugin_init;
             (*plugin).deinit = chunkwm_plugin_deinit;
             (*plugin).run = chunkwm_plugin_main;
         }
 
-        pub unsafe extern "C" fn chunkwm_init_plugin_subscriptions(plugin: *mut ChunkWMPlugin) {
-            (*plugin).subscriptions = $struct_ident::subscribe().as_ptr();
-            (*plugin).subscription_count = $struct_ident::subscribe().len() as c_uint;
+        unsafe extern "C" fn chunkwm_init_plugin_subscriptions(plugin: *mut ChunkWMPlugin) {
+            let subscriptions =
+                chunkwm::state::cleanup_subscriptions($struct_ident::subscribe());
+            // Called once; chunkwm uses the subscriptions for as long as the plugin is loaded.
+            let subscriptions: &'static [Subscription] =
+                Box::leak(subscriptions.into_boxed_slice());
+            (*plugin).subscriptions = subscriptions.as_ptr();
+            (*plugin).subscription_count = subscriptions.len() as c_uint;
         }
 
+        /// The plugin's vtable; `Exports.initialize` in the loader.
         #[no_mangle]
         pub extern "C" fn GetPlugin() -> *mut ChunkWMPlugin {
-            use std::sync::{Once, ONCE_INIT};
-            static INIT: Once = ONCE_INIT;
+            use std::ptr;
+            use std::sync::Once;
+            static INIT: Once = Once::new();
 
             static mut SINGLETON: ChunkWMPlugin = {
-                use std::ptr;
                 extern "C" fn _init(_api: API) -> bool {
                     false
                 }
@@ -208,15 +351,17 @@ macro_rules! chunkwm_plugin {
             };
 
             unsafe {
+                let singleton = ptr::addr_of_mut!(SINGLETON);
                 INIT.call_once(|| {
-                    chunkwm_init_plugin_vtable(&mut SINGLETON);
-                    chunkwm_init_plugin_subscriptions(&mut SINGLETON);
+                    chunkwm_init_plugin_vtable(singleton);
+                    chunkwm_init_plugin_subscriptions(singleton);
                 });
 
-                &mut SINGLETON
+                singleton
             }
         }
 
+        /// The plugin details chunkwm's loader looks up.
         #[allow(non_upper_case_globals)]
         #[no_mangle]
         pub static Exports: ChunkWMPluginDetails = ChunkWMPluginDetails {
@@ -228,10 +373,10 @@ macro_rules! chunkwm_plugin {
                 b'p' as c_char,
                 b'l' as c_char,
             ],
-            api_version: 8,
-            file_name: $file_name as *const u8,
-            plugin_name: $plugin_name as *const u8,
-            plugin_version: $plugin_version as *const u8,
+            api_version: API_VERSION,
+            file_name: &FILE_NAME_C as *const _ as *const u8,
+            plugin_name: &PLUGIN_NAME_C as *const _ as *const u8,
+            plugin_version: &PLUGIN_VERSION_C as *const _ as *const u8,
             initialize: GetPlugin,
         };
     }};
@@ -0,0 +1,473 @@
+//! The `plugin` module contains the `PluginCell`, the container `chunkwm_plugin!` stores the
+//! plugin instance in.
+//!
+//! chunkwm calls into the plugin through C functions, so the instance has to live in a `static`.
+//! The `PluginCell` makes sure there is never more than one `&mut` to the instance:
+//!
+//! - calls from other threads wait until the current call has finished;
+//! - a nested call on the same thread (e.g. when `handle` causes chunkwm to call back into the
+//!   plugin) is rejected, instead of aliasing the instance;
+//! - a panic in the plugin is caught, and poisons the cell: later calls are rejected until the
+//!   plugin is initialised again;
+//! - the instance is dropped when the plugin is deinitialised.
+//!
+//! The module also contains the helpers `chunkwm_plugin!` uses for the plugin's metadata, and a
+//! description of the symbols it exports.
+
+use bridge::payload::Payload;
+use raw::API_VERSION;
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::os::raw::c_int;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use ChunkWMError;
+
+/// The version of this crate.
+pub const CRATE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The daemon command `chunkwm_plugin!` answers with the plugin's version. It is prefixed with
+/// the name of this crate, so that the plugin's own commands, such as a `version` command, still
+/// reach `handle`.
+pub const VERSION_COMMAND: &str = "chunkwm-rs-version";
+
+thread_local! {
+    /// The cells that are being accessed on this thread.
+    static ACTIVE: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// The container for a plugin instance.
+///
+/// chunkwm calls the plugin from more than one thread, and timers are delivered from the timer
+/// thread, so the instance is behind a `Mutex`. A cell can only be shared between threads (e.g.
+/// in a `static`) when the plugin type is `Send`: the instance is moved between threads, one call
+/// at a time.
+pub struct PluginCell<T> {
+    plugin: Mutex<Option<T>>,
+    poisoned: AtomicBool,
+}
+
+/// Marks a cell as active on this thread for as long as it lives.
+struct Active(usize);
+
+impl Active {
+    fn enter(address: usize) -> Result<Active, ChunkWMError> {
+        ACTIVE.with(|active| {
+            let mut active = active.borrow_mut();
+            if active.contains(&address) {
+                return Err(ChunkWMError::PluginUnavailable(
+                    "the plugin is already handling a call on this thread",
+                ));
+            }
+            active.push(address);
+            Ok(Active(address))
+        })
+    }
+}
+
+impl Drop for Active {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| active.borrow_mut().retain(|&a| a != self.0));
+    }
+}
+
+impl<T> Default for PluginCell<T> {
+    fn default() -> PluginCell<T> {
+        PluginCell::new()
+    }
+}
+
+impl<T> PluginCell<T> {
+    /// Create an empty cell.
+    pub const fn new() -> PluginCell<T> {
+        PluginCell {
+            plugin: Mutex::new(None),
+            poisoned: AtomicBool::new(false),
+        }
+    }
+
+    /// Create the instance, replacing (and dropping) the previous instance. A panic while creating
+    /// the instance leaves the cell empty.
+    ///
+    /// Calls from other threads wait until the instance has been created, so that e.g. a timer
+    /// scheduled while creating the instance is delivered to it.
+    pub fn init<F: FnOnce() -> T>(&self, f: F) -> Result<(), ChunkWMError> {
+        let _active = Active::enter(self.address())?;
+        let mut guard = self.lock();
+        let plugin = panic::catch_unwind(AssertUnwindSafe(f)).map_err(|_| {
+            ChunkWMError::PluginUnavailable("the plugin panicked while initialising")
+        })?;
+
+        let previous = guard.replace(plugin);
+        self.poisoned.store(false, Ordering::SeqCst);
+        drop(guard);
+        drop(previous);
+        Ok(())
+    }
+
+    /// Call a function with the instance.
+    pub fn with<R, F: FnOnce(&mut T) -> R>(&self, f: F) -> Result<R, ChunkWMError> {
+        let _active = Active::enter(self.address())?;
+        let mut plugin = self.lock();
+        if self.is_poisoned() {
+            return Err(ChunkWMError::PluginUnavailable(
+                "the plugin panicked during an earlier call",
+            ));
+        }
+        let plugin = match *plugin {
+            Some(ref mut plugin) => plugin,
+            None => {
+                return Err(ChunkWMError::PluginUnavailable(
+                    "the plugin was not initialised",
+                ))
+            }
+        };
+
+        panic::catch_unwind(AssertUnwindSafe(|| f(plugin))).map_err(|_| {
+            self.poisoned.store(true, Ordering::SeqCst);
+            ChunkWMError::PluginUnavailable("the plugin panicked")
+        })
+    }
+
+    /// Take the instance out of the cell, call `f` with it (unless the cell is poisoned), and drop
+    /// it. Returns whether there was an instance.
+    pub fn deinit<F: FnOnce(&mut T)>(&self, f: F) -> Result<bool, ChunkWMError> {
+        let _active = Active::enter(self.address())?;
+        let plugin = self.lock().take();
+        let poisoned = self.poisoned.swap(false, Ordering::SeqCst);
+
+        match plugin {
+            Some(mut plugin) => {
+                let result = panic::catch_unwind(AssertUnwindSafe(move || {
+                    if !poisoned {
+                        f(&mut plugin);
+                    }
+                    drop(plugin);
+                }));
+                result.map(|_| true).map_err(|_| {
+                    ChunkWMError::PluginUnavailable("the plugin panicked during shutdown")
+                })
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Check whether the plugin panicked.
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::SeqCst)
+    }
+
+    /// Check whether the cell contains an instance.
+    pub fn is_initialised(&self) -> bool {
+        self.lock().is_some()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<T>> {
+        // Panics are caught while the lock is held, so the lock itself is never poisoned.
+        self.plugin.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn address(&self) -> usize {
+        self as *const PluginCell<T> as usize
+    }
+}
+
+/// The symbols `chunkwm_plugin!` exports. chunkwm's loader looks up `Exports`, which points to
+/// `GetPlugin`.
+pub const EXPORTED_SYMBOLS: &[&str] = &["Exports", "GetPlugin"];
+
+/// The C declarations of the types chunkwm's loader uses.
+const CHUNKWM_HEADER: &str = include_str!("../chunkwm-lib/include/chunkwm.h");
+
+/// Generate a C header that describes the ABI a `chunkwm_plugin!` plugin exports, for the API
+/// version selected with the `api-7` and `api-8` features.
+pub fn exports_header() -> String {
+    format!(
+        "/* Generated by chunkwm-rs {}. */\n\
+         #ifndef CHUNKWM_RS_PLUGIN_H\n\
+         #define CHUNKWM_RS_PLUGIN_H\n\
+         \n\
+         #define CHUNKWM_PLUGIN_API_VERSION {}\n\
+         \n\
+         {}\n\
+         extern struct plugin_details {};\n\
+         plugin *{}(void);\n\
+         \n\
+         #endif\n",
+        CRATE_VERSION, API_VERSION, CHUNKWM_HEADER, EXPORTED_SYMBOLS[0], EXPORTED_SYMBOLS[1]
+    )
+}
+
+/// Write the header generated by `exports_header` to a file, e.g. from a build script.
+pub fn write_exports_header<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    fs::write(path, exports_header())
+}
+
+/// Append a NUL to a string, for the C strings in the plugin details. When used in a constant,
+/// a string that already contains a NUL is rejected at compile time.
+///
+/// `N` must be the length of the string plus one.
+pub const fn nul_terminated<const N: usize>(s: &str) -> [u8; N] {
+    let bytes = s.as_bytes();
+    assert!(
+        bytes.len() + 1 == N,
+        "N must be the length of the string plus one"
+    );
+    let mut terminated = [0; N];
+    let mut i = 0;
+    while i < bytes.len() {
+        assert!(bytes[i] != 0, "plugin metadata must not contain NUL bytes");
+        terminated[i] = bytes[i];
+        i += 1;
+    }
+    terminated
+}
+
+/// The metadata of a plugin, as exported by `chunkwm_plugin!`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PluginMetadata {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub api_version: c_int,
+}
+
+impl PluginMetadata {
+    /// The response to the `chunkwm-rs-version` daemon command, e.g.
+    /// `border 0.1.0 (chunkwm-rs 0.5.0, API version 8)`.
+    pub fn version_string(&self) -> String {
+        format!(
+            "{} {} (chunkwm-rs {}, API version {})\n",
+            self.name, self.version, CRATE_VERSION, self.api_version
+        )
+    }
+
+    /// Answer the `chunkwm-rs-version` daemon command. Returns whether the payload was that
+    /// command; other commands are left to the plugin.
+    pub fn handle_version_command(&self, payload: &Payload) -> Result<bool, ChunkWMError> {
+        if payload.command()? != VERSION_COMMAND {
+            return Ok(false);
+        }
+        payload.respond(self.version_string())?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    struct Counter {
+        count: u32,
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn counter() -> (Counter, Arc<AtomicBool>) {
+        let dropped = Arc::new(AtomicBool::new(false));
+        let counter = Counter {
+            count: 0,
+            dropped: dropped.clone(),
+        };
+        (counter, dropped)
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        fn assert_sync<T: Sync>() {}
+        assert_sync::<PluginCell<Counter>>();
+    }
+
+    #[test]
+    fn uninitialised() {
+        let cell: PluginCell<u32> = PluginCell::new();
+        assert!(cell.with(|_| ()).is_err());
+        assert!(!cell.deinit(|_| ()).unwrap());
+    }
+
+    #[test]
+    fn dispatch() {
+        let cell = PluginCell::new();
+        cell.init(|| 1u32).unwrap();
+        assert_eq!(
+            cell.with(|n| {
+                *n += 1;
+                *n
+            })
+            .unwrap(),
+            2
+        );
+        assert!(cell.is_initialised());
+    }
+
+    #[test]
+    fn reentrant_dispatch_is_rejected() {
+        static CELL: PluginCell<u32> = PluginCell::new();
+        CELL.init(|| 0).unwrap();
+
+        let nested = CELL
+            .with(|n| {
+                *n += 1;
+                // E.g. `handle` causes chunkwm to call the plugin again.
+                match CELL.with(|n| *n += 1) {
+                    Err(ChunkWMError::PluginUnavailable(_)) => {}
+                    _ => panic!("re-entrant call was not rejected"),
+                }
+                assert!(CELL.deinit(|_| ()).is_err());
+                *n
+            })
+            .unwrap();
+
+        assert_eq!(nested, 1);
+        // The cell can be used again after the outer call has finished.
+        assert_eq!(CELL.with(|n| *n).unwrap(), 1);
+    }
+
+    #[test]
+    fn other_threads_wait() {
+        let cell = Arc::new(PluginCell::new());
+        cell.init(|| 0u32).unwrap();
+
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let cell = cell.clone();
+                thread::spawn(move || {
+                    for _ in 0..100 {
+                        cell.with(|n| *n += 1).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        assert_eq!(cell.with(|n| *n).unwrap(), 400);
+    }
+
+    #[test]
+    fn other_threads_wait_for_init() {
+        let cell = Arc::new(PluginCell::new());
+        let (started, wait) = mpsc::channel();
+
+        let reader = {
+            let cell = cell.clone();
+            thread::spawn(move || {
+                started.send(()).unwrap();
+                // E.g. a timer that expires while the plugin is being created.
+                cell.with(|n| *n)
+            })
+        };
+        cell.init(|| {
+            wait.recv().unwrap();
+            thread::sleep(Duration::from_millis(20));
+            7u32
+        })
+        .unwrap();
+
+        assert_eq!(reader.join().unwrap().unwrap(), 7);
+    }
+
+    #[test]
+    fn panic_poisons() {
+        let cell = PluginCell::new();
+        let (plugin, dropped) = counter();
+        cell.init(move || plugin).unwrap();
+
+        let result = cell.with(|_| panic!("bug in the plugin"));
+
+        assert!(result.is_err());
+        assert!(cell.is_poisoned());
+        assert!(cell.with(|c| c.count).is_err());
+
+        // Shutdown is skipped for a poisoned plugin, but it is still dropped.
+        let mut shutdown = false;
+        assert!(cell.deinit(|_| shutdown = true).unwrap());
+        assert!(!shutdown);
+        assert!(dropped.load(Ordering::SeqCst));
+        assert!(!cell.is_poisoned());
+
+        // The plugin can be initialised again.
+        let (plugin, _) = counter();
+        cell.init(move || plugin).unwrap();
+        assert_eq!(cell.with(|c| c.count).unwrap(), 0);
+    }
+
+    #[test]
+    fn nul_terminate() {
+        const NAME: &str = "border";
+        static TERMINATED: [u8; NAME.len() + 1] = nul_terminated(NAME);
+        assert_eq!(&TERMINATED, b"border\0");
+    }
+
+    #[test]
+    fn version_command() {
+        use raw::{PayloadRef, RawPayload};
+        use std::io::Read;
+        use std::os::unix::io::AsRawFd;
+        use std::os::unix::net::UnixStream;
+
+        let metadata = PluginMetadata {
+            name: "border",
+            version: "0.1.0",
+            api_version: 8,
+        };
+        let (daemon, mut client) = UnixStream::pair().unwrap();
+        let mut raw = RawPayload {
+            sock_fd: daemon.as_raw_fd(),
+            command: b"query\0".as_ptr() as *const _,
+            message: b"\0".as_ptr() as *const _,
+        };
+        assert!(!metadata
+            .handle_version_command(&Payload::from(&mut raw as PayloadRef))
+            .unwrap());
+
+        raw.command = b"version\0".as_ptr() as *const _;
+        assert!(!metadata
+            .handle_version_command(&Payload::from(&mut raw as PayloadRef))
+            .unwrap());
+
+        raw.command = b"chunkwm-rs-version\0".as_ptr() as *const _;
+        assert!(metadata
+            .handle_version_command(&Payload::from(&mut raw as PayloadRef))
+            .unwrap());
+        drop(daemon);
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(
+            response,
+            format!(
+                "border 0.1.0 (chunkwm-rs {}, API version 8)\n",
+                CRATE_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn deinit_drops() {
+        let cell = PluginCell::new();
+        let (plugin, dropped) = counter();
+        cell.init(move || plugin).unwrap();
+        cell.with(|c| c.count += 1).unwrap();
+
+        let mut count = 0;
+        assert!(cell.deinit(|c| count = c.count).unwrap());
+        assert_eq!(count, 1);
+        assert!(dropped.load(Ordering::SeqCst));
+        assert!(!cell.is_initialised());
+        assert!(cell.with(|_| ()).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+//! The `query` module contains a standard set of daemon commands that report the state of the
+//! `WorkspaceModel`, so that every plugin can be inspected in the same way.
+//!
+//! The commands are opt-in: create a `QueryCommands` and pass daemon commands to it. The reply is
+//! written to the socket of the client, in plain text (tab separated, one item per line) or in
+//! JSON when `--json` is given. Tabs, newlines and backslashes in names and values of the plain
+//! text are escaped with a backslash (`\t`, `\n`, `\r` and `\\`), and errors are replied as a
+//! single line with the message.
+//!
+//! ```sh
+//! chunkc plugin::query --windows
+//! chunkc plugin::query --focused --json
+//! chunkc plugin::query --cvars
+//! ```
+//!
+//! The available queries are `--windows`, `--focused`, `--applications`, `--displays`, `--spaces`
+//! and `--cvars`.
+//!
+//! ## Example
+//! ```rust
+//! # #[macro_use] extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::query::QueryCommands;
+//! use chunkwm::workspace::WorkspaceModel;
+//!
+//! pub struct Plugin {
+//!     api: API,
+//!     model: WorkspaceModel,
+//!     queries: QueryCommands,
+//! }
+//!
+//! impl HandleEvent for Plugin {
+//!     fn new(api: API) -> Plugin {
+//!         Plugin {
+//!             api,
+//!             model: WorkspaceModel::new(),
+//!             queries: QueryCommands::new().cvar("plugin_gap"),
+//!         }
+//!     }
+//!
+//!     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+//!         self.model.handle(&event);
+//!         if self.queries.handle(&event, &self.model, &self.api)? {
+//!             return Ok(());
+//!         }
+//!         // handle other events and commands
+//!         Ok(())
+//!     }
+//!     // some methods omitted
+//!     # subscribe_all!();
+//!     # fn shutdown(&self) {}
+//! }
+//! # fn main() {}
+//! ```
+
+use api::API;
+use event::Event;
+use files::escape;
+use json::Json;
+use workspace::{DisplayInfo, WindowInfo, WorkspaceModel};
+use ChunkWMError;
+
+/// A query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Query {
+    /// All windows.
+    Windows,
+    /// The focused window.
+    Focused,
+    /// All applications.
+    Applications,
+    /// All displays.
+    Displays,
+    /// All known spaces.
+    Spaces,
+    /// The `CVar`s registered with [`QueryCommands::cvar`](./struct.QueryCommands.html#method.cvar).
+    CVars,
+}
+
+/// The output format of a query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Tab separated fields, one item per line.
+    Plain,
+    /// A JSON document.
+    Json,
+}
+
+/// The `QueryCommands` answers the standard query commands.
+#[derive(Debug, Clone)]
+pub struct QueryCommands {
+    command: &'static str,
+    cvars: Vec<&'static str>,
+}
+
+impl Default for QueryCommands {
+    fn default() -> QueryCommands {
+        QueryCommands::new()
+    }
+}
+
+impl QueryCommands {
+    /// Create a new `QueryCommands`, answering the `query` command.
+    pub fn new() -> QueryCommands {
+        QueryCommands {
+            command: "query",
+            cvars: Vec::new(),
+        }
+    }
+
+    /// Answer a different command than `query`.
+    pub fn with_command(mut self, command: &'static str) -> QueryCommands {
+        self.command = command;
+        self
+    }
+
+    /// Report a `CVar` in the `--cvars` query.
+    pub fn cvar(mut self, name: &'static str) -> QueryCommands {
+        self.cvars.push(name);
+        self
+    }
+
+    /// Get the command that is answered.
+    pub fn command(&self) -> &'static str {
+        self.command
+    }
+
+    /// Answer a daemon command, and send the reply to the client.
+    ///
+    /// Returns `Ok(false)` when the event is not a query command, so that the plugin can handle it
+    /// itself.
+    pub fn handle(
+        &self,
+        event: &Event,
+        model: &WorkspaceModel,
+        api: &API,
+    ) -> Result<bool, ChunkWMError> {
+        let payload = match *event {
+            Event::DaemonCommand(ref payload) => payload,
+            _ => return Ok(false),
+        };
+
+        let cvar = |name: &str| {
+            if api.cvar_exists(name) {
+                api.get_cvar::<String>(name).ok()
+            } else {
+                None
+            }
+        };
+        match self.answer(&payload.command()?, &payload.message()?, model, cvar) {
+            Some(reply) => payload.reply(reply).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    /// Answer a command without sending the reply. Returns `None` when the command is not a query
+    /// command. `cvar` looks up the value of a `CVar`.
+    pub fn answer<F>(
+        &self,
+        command: &str,
+        message: &str,
+        model: &WorkspaceModel,
+        cvar: F,
+    ) -> Option<Result<String, ChunkWMError>>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if command != self.command {
+            return None;
+        }
+
+        Some(parse(message).map(|(query, format)| match format {
+            Format::Plain => self.plain(query, model, &cvar),
+            Format::Json => format!("{}\n", self.json(query, model, &cvar)),
+        }))
+    }
+
+    fn plain<F>(&self, query: Query, model: &WorkspaceModel, cvar: &F) -> String
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let lines: Vec<String> = match query {
+            Query::Windows => model
+                .windows()
+                .map(|window| plain_window(model, window))
+                .collect(),
+            Query::Focused => model
+                .focused_window()
+                .map(|window| plain_window(model, window))
+                .into_iter()
+                .collect(),
+            Query::Applications => model
+                .applications()
+                .map(|app| format!("{}\t{}\t{}", app.pid, escape(&app.name), app.hidden))
+                .collect(),
+            Query::Displays => model.displays().map(plain_display).collect(),
+            Query::Spaces => model
+                .spaces()
+                .map(|space| {
+                    let active = model
+                        .display(space.display)
+                        .map(|display| display.active_space == Some(space.id))
+                        .unwrap_or(false);
+                    format!(
+                        "{}\t{}\t{}\t{}",
+                        space.id,
+                        space.display,
+                        active,
+                        or_dash(model.space_label(space.id).map(escape))
+                    )
+                })
+                .collect(),
+            Query::CVars => self
+                .cvars
+                .iter()
+                .map(|name| {
+                    let value = cvar(name).unwrap_or_default();
+                    format!("{}\t{}", escape(name), escape(&value))
+                })
+                .collect(),
+        };
+
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    fn json<F>(&self, query: Query, model: &WorkspaceModel, cvar: &F) -> Json
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        match query {
+            Query::Windows => Json::Array(
+                model
+                    .windows()
+                    .map(|window| json_window(model, window))
+                    .collect(),
+            ),
+            Query::Focused => model
+                .focused_window()
+                .map(|window| json_window(model, window))
+                .unwrap_or(Json::Null),
+            Query::Applications => Json::Array(
+                model
+                    .applications()
+                    .map(|app| {
+                        Json::object(vec![
+                            ("pid", app.pid.into()),
+                            ("name", app.name.as_str().into()),
+                            ("hidden", app.hidden.into()),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Query::Displays => Json::Array(model.displays().map(json_display).collect()),
+            Query::Spaces => Json::Array(
+                model
+                    .spaces()
+                    .map(|space| {
+                        let active = model
+                            .display(space.display)
+                            .map(|display| display.active_space == Some(space.id))
+                            .unwrap_or(false);
+                        Json::object(vec![
+                            ("id", space.id.into()),
+                            ("display", space.display.into()),
+                            ("active", active.into()),
+                            ("label", model.space_label(space.id).into()),
+                        ])
+                    })
+                    .collect(),
+            ),
+            Query::CVars => Json::Object(
+                self.cvars
+                    .iter()
+                    .map(|&name| (name, cvar(name).into()))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Parse the message of a query command, e.g. `--windows --json`.
+pub fn parse(message: &str) -> Result<(Query, Format), ChunkWMError> {
+    let mut query = None;
+    let mut format = Format::Plain;
+
+    for arg in message.split_whitespace() {
+        let q = match arg {
+            "--json" => {
+                format = Format::Json;
+                continue;
+            }
+            "--plain" => {
+                format = Format::Plain;
+                continue;
+            }
+            "--windows" => Query::Windows,
+            "--focused" => Query::Focused,
+            "--applications" => Query::Applications,
+            "--displays" => Query::Displays,
+            "--spaces" => Query::Spaces,
+            "--cvars" => Query::CVars,
+            _ => return Err(ChunkWMError::ParseError("unknown query argument")),
+        };
+        if query.replace(q).is_some() {
+            return Err(ChunkWMError::ParseError("only one query can be given"));
+        }
+    }
+
+    query
+        .map(|query| (query, format))
+        .ok_or(ChunkWMError::ParseError("no query given"))
+}
+
+fn application_name(model: &WorkspaceModel, window: &WindowInfo) -> String {
+    model
+        .application(window.owner)
+        .map(|app| app.name.clone())
+        .unwrap_or_default()
+}
+
+/// Format a value, or `-` when there is none.
+pub(crate) fn or_dash<T: ToString>(value: Option<T>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "-".to_owned())
+}
+
+fn plain_window(model: &WorkspaceModel, window: &WindowInfo) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{},{}\t{}x{}\t{}\t{}",
+        window.id,
+        window.owner,
+        escape(&application_name(model, window)),
+        escape(&window.name),
+        window.position.x,
+        window.position.y,
+        window.size.width,
+        window.size.height,
+        or_dash(window.display),
+        or_dash(window.space),
+    )
+}
+
+fn json_window(model: &WorkspaceModel, window: &WindowInfo) -> Json {
+    Json::object(vec![
+        ("id", window.id.into()),
+        ("pid", window.owner.into()),
+        ("application", application_name(model, window).into()),
+        ("name", window.name.as_str().into()),
+        ("x", window.position.x.into()),
+        ("y", window.position.y.into()),
+        ("width", window.size.width.into()),
+        ("height", window.size.height.into()),
+        ("minimized", window.minimized.into()),
+        ("display", window.display.into()),
+        ("space", window.space.into()),
+    ])
+}
+
+fn plain_display(display: &DisplayInfo) -> String {
+    let frame = display
+        .frame
+        .map(|f| {
+            format!(
+                "{},{}\t{}x{}",
+                f.origin.x, f.origin.y, f.size.width, f.size.height
+            )
+        })
+        .unwrap_or_else(|| "-\t-".to_owned());
+    format!(
+        "{}\t{}\t{}",
+        display.id,
+        frame,
+        or_dash(display.active_space)
+    )
+}
+
+fn json_display(display: &DisplayInfo) -> Json {
+    let frame = display.frame.map(|f| {
+        Json::object(vec![
+            ("x", f.origin.x.into()),
+            ("y", f.origin.y.into()),
+            ("width", f.size.width.into()),
+            ("height", f.size.height.into()),
+        ])
+    });
+    Json::object(vec![
+        ("id", display.id.into()),
+        ("frame", frame.unwrap_or(Json::Null)),
+        ("active_space", display.active_space.into()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geometry::{CGPoint, CGRect, CGSize};
+    use testing;
+
+    fn model() -> WorkspaceModel {
+        let mut model = WorkspaceModel::new();
+        model.set_display_frame(
+            1,
+            CGRect::new(&CGPoint::new(0.0, 0.0), &CGSize::new(800.0, 600.0)),
+        );
+        model.set_active_space(1, 5);
+        let app = testing::application(100, "Terminal");
+        model.handle(&Event::ApplicationLaunched(testing::same_application(&app)));
+        model.handle(&Event::WindowCreated(testing::window(
+            &app,
+            1,
+            "zsh",
+            (0.0, 0.0, 400.0, 300.0),
+        )));
+        model.handle(&Event::WindowFocused(testing::window(
+            &app,
+            2,
+            "vim \"x\"",
+            (400.0, 0.0, 400.0, 300.0),
+        )));
+        model
+    }
+
+    fn cvar(name: &str) -> Option<String> {
+        match name {
+            "plugin_gap" => Some("10".to_owned()),
+            _ => None,
+        }
+    }
+
+    fn answer(queries: &QueryCommands, message: &str) -> String {
+        queries
+            .answer("query", message, &model(), cvar)
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_arguments() {
+        assert_eq!(parse("--windows").unwrap(), (Query::Windows, Format::Plain));
+        assert_eq!(
+            parse(" --json  --focused").unwrap(),
+            (Query::Focused, Format::Json)
+        );
+        assert_eq!(parse("").unwrap_err().message(), "no query given");
+        assert!(parse("--windows --spaces").is_err());
+        assert!(parse("--nope").is_err());
+    }
+
+    #[test]
+    fn other_commands_are_ignored() {
+        let queries = QueryCommands::new();
+        assert!(queries
+            .answer("focus", "--windows", &model(), cvar)
+            .is_none());
+        let queries = QueryCommands::new().with_command("inspect");
+        assert!(queries
+            .answer("inspect", "--windows", &model(), cvar)
+            .is_some());
+    }
+
+    #[test]
+    fn plain_windows() {
+        assert_eq!(
+            answer(&QueryCommands::new(), "--windows"),
+            "1\t100\tTerminal\tzsh\t0,0\t400x300\t1\t5\n\
+             2\t100\tTerminal\tvim \"x\"\t400,0\t400x300\t1\t5\n"
+        );
+
+        let mut model = model();
+        let app = testing::application(200, "Tab\tApp");
+        model.handle(&Event::ApplicationLaunched(testing::same_application(&app)));
+        model.handle(&Event::WindowCreated(testing::window(
+            &app,
+            3,
+            "a\\b\nc",
+            (0.0, 0.0, 10.0, 10.0),
+        )));
+        let reply = QueryCommands::new()
+            .answer("query", "--windows", &model, cvar)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            reply.lines().last(),
+            Some("3\t200\tTab\\tApp\ta\\\\b\\nc\t0,0\t10x10\t1\t5")
+        );
+    }
+
+    #[test]
+    fn json_focused() {
+        assert_eq!(
+            answer(&QueryCommands::new(), "--focused --json"),
+            "{\"id\":2,\"pid\":100,\"application\":\"Terminal\",\"name\":\"vim \\\"x\\\"\",\
+             \"x\":400,\"y\":0,\"width\":400,\"height\":300,\"minimized\":false,\
+             \"display\":1,\"space\":5}\n"
+        );
+    }
+
+    #[test]
+    fn displays_and_spaces() {
+        let queries = QueryCommands::new();
+        assert_eq!(answer(&queries, "--displays"), "1\t0,0\t800x600\t5\n");
+        assert_eq!(answer(&queries, "--spaces"), "5\t1\ttrue\t-\n");
+        assert_eq!(
+            answer(&queries, "--spaces --json"),
+            "[{\"id\":5,\"display\":1,\"active\":true,\"label\":null}]\n"
+        );
+
+        let mut model = model();
+        model.set_space_labels(vec![(5, "code")]);
+        assert_eq!(
+            queries
+                .answer("query", "--spaces", &model, cvar)
+                .unwrap()
+                .unwrap(),
+            "5\t1\ttrue\tcode\n"
+        );
+    }
+
+    #[test]
+    fn cvars() {
+        let queries = QueryCommands::new()
+            .cvar("plugin_gap")
+            .cvar("plugin_missing");
+        assert_eq!(
+            answer(&queries, "--cvars"),
+            "plugin_gap\t10\nplugin_missing\t\n"
+        );
+        assert_eq!(
+            answer(&queries, "--cvars --json"),
+            "{\"plugin_gap\":\"10\",\"plugin_missing\":null}\n"
+        );
+    }
+
+    #[test]
+    fn invalid_query() {
+        let queries = QueryCommands::new();
+        assert!(queries
+            .answer("query", "--bogus", &model(), cvar)
+            .unwrap()
+            .is_err());
+    }
+}
@@ -1,11 +1,27 @@
 use bridge::api::API;
 use bridge::event::Subscription;
-use core_foundation::base::CFTypeRef;
-use core_foundation::string::CFStringRef;
-use core_graphics::display::CGDirectDisplayID;
-use core_graphics::geometry::{CGPoint, CGSize};
 use display::SpaceType;
-use std::os::raw::{c_char, c_float, c_int, c_uint, c_ulong, c_void};
+use geometry::{CGPoint, CGSize};
+use std::os::raw::{c_char, c_float, c_int, c_uint, c_void};
+
+#[cfg(target_os = "macos")]
+pub use core_foundation::base::CFTypeRef;
+#[cfg(target_os = "macos")]
+pub use core_foundation::string::CFStringRef;
+#[cfg(target_os = "macos")]
+pub use core_graphics::display::CGDirectDisplayID;
+
+/// A reference to a CoreFoundation object. Only macOS has CoreFoundation, so on other targets this
+/// is an opaque pointer.
+#[cfg(not(target_os = "macos"))]
+pub type CFTypeRef = *const c_void;
+/// A reference to a CoreFoundation string. Only macOS has CoreFoundation, so on other targets this
+/// is an opaque pointer.
+#[cfg(not(target_os = "macos"))]
+pub type CFStringRef = *const c_void;
+/// The id of a display.
+#[cfg(not(target_os = "macos"))]
+pub type CGDirectDisplayID = u32;
 
 /// The function type used for the plugin's init function.
 pub type PluginBoolFunc = extern "C" fn(api: API) -> bool;
@@ -26,6 +42,15 @@ pub struct ChunkWMPlugin {
     pub subscription_count: c_uint,
 }
 
+/// The plugin API version `chunkwm_plugin!` exports by default, selected with the `api-7` and
+/// `api-8` features.
+#[cfg(feature = "api-7")]
+pub const API_VERSION: c_int = 7;
+/// The plugin API version `chunkwm_plugin!` exports by default, selected with the `api-7` and
+/// `api-8` features.
+#[cfg(all(feature = "api-8", not(feature = "api-7")))]
+pub const API_VERSION: c_int = 8;
+
 /// The raw `plugin_details` type `ChunkWM` uses for getting information about a plugin.
 #[repr(C)]
 pub struct ChunkWMPluginDetails {
@@ -50,31 +75,33 @@ pub type PID = c_int;
 #[derive(Debug, Clone, Copy)]
 #[repr(C)]
 pub struct RawObserver {
-    ///
+    /// The accessibility observer.
     pub observer: AXUIElementRef,
-    ///
+    /// Whether the observer is enabled.
     pub enabled: bool,
-    ///
+    /// Whether the observer is valid.
     pub valid: bool,
 }
 
 /// The process serial number. Both used as a raw type and as a Rust type.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[repr(C)]
 pub struct ProcessSerialNumber {
-    ///
-    pub high_long: c_ulong,
-    ///
-    pub low_long: c_ulong,
+    // These are `UInt32`s, which is an `unsigned int` (not an `unsigned long`) on 64-bit macOS.
+    /// The high 32 bits.
+    pub high_long: c_uint,
+    /// The low 32 bits.
+    pub low_long: c_uint,
 }
 
 /// The raw application representation. This is used in the C/C++ plugin, and shouldn't really be
 /// necessary for you to use in your Rust code.
 #[repr(C)]
 pub struct RawApplication {
-    ///
+    /// The application's accessibility element.
     pub element: AXUIElementRef,
-    ///
+    /// The application's observer.
     pub observer: RawObserver,
     /// The application's name.
     pub name: *const c_char,
@@ -91,11 +118,11 @@ pub type ApplicationRef = *mut RawApplication;
 /// necessary for you to use in your Rust code.
 #[repr(C)]
 pub struct RawWindow {
-    ///
+    /// The window's accessibility element.
     pub element: AXUIElementRef,
-    ///
+    /// The window's accessibility role.
     pub main_role: CFStringRef,
-    ///
+    /// The window's accessibility subrole.
     pub sub_role: CFStringRef,
     /// The 'owner' application of the window, i.e. the application it belongs to.
     pub owner: ApplicationRef,
@@ -104,7 +131,8 @@ pub struct RawWindow {
     /// The window's name.
     pub name: *const c_char,
     /// The flags on the window.
-    // TODO: Flags is `volatile`; maybe this should be a pointer?
+    // The flags are `volatile` in C; it doesn't change the layout, but chunkwm changes the
+    // flags from other threads, so read them with `ptr::read_volatile` (see `Window::flags`).
     pub flags: u32,
     /// The window's level.
     pub level: u32,
@@ -0,0 +1,670 @@
+//! The `scratchpad` module contains a `Scratchpad`, which keeps windows out of sight until they
+//! are summoned, like the scratchpad of i3.
+//!
+//! Marking the focused window moves it to the scratchpad under a name, which hides it. Toggling
+//! the name shows the window centred on the active display and focuses it, or hides it again when
+//! it is already shown and focused. Removing a window from the scratchpad shows it, and leaves it
+//! where it is from then on.
+//!
+//! The scratchpad is controlled with a daemon command. The name can be left out, and defaults to
+//! `default`; `--remove` without a name removes the focused window.
+//!
+//! ```sh
+//! chunkc plugin::scratchpad --mark term
+//! chunkc plugin::scratchpad --toggle term
+//! chunkc plugin::scratchpad --remove term
+//! chunkc plugin::scratchpad --list
+//! ```
+//!
+//! When a scratchpad window is destroyed, or its application terminates, the scratchpad keeps the
+//! name of the application and the title the window had when it was marked. The next window with
+//! the same application and title takes its place, and is hidden right away; a scratchpad terminal
+//! survives restarting the terminal.
+//!
+//! The settings implement `FromConfig`:
+//!
+//! ```text
+//! hide = minimize
+//! width = 0.6
+//! height = 0.5
+//! ```
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::scratchpad::{Scratchpad, ScratchpadSettings};
+//! use chunkwm::system::WindowSystem;
+//!
+//! fn handle<S: WindowSystem>(
+//!     scratchpad: &mut Scratchpad<S>,
+//!     event: &Event,
+//! ) -> Result<(), ChunkWMError> {
+//!     if scratchpad.handle(event)? {
+//!         return Ok(());
+//!     }
+//!     // handle other events and commands
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+
+use animation::apply_frame;
+use config::{ConfigValues, FromConfig};
+use event::{DisplayID, Event};
+use geometry::{CGFloat, CGPoint, CGRect, CGSize};
+use raw::PID;
+use std::str::FromStr;
+use system::{cursor_or_first_display, WindowSystem};
+use window::Window;
+use ChunkWMError;
+
+/// The name of a scratchpad window when the command doesn't give one.
+pub const DEFAULT_NAME: &str = "default";
+
+/// How scratchpad windows are hidden.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HideMode {
+    /// Minimize the window to the dock.
+    Minimize,
+    /// Move the window past the bottom right corner of the displays.
+    OffScreen,
+}
+
+impl FromStr for HideMode {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<HideMode, ChunkWMError> {
+        match s {
+            "minimize" => Ok(HideMode::Minimize),
+            "offscreen" => Ok(HideMode::OffScreen),
+            _ => Err(ChunkWMError::ParseError("unknown hide mode")),
+        }
+    }
+}
+
+/// The settings of a `Scratchpad`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScratchpadSettings {
+    /// How windows are hidden. Key: `hide` (`minimize` or `offscreen`).
+    pub hide: HideMode,
+    /// The width of a shown window, as a fraction of the display's width. 0 keeps the window's
+    /// width. Key: `width`.
+    pub width: CGFloat,
+    /// The height of a shown window, as a fraction of the display's height. 0 keeps the window's
+    /// height. Key: `height`.
+    pub height: CGFloat,
+}
+
+impl Default for ScratchpadSettings {
+    fn default() -> ScratchpadSettings {
+        ScratchpadSettings {
+            hide: HideMode::Minimize,
+            width: 0.0,
+            height: 0.0,
+        }
+    }
+}
+
+impl FromConfig for ScratchpadSettings {
+    fn from_config(values: &ConfigValues) -> ScratchpadSettings {
+        let default = ScratchpadSettings::default();
+        ScratchpadSettings {
+            hide: values.get("hide", default.hide),
+            width: values.get("width", default.width),
+            height: values.get("height", default.height),
+        }
+    }
+}
+
+/// A scratchpad command.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScratchpadCommand {
+    /// Move the focused window to the scratchpad. Argument: `--mark [name]`.
+    Mark(String),
+    /// Show or hide a scratchpad window. Argument: `--toggle [name]`.
+    Toggle(String),
+    /// Take a window out of the scratchpad, the focused window when no name is given. Argument:
+    /// `--remove [name]`.
+    Remove(Option<String>),
+    /// List the scratchpad windows. Argument: `--list`.
+    List,
+}
+
+impl FromStr for ScratchpadCommand {
+    type Err = ChunkWMError;
+
+    fn from_str(s: &str) -> Result<ScratchpadCommand, ChunkWMError> {
+        let mut words = s.split_whitespace();
+        let (command, name) = (words.next(), words.next().map(str::to_owned));
+        if words.next().is_some() {
+            return Err(ChunkWMError::ParseError("too many arguments"));
+        }
+        let or_default = |name: Option<String>| name.unwrap_or_else(|| DEFAULT_NAME.to_owned());
+        match (command, name) {
+            (Some("--mark"), name) => Ok(ScratchpadCommand::Mark(or_default(name))),
+            (Some("--toggle"), name) => Ok(ScratchpadCommand::Toggle(or_default(name))),
+            (Some("--remove"), name) => Ok(ScratchpadCommand::Remove(name)),
+            (Some("--list"), None) => Ok(ScratchpadCommand::List),
+            _ => Err(ChunkWMError::ParseError("unknown scratchpad command")),
+        }
+    }
+}
+
+/// A scratchpad window.
+struct Entry {
+    name: String,
+    /// The id of the window, or `None` while waiting for the window to be opened again.
+    window: Option<u32>,
+    owner: PID,
+    application: String,
+    title: String,
+    hidden: bool,
+}
+
+/// Hides marked windows, and shows them on the active display when they are toggled.
+pub struct Scratchpad<S: WindowSystem> {
+    system: S,
+    settings: ScratchpadSettings,
+    command: &'static str,
+    entries: Vec<Entry>,
+    /// The id and owner of the focused window.
+    focused: Option<(u32, PID)>,
+}
+
+impl<S: WindowSystem> Scratchpad<S> {
+    /// Create a new `Scratchpad`, answering the `scratchpad` command.
+    pub fn new(system: S, settings: ScratchpadSettings) -> Scratchpad<S> {
+        Scratchpad {
+            system,
+            settings,
+            command: "scratchpad",
+            entries: Vec::new(),
+            focused: None,
+        }
+    }
+
+    /// Answer a different command than `scratchpad`.
+    pub fn with_command(mut self, command: &'static str) -> Scratchpad<S> {
+        self.command = command;
+        self
+    }
+
+    /// Get the window system.
+    pub fn system(&self) -> &S {
+        &self.system
+    }
+
+    /// Get the window system mutably.
+    pub fn system_mut(&mut self) -> &mut S {
+        &mut self.system
+    }
+
+    /// Get the settings.
+    pub fn settings(&self) -> &ScratchpadSettings {
+        &self.settings
+    }
+
+    /// Change the settings. They apply the next time a window is shown or hidden.
+    pub fn set_settings(&mut self, settings: ScratchpadSettings) {
+        self.settings = settings;
+    }
+
+    /// Get the names of the scratchpad windows, in the order they were marked.
+    pub fn names(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|entry| entry.name.as_str())
+            .collect()
+    }
+
+    /// Get the id of a scratchpad window, or `None` when there is no such name or the window is
+    /// not open.
+    pub fn window_id(&self, name: &str) -> Option<u32> {
+        self.find(name).and_then(|index| self.entries[index].window)
+    }
+
+    /// Check whether a scratchpad window is hidden.
+    pub fn is_hidden(&self, name: &str) -> Option<bool> {
+        self.find(name).map(|index| self.entries[index].hidden)
+    }
+
+    /// Update the scratchpad with an event, and run the scratchpad command. The reply to the
+    /// command is sent to the client.
+    ///
+    /// Returns `Ok(true)` when the event was the scratchpad command.
+    pub fn handle(&mut self, event: &Event) -> Result<bool, ChunkWMError> {
+        match *event {
+            Event::DaemonCommand(ref payload) => {
+                let command = self.command;
+                return payload.answer(command, |command| self.run(command));
+            }
+            Event::WindowFocused(ref window) => {
+                self.focused = Some((window.id()?, window.owner()?.pid()?));
+            }
+            Event::WindowCreated(ref window) | Event::WindowTitleChanged(ref window) => {
+                self.rematch(window)?;
+            }
+            Event::WindowDestroyed(ref window) => {
+                let id = window.id()?;
+                for entry in &mut self.entries {
+                    if entry.window == Some(id) {
+                        entry.window = None;
+                    }
+                }
+                if self.focused_id() == Some(id) {
+                    self.focused = None;
+                }
+            }
+            Event::ApplicationTerminated(ref application) => {
+                // chunkwm doesn't send `WindowDestroyed` for the windows of the application.
+                let pid = application.pid()?;
+                for entry in &mut self.entries {
+                    if entry.owner == pid {
+                        entry.window = None;
+                    }
+                }
+                if self.focused.map(|focused| focused.1) == Some(pid) {
+                    self.focused = None;
+                }
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Run a scratchpad command, and get the reply.
+    pub fn run(&mut self, command: ScratchpadCommand) -> Result<String, ChunkWMError> {
+        match command {
+            ScratchpadCommand::Mark(name) => self.mark(&name).map(|_| String::new()),
+            ScratchpadCommand::Toggle(name) => self.toggle(&name).map(|_| String::new()),
+            ScratchpadCommand::Remove(name) => {
+                let index = match name {
+                    Some(ref name) => self.find(name),
+                    None => self.focused_id().and_then(|id| self.find_window(id)),
+                };
+                match index {
+                    Some(index) => self.remove(index).map(|_| String::new()),
+                    None => Err(ChunkWMError::Internal("no such scratchpad window")),
+                }
+            }
+            ScratchpadCommand::List => Ok(self.list()),
+        }
+    }
+
+    /// Move the focused window to the scratchpad under a name, and hide it. A window that was
+    /// marked under the name before is removed from the scratchpad.
+    pub fn mark(&mut self, name: &str) -> Result<(), ChunkWMError> {
+        let (id, owner) = self
+            .focused
+            .ok_or(ChunkWMError::Internal("no window is focused"))?;
+        let window = self.system.window(id)?;
+        if let Some(index) = self.find_window(id) {
+            self.entries.remove(index);
+        }
+        if let Some(index) = self.find(name) {
+            self.remove(index)?;
+        }
+
+        self.entries.push(Entry {
+            name: name.to_owned(),
+            application: window.owner()?.name()?,
+            title: window.name()?,
+            window: Some(id),
+            owner,
+            hidden: false,
+        });
+        let index = self.entries.len() - 1;
+        self.hide(index)
+    }
+
+    /// Show a scratchpad window and focus it, or hide it when it is shown and focused.
+    pub fn toggle(&mut self, name: &str) -> Result<(), ChunkWMError> {
+        let index = self
+            .find(name)
+            .ok_or(ChunkWMError::Internal("no such scratchpad window"))?;
+        let id = self.entries[index]
+            .window
+            .ok_or(ChunkWMError::Internal("the scratchpad window is not open"))?;
+
+        if self.is_shown(index)? && self.focused_id() == Some(id) {
+            self.hide(index)
+        } else {
+            self.show(index, true)
+        }
+    }
+
+    /// Take a window out of the scratchpad. A hidden window is shown, without focusing it.
+    fn remove(&mut self, index: usize) -> Result<(), ChunkWMError> {
+        if self.entries[index].window.is_some() && !self.is_shown(index)? {
+            self.show(index, false)?;
+        }
+        self.entries.remove(index);
+        Ok(())
+    }
+
+    fn list(&self) -> String {
+        self.entries
+            .iter()
+            .map(|entry| {
+                let state = match (&entry.window, entry.hidden) {
+                    (None, _) => "closed",
+                    (Some(_), true) => "hidden",
+                    (Some(_), false) => "shown",
+                };
+                format!(
+                    "{}\t{}\t{}\t{}\n",
+                    entry.name, entry.application, entry.title, state
+                )
+            })
+            .collect()
+    }
+
+    fn find(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.name == name)
+    }
+
+    fn find_window(&self, id: u32) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|entry| entry.window == Some(id))
+    }
+
+    fn focused_id(&self) -> Option<u32> {
+        self.focused.map(|focused| focused.0)
+    }
+
+    /// Check whether a window is shown: the scratchpad didn't hide it, and it wasn't minimized.
+    fn is_shown(&self, index: usize) -> Result<bool, ChunkWMError> {
+        let entry = &self.entries[index];
+        match entry.window {
+            Some(id) => Ok(!entry.hidden && !self.system.is_minimized(&self.system.window(id)?)?),
+            None => Ok(false),
+        }
+    }
+
+    /// Let a newly created or renamed window take the place of a closed scratchpad window with
+    /// the same application and title.
+    fn rematch(&mut self, window: &Window) -> Result<(), ChunkWMError> {
+        let id = window.id()?;
+        if self.find_window(id).is_some() {
+            return Ok(());
+        }
+        let (application, title) = (window.owner()?.name()?, window.name()?);
+        let index = self.entries.iter().position(|entry| {
+            entry.window.is_none() && entry.application == application && entry.title == title
+        });
+        if let Some(index) = index {
+            {
+                let entry = &mut self.entries[index];
+                entry.window = Some(id);
+                entry.owner = window.owner()?.pid()?;
+            }
+            self.hide(index)?;
+        }
+        Ok(())
+    }
+
+    fn hide(&mut self, index: usize) -> Result<(), ChunkWMError> {
+        let window = match self.entries[index].window {
+            Some(id) => self.system.window(id)?,
+            None => return Ok(()),
+        };
+        match self.settings.hide {
+            HideMode::Minimize => self.system.set_minimized(&window, true)?,
+            HideMode::OffScreen => {
+                let offscreen = self.offscreen_position()?;
+                self.system.set_position(&window, offscreen)?
+            }
+        }
+        self.entries[index].hidden = true;
+        Ok(())
+    }
+
+    /// Show a window centred on the active display, on that display's active space.
+    fn show(&mut self, index: usize, focus: bool) -> Result<(), ChunkWMError> {
+        let id = match self.entries[index].window {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+        let window = self.system.window(id)?;
+        let display = self.active_display(id)?;
+        let bounds = self.system.display_bounds(display)?;
+        let space = self.system.active_space(display)?;
+
+        if self.system.is_minimized(&window)? {
+            self.system.set_minimized(&window, false)?;
+        }
+        if !self.system.space_has_window(space, &window)? {
+            self.system.move_to_space(&window, space)?;
+        }
+        let frame = centred(&bounds, &window.size()?, &self.settings);
+        apply_frame(&mut self.system, &window, &frame)?;
+        if focus {
+            self.system.focus(&window)?;
+        }
+        self.entries[index].hidden = false;
+        Ok(())
+    }
+
+    /// The display of the focused window, unless it is the window being shown; otherwise the
+    /// display under the cursor, or the first display.
+    fn active_display(&self, showing: u32) -> Result<DisplayID, ChunkWMError> {
+        if let Some((focused, _)) = self.focused {
+            if focused != showing {
+                let window = self.system.window(focused);
+                if let Ok(display) = window.and_then(|window| self.system.window_display(&window)) {
+                    return Ok(display);
+                }
+            }
+        }
+        cursor_or_first_display(&self.system)
+    }
+
+    /// The bottom right corner of the area the displays cover.
+    fn offscreen_position(&self) -> Result<CGPoint, ChunkWMError> {
+        let mut corner = CGPoint::new(0.0, 0.0);
+        for display in self.system.displays()? {
+            let bounds = self.system.display_bounds(display)?;
+            corner.x = corner.x.max(bounds.origin.x + bounds.size.width);
+            corner.y = corner.y.max(bounds.origin.y + bounds.size.height);
+        }
+        Ok(corner)
+    }
+}
+
+/// The frame of a window shown centred on a display. The window keeps its size, unless the
+/// settings give one, but never gets larger than the display.
+fn centred(bounds: &CGRect, size: &CGSize, settings: &ScratchpadSettings) -> CGRect {
+    let length = |fraction: CGFloat, current: CGFloat, display: CGFloat| {
+        if fraction > 0.0 {
+            (display * fraction).min(display)
+        } else {
+            current.min(display)
+        }
+    };
+    let width = length(settings.width, size.width, bounds.size.width);
+    let height = length(settings.height, size.height, bounds.size.height);
+    CGRect::new(
+        &CGPoint::new(
+            bounds.origin.x + (bounds.size.width - width) / 2.0,
+            bounds.origin.y + (bounds.size.height - height) / 2.0,
+        ),
+        &CGSize::new(width, height),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use simulator::{Simulator, WindowSpec};
+    use testing::{frame, pump, rect};
+
+    /// A simulator with a terminal window (1) and an editor window (2), with the editor focused.
+    fn setup(settings: ScratchpadSettings) -> (Simulator, Scratchpad<Simulator>) {
+        let simulator = Simulator::new();
+        let mut scratchpad = Scratchpad::new(simulator.clone(), settings);
+        simulator.launch(1, "Terminal");
+        simulator.launch(2, "Editor");
+        simulator
+            .create_window(1, WindowSpec::new("shell", rect(0.0, 0.0, 400.0, 200.0)))
+            .unwrap();
+        simulator
+            .create_window(2, WindowSpec::new("notes", rect(0.0, 0.0, 1920.0, 1080.0)))
+            .unwrap();
+        simulator.focus_window(2).unwrap();
+        pump(&simulator, &mut scratchpad);
+        (simulator, scratchpad)
+    }
+
+    #[test]
+    fn parse_commands() {
+        use self::ScratchpadCommand::*;
+
+        assert_eq!(
+            "--mark".parse::<ScratchpadCommand>().unwrap(),
+            Mark("default".into())
+        );
+        assert_eq!(
+            "--toggle term".parse::<ScratchpadCommand>().unwrap(),
+            Toggle("term".into())
+        );
+        assert_eq!(
+            "--remove".parse::<ScratchpadCommand>().unwrap(),
+            Remove(None)
+        );
+        assert_eq!("--list".parse::<ScratchpadCommand>().unwrap(), List);
+        assert!("--list all".parse::<ScratchpadCommand>().is_err());
+        assert!("--mark a b".parse::<ScratchpadCommand>().is_err());
+        assert!("--show".parse::<ScratchpadCommand>().is_err());
+    }
+
+    #[test]
+    fn mark_and_toggle() {
+        let (simulator, mut scratchpad) = setup(ScratchpadSettings::default());
+        simulator.focus_window(1).unwrap();
+        pump(&simulator, &mut scratchpad);
+
+        scratchpad
+            .run(ScratchpadCommand::Mark("term".into()))
+            .unwrap();
+        pump(&simulator, &mut scratchpad);
+        let terminal = simulator.window(1).unwrap();
+        assert!(simulator.is_minimized(&terminal).unwrap());
+        assert_eq!(scratchpad.is_hidden("term"), Some(true));
+
+        // Shown centred and focused, then hidden again.
+        simulator.focus_window(2).unwrap();
+        pump(&simulator, &mut scratchpad);
+        scratchpad.toggle("term").unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert!(!simulator.is_minimized(&terminal).unwrap());
+        assert_eq!(frame(&simulator, 1), (760.0, 440.0, 400.0, 200.0));
+        assert_eq!(simulator.focused_window_id(), Some(1));
+        scratchpad.toggle("term").unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert!(simulator.is_minimized(&terminal).unwrap());
+
+        // A shown window that lost focus is focused instead of hidden.
+        scratchpad.toggle("term").unwrap();
+        simulator.focus_window(2).unwrap();
+        pump(&simulator, &mut scratchpad);
+        scratchpad.toggle("term").unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert!(!simulator.is_minimized(&terminal).unwrap());
+        assert_eq!(simulator.focused_window_id(), Some(1));
+
+        assert_eq!(
+            scratchpad.run(ScratchpadCommand::List).unwrap(),
+            "term\tTerminal\tshell\tshown\n"
+        );
+        assert!(scratchpad.toggle("other").is_err());
+
+        // Removing the focused window leaves it where it is.
+        scratchpad.run(ScratchpadCommand::Remove(None)).unwrap();
+        assert!(scratchpad.names().is_empty());
+        assert_eq!(frame(&simulator, 1), (760.0, 440.0, 400.0, 200.0));
+    }
+
+    #[test]
+    fn offscreen_on_the_active_display() {
+        let settings = ScratchpadSettings {
+            hide: HideMode::OffScreen,
+            width: 0.5,
+            height: 0.5,
+        };
+        let (simulator, mut scratchpad) = setup(settings);
+        let second = simulator.add_display(rect(1920.0, 0.0, 1280.0, 800.0));
+        simulator.focus_window(1).unwrap();
+        pump(&simulator, &mut scratchpad);
+
+        scratchpad.mark("term").unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(frame(&simulator, 1), (3200.0, 1080.0, 400.0, 200.0));
+
+        // The focused window moves to the second display, so the scratchpad is shown there.
+        simulator
+            .set_window_frame(2, rect(2000.0, 0.0, 800.0, 600.0))
+            .unwrap();
+        simulator.focus_window(2).unwrap();
+        pump(&simulator, &mut scratchpad);
+        scratchpad.toggle("term").unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(frame(&simulator, 1), (2240.0, 200.0, 640.0, 400.0));
+        let space = simulator.active_space(second).unwrap();
+        assert_eq!(simulator.window_space(1).unwrap(), space);
+
+        // Marking another window under the same name shows the first window.
+        scratchpad.toggle("term").unwrap();
+        pump(&simulator, &mut scratchpad);
+        simulator.focus_window(2).unwrap();
+        pump(&simulator, &mut scratchpad);
+        scratchpad.mark("term").unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(scratchpad.window_id("term"), Some(2));
+        assert_eq!(frame(&simulator, 1), (2240.0, 200.0, 640.0, 400.0));
+    }
+
+    #[test]
+    fn survives_relaunch() {
+        let (simulator, mut scratchpad) = setup(ScratchpadSettings::default());
+        simulator.focus_window(1).unwrap();
+        pump(&simulator, &mut scratchpad);
+        scratchpad.mark(DEFAULT_NAME).unwrap();
+        pump(&simulator, &mut scratchpad);
+
+        simulator.destroy_window(1).unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(scratchpad.window_id(DEFAULT_NAME), None);
+        assert!(scratchpad.toggle(DEFAULT_NAME).is_err());
+
+        // Another title doesn't match, until it changes to the marked title.
+        simulator
+            .create_window(1, WindowSpec::new("loading", rect(0.0, 0.0, 300.0, 300.0)))
+            .unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(scratchpad.window_id(DEFAULT_NAME), None);
+        simulator.set_window_name(3, "shell").unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(scratchpad.window_id(DEFAULT_NAME), Some(3));
+        assert!(simulator
+            .is_minimized(&simulator.window(3).unwrap())
+            .unwrap());
+
+        // Relaunching the application.
+        simulator.terminate(1).unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(scratchpad.window_id(DEFAULT_NAME), None);
+        simulator.launch(3, "Terminal");
+        simulator
+            .create_window(3, WindowSpec::new("shell", rect(0.0, 0.0, 300.0, 300.0)))
+            .unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(scratchpad.window_id(DEFAULT_NAME), Some(4));
+
+        scratchpad.toggle(DEFAULT_NAME).unwrap();
+        pump(&simulator, &mut scratchpad);
+        assert_eq!(simulator.focused_window_id(), Some(4));
+        assert_eq!(frame(&simulator, 4), (810.0, 390.0, 300.0, 300.0));
+    }
+}
@@ -0,0 +1,1574 @@
+//! The `simulator` module contains a simulated window system, so that plugins can be developed and
+//! tested without macOS.
+//!
+//! The `Simulator` keeps applications, windows, displays, spaces, the dock and the cursor in
+//! memory. Changing them, either with the methods on `Simulator` or through `WindowSystem`, queues
+//! the events chunkwm would broadcast (e.g. moving a window queues `WindowMoved`). `deliver` hands
+//! the queued events to a plugin, the way `chunkwm_plugin!` does: only subscribed events are
+//! delivered, and the `tracked_state` is cleaned up afterwards.
+//!
+//! The `Application`s and `Window`s in events point into the simulator. They stay valid, also after
+//! the window is destroyed or the application terminated, until the simulator is dropped.
+//!
+//! The simulator also implements the plugin `API`. Its `CVar`s and log are stored per thread.
+//!
+//! ## Example
+//! ```rust
+//! # #[macro_use] extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::geometry::{CGPoint, CGRect, CGSize};
+//! use chunkwm::simulator::{Simulator, WindowSpec};
+//!
+//! pub struct Plugin {
+//!     focused: Vec<String>,
+//! }
+//!
+//! impl HandleEvent for Plugin {
+//!     fn new(_: API) -> Plugin {
+//!         Plugin { focused: Vec::new() }
+//!     }
+//!
+//!     subscribe!(Subscription::WindowFocused);
+//!
+//!     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+//!         if let Event::WindowFocused(window) = event {
+//!             self.focused.push(window.name()?);
+//!         }
+//!         Ok(())
+//!     }
+//!     // some methods omitted
+//!     # fn shutdown(&self) {}
+//! }
+//!
+//! # fn main() {
+//! let simulator = Simulator::new();
+//! let mut plugin: Plugin = simulator.start();
+//! simulator.launch(100, "Terminal");
+//! let frame = CGRect::new(&CGPoint::new(0.0, 0.0), &CGSize::new(800.0, 600.0));
+//! let window = simulator.create_window(100, WindowSpec::new("vim", frame)).unwrap();
+//! simulator.focus_window(window.id().unwrap()).unwrap();
+//!
+//! simulator.deliver(&mut plugin).unwrap();
+//! assert_eq!(plugin.focused, vec!["vim"]);
+//! # }
+//! ```
+
+#[cfg(not(feature = "api-7"))]
+use api::LogLevel;
+use api::API;
+use application::Application;
+use display::{DisplayUuid, DockOrientation, SpaceType};
+use event::{DisplayID, Event, HandleEvent};
+use geometry::{self, CGPoint, CGRect, CGSize};
+use raw::*;
+use state::Cleanup;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::rc::Rc;
+use system::{Dock, WindowSystem};
+use window::{Window, WindowFlag};
+use ChunkWMError;
+
+/// The description of a window to create.
+#[derive(Debug, Clone)]
+pub struct WindowSpec {
+    pub name: String,
+    pub frame: CGRect,
+    /// The accessibility role, `AXWindow` by default.
+    pub role: String,
+    /// The accessibility subrole, `AXStandardWindow` by default.
+    pub subrole: String,
+    pub level: u32,
+    /// The raw flags, see `WindowFlag::bit`.
+    pub flags: u32,
+    pub movable: bool,
+    pub resizable: bool,
+}
+
+impl WindowSpec {
+    /// Describe a standard, movable and resizable window.
+    pub fn new<S: Into<String>>(name: S, frame: CGRect) -> WindowSpec {
+        WindowSpec {
+            name: name.into(),
+            frame,
+            role: "AXWindow".to_owned(),
+            subrole: "AXStandardWindow".to_owned(),
+            level: 0,
+            flags: 0,
+            movable: true,
+            resizable: true,
+        }
+    }
+}
+
+struct SimApplication {
+    raw: *mut RawApplication,
+    hidden: bool,
+    focused_window: Option<u32>,
+}
+
+struct SimWindow {
+    raw: *mut RawWindow,
+    role: String,
+    subrole: String,
+    movable: bool,
+    resizable: bool,
+    space: CGSSpaceID,
+    /// The frame and space to return to when leaving fullscreen.
+    fullscreen: Option<(CGRect, CGSSpaceID)>,
+}
+
+struct SimDisplay {
+    identifier: DisplayUuid,
+    arrangement: u32,
+    frame: CGRect,
+    active_space: CGSSpaceID,
+}
+
+struct SimSpace {
+    space_type: SpaceType,
+    display: DisplayID,
+    /// The position among the spaces of the display.
+    order: usize,
+}
+
+struct State {
+    applications: BTreeMap<PID, SimApplication>,
+    windows: BTreeMap<u32, SimWindow>,
+    displays: BTreeMap<DisplayID, SimDisplay>,
+    spaces: BTreeMap<CGSSpaceID, SimSpace>,
+    active_display: DisplayID,
+    active_application: Option<PID>,
+    dock: Dock,
+    cursor: CGPoint,
+    events: VecDeque<Event>,
+    next_window: u32,
+    next_display: DisplayID,
+    next_space: CGSSpaceID,
+    /// The raw structs of destroyed windows and terminated applications, which events may still
+    /// point to.
+    retired_windows: Vec<*mut RawWindow>,
+    retired_applications: Vec<*mut RawApplication>,
+}
+
+/// The UUID of a simulated display.
+fn simulated_identifier(id: DisplayID) -> DisplayUuid {
+    format!("00000000-0000-0000-0000-{:012X}", id)
+        .parse()
+        .expect("simulated display UUIDs are valid")
+}
+
+fn no_such_window() -> ChunkWMError {
+    ChunkWMError::Internal("no such window")
+}
+
+fn no_such_application() -> ChunkWMError {
+    ChunkWMError::Internal("no such application")
+}
+
+fn no_such_display() -> ChunkWMError {
+    ChunkWMError::Internal("no such display")
+}
+
+fn no_such_space() -> ChunkWMError {
+    ChunkWMError::Internal("no such space")
+}
+
+impl State {
+    fn window(&self, id: u32) -> Result<&SimWindow, ChunkWMError> {
+        self.windows.get(&id).ok_or_else(no_such_window)
+    }
+
+    fn window_mut(&mut self, id: u32) -> Result<&mut SimWindow, ChunkWMError> {
+        self.windows.get_mut(&id).ok_or_else(no_such_window)
+    }
+
+    fn application(&self, pid: PID) -> Result<&SimApplication, ChunkWMError> {
+        self.applications.get(&pid).ok_or_else(no_such_application)
+    }
+
+    fn display(&self, id: DisplayID) -> Result<&SimDisplay, ChunkWMError> {
+        self.displays.get(&id).ok_or_else(no_such_display)
+    }
+
+    fn raw_window(&self, id: u32) -> Result<&RawWindow, ChunkWMError> {
+        Ok(unsafe { &*self.window(id)?.raw })
+    }
+
+    fn raw_window_mut(&mut self, id: u32) -> Result<&mut RawWindow, ChunkWMError> {
+        Ok(unsafe { &mut *self.window(id)?.raw })
+    }
+
+    fn window_handle(&self, id: u32) -> Result<Window, ChunkWMError> {
+        Ok(Window::from(self.window(id)?.raw))
+    }
+
+    fn application_handle(&self, pid: PID) -> Result<Application, ChunkWMError> {
+        Ok(Application::from(self.application(pid)?.raw))
+    }
+
+    fn owner(&self, id: u32) -> Result<PID, ChunkWMError> {
+        Ok(unsafe { (*self.raw_window(id)?.owner).pid })
+    }
+
+    fn frame(&self, id: u32) -> Result<CGRect, ChunkWMError> {
+        let raw = self.raw_window(id)?;
+        Ok(CGRect::new(&raw.position, &raw.size))
+    }
+
+    fn push_window_event(
+        &mut self,
+        id: u32,
+        event: fn(Window) -> Event,
+    ) -> Result<(), ChunkWMError> {
+        let window = self.window_handle(id)?;
+        self.events.push_back(event(window));
+        Ok(())
+    }
+
+    fn push_application_event(
+        &mut self,
+        pid: PID,
+        event: fn(Application) -> Event,
+    ) -> Result<(), ChunkWMError> {
+        let application = self.application_handle(pid)?;
+        self.events.push_back(event(application));
+        Ok(())
+    }
+
+    /// The display that contains a point, if any.
+    fn display_at(&self, point: &CGPoint) -> Option<DisplayID> {
+        self.displays
+            .iter()
+            .find(|&(_, display)| geometry::contains(&display.frame, point))
+            .map(|(id, _)| *id)
+    }
+
+    /// Move and resize a window, queueing `WindowMoved` and `WindowResized` for what changed. A
+    /// window that moves to another display moves to that display's active space.
+    fn set_frame(&mut self, id: u32, frame: CGRect) -> Result<(), ChunkWMError> {
+        let old = self.frame(id)?;
+        let raw = self.raw_window_mut(id)?;
+        raw.position = frame.origin;
+        raw.size = frame.size;
+
+        let display = self.display_at(&geometry::center(&frame));
+        let space = self.window(id)?.space;
+        let current = self.spaces.get(&space).map(|space| space.display);
+        if let Some(display) = display.filter(|display| Some(*display) != current) {
+            let active_space = self.display(display)?.active_space;
+            self.window_mut(id)?.space = active_space;
+        }
+
+        if old.origin.x != frame.origin.x || old.origin.y != frame.origin.y {
+            self.push_window_event(id, Event::WindowMoved)?;
+        }
+        if old.size.width != frame.size.width || old.size.height != frame.size.height {
+            self.push_window_event(id, Event::WindowResized)?;
+        }
+        Ok(())
+    }
+
+    fn activate(&mut self, pid: PID) -> Result<(), ChunkWMError> {
+        self.application(pid)?;
+        if self.active_application == Some(pid) {
+            return Ok(());
+        }
+        if let Some(previous) = self.active_application {
+            self.push_application_event(previous, Event::ApplicationDeactivated)?;
+        }
+        self.active_application = Some(pid);
+        self.push_application_event(pid, Event::ApplicationActivated)
+    }
+
+    fn focus_window(&mut self, id: u32) -> Result<(), ChunkWMError> {
+        let pid = self.owner(id)?;
+        self.activate(pid)?;
+        if let Some(application) = self.applications.get_mut(&pid) {
+            application.focused_window = Some(id);
+        }
+        self.push_window_event(id, Event::WindowFocused)
+    }
+
+    fn create_window(
+        &mut self,
+        pid: PID,
+        spec: WindowSpec,
+        event: fn(Window) -> Event,
+    ) -> Result<Window, ChunkWMError> {
+        let owner = self.application(pid)?.raw;
+        let display = self
+            .display_at(&geometry::center(&spec.frame))
+            .unwrap_or(self.active_display);
+        let space = self.display(display)?.active_space;
+
+        self.next_window += 1;
+        let id = self.next_window;
+        let raw = Box::into_raw(Box::new(RawWindow {
+            element: ptr::null(),
+            main_role: ptr::null(),
+            sub_role: ptr::null(),
+            owner,
+            id,
+            name: CString::new(spec.name)
+                .map_err(|_| ChunkWMError::ParseError("window name contains a NUL byte"))?
+                .into_raw(),
+            flags: spec.flags,
+            level: spec.level,
+            position: spec.frame.origin,
+            size: spec.frame.size,
+        }));
+        self.windows.insert(
+            id,
+            SimWindow {
+                raw,
+                role: spec.role,
+                subrole: spec.subrole,
+                movable: spec.movable,
+                resizable: spec.resizable,
+                space,
+                fullscreen: None,
+            },
+        );
+        self.push_window_event(id, event)?;
+        self.window_handle(id)
+    }
+
+    fn destroy_window(&mut self, id: u32) -> Result<(), ChunkWMError> {
+        self.push_window_event(id, Event::WindowDestroyed)?;
+        let pid = self.owner(id)?;
+        let window = self.windows.remove(&id).ok_or_else(no_such_window)?;
+        if let Some(application) = self.applications.get_mut(&pid) {
+            if application.focused_window == Some(id) {
+                application.focused_window = None;
+            }
+        }
+        if let Some((_, space)) = window.fullscreen {
+            self.leave_fullscreen_space(window.space, space);
+        }
+        self.retired_windows.push(window.raw);
+        Ok(())
+    }
+
+    fn set_flag(&mut self, id: u32, flag: WindowFlag, set: bool) -> Result<(), ChunkWMError> {
+        let raw = self.raw_window_mut(id)?;
+        if set {
+            raw.flags |= flag.bit();
+        } else {
+            raw.flags &= !flag.bit();
+        }
+        Ok(())
+    }
+
+    fn add_space(&mut self, display: DisplayID, space_type: SpaceType) -> CGSSpaceID {
+        self.next_space += 1;
+        let order = self.spaces_of(display).len();
+        self.spaces.insert(
+            self.next_space,
+            SimSpace {
+                space_type,
+                display,
+                order,
+            },
+        );
+        self.next_space
+    }
+
+    /// The spaces of a display, in order.
+    fn spaces_of(&self, display: DisplayID) -> Vec<CGSSpaceID> {
+        let mut spaces: Vec<_> = self
+            .spaces
+            .iter()
+            .filter(|&(_, space)| space.display == display)
+            .collect();
+        spaces.sort_by_key(|&(_, space)| space.order);
+        spaces.into_iter().map(|(id, _)| *id).collect()
+    }
+
+    /// Make a space the active space of its display, queueing `SpaceChanged`, or
+    /// `DisplayChanged` when the display was not active.
+    fn switch_space(&mut self, space: CGSSpaceID) -> Result<(), ChunkWMError> {
+        let display = self.spaces.get(&space).ok_or_else(no_such_space)?.display;
+        self.displays
+            .get_mut(&display)
+            .ok_or_else(no_such_display)?
+            .active_space = space;
+        if self.active_display == display {
+            self.events.push_back(Event::SpaceChanged);
+        } else {
+            self.active_display = display;
+            self.events.push_back(Event::DisplayChanged);
+        }
+        Ok(())
+    }
+
+    /// Remove a fullscreen space, and return to the space the window came from.
+    fn leave_fullscreen_space(&mut self, fullscreen: CGSSpaceID, previous: CGSSpaceID) {
+        self.spaces.remove(&fullscreen);
+        let _ = self.switch_space(previous);
+    }
+
+    fn set_fullscreen(&mut self, id: u32, fullscreen: bool) -> Result<(), ChunkWMError> {
+        let window = self.window(id)?;
+        match (window.fullscreen, fullscreen) {
+            (None, true) => {
+                let (frame, space) = (self.frame(id)?, window.space);
+                let display = self.spaces.get(&space).ok_or_else(no_such_space)?.display;
+                let bounds = self.display(display)?.frame;
+                let fullscreen_space = self.add_space(display, SpaceType::Fullscreen);
+                {
+                    let window = self.window_mut(id)?;
+                    window.fullscreen = Some((frame, space));
+                    window.space = fullscreen_space;
+                }
+                self.switch_space(fullscreen_space)?;
+                self.set_frame(id, bounds)
+            }
+            (Some((frame, space)), false) => {
+                let fullscreen_space = window.space;
+                {
+                    let window = self.window_mut(id)?;
+                    window.fullscreen = None;
+                    window.space = space;
+                }
+                self.leave_fullscreen_space(fullscreen_space, space);
+                self.set_frame(id, frame)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The displays, ordered by arrangement.
+    fn displays_by_arrangement(&self) -> Vec<DisplayID> {
+        let mut displays: Vec<_> = self.displays.iter().collect();
+        displays.sort_by_key(|&(_, display)| display.arrangement);
+        displays.into_iter().map(|(id, _)| *id).collect()
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        let windows = self.windows.values().map(|window| window.raw);
+        for raw in windows
+            .chain(self.retired_windows.drain(..))
+            .collect::<Vec<_>>()
+        {
+            unsafe {
+                let raw = Box::from_raw(raw);
+                drop(CString::from_raw(raw.name as *mut c_char));
+            }
+        }
+        let applications = self
+            .applications
+            .values()
+            .map(|application| application.raw);
+        for raw in applications
+            .chain(self.retired_applications.drain(..))
+            .collect::<Vec<_>>()
+        {
+            unsafe {
+                let raw = Box::from_raw(raw);
+                drop(CString::from_raw(raw.name as *mut c_char));
+            }
+        }
+    }
+}
+
+/// A simulated window system. Clones share the same state, so a plugin can keep a clone to use
+/// as its `WindowSystem`.
+#[derive(Clone)]
+pub struct Simulator {
+    state: Rc<RefCell<State>>,
+}
+
+impl Default for Simulator {
+    fn default() -> Simulator {
+        Simulator::new()
+    }
+}
+
+impl Simulator {
+    /// Create a simulator with one 1920x1080 display, which has one user space, and a dock at the
+    /// bottom. No events are queued.
+    pub fn new() -> Simulator {
+        let mut state = State {
+            applications: BTreeMap::new(),
+            windows: BTreeMap::new(),
+            displays: BTreeMap::new(),
+            spaces: BTreeMap::new(),
+            active_display: 1,
+            active_application: None,
+            dock: Dock {
+                orientation: DockOrientation::Bottom,
+                tile_size: 64,
+                auto_hide: false,
+            },
+            cursor: CGPoint::new(0.0, 0.0),
+            events: VecDeque::new(),
+            next_window: 0,
+            next_display: 1,
+            next_space: 0,
+            retired_windows: Vec::new(),
+            retired_applications: Vec::new(),
+        };
+        let space = state.add_space(1, SpaceType::User);
+        state.displays.insert(
+            1,
+            SimDisplay {
+                identifier: simulated_identifier(1),
+                arrangement: 0,
+                frame: CGRect::new(&CGPoint::new(0.0, 0.0), &CGSize::new(1920.0, 1080.0)),
+                active_space: space,
+            },
+        );
+
+        Simulator {
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    /// Get the plugin `API`, which is backed by the simulator's `CVar`s.
+    pub fn api(&self) -> API {
+        API::from_functions(
+            api_update_cvar,
+            api_acquire_cvar,
+            api_find_cvar,
+            api_plugin_broadcast,
+            #[cfg(not(feature = "api-7"))]
+            api_log,
+        )
+    }
+
+    /// Create a plugin with `HandleEvent::new`, as chunkwm does when it loads the plugin.
+    pub fn start<P: HandleEvent>(&self) -> P {
+        P::new(self.api())
+    }
+
+    /// Set a `CVar`, like `chunkc set`.
+    pub fn set_cvar<V: ToString>(&self, name: &str, value: V) {
+        CVARS.with(|cvars| {
+            cvars.borrow_mut().insert(
+                name.to_owned(),
+                CString::new(value.to_string()).expect("CVar value contains a NUL byte"),
+            )
+        });
+    }
+
+    /// Get a `CVar`.
+    pub fn cvar(&self, name: &str) -> Option<String> {
+        CVARS.with(|cvars| {
+            cvars
+                .borrow()
+                .get(name)
+                .map(|value| value.to_string_lossy().into_owned())
+        })
+    }
+
+    /// Get the messages plugins logged with `API::log`, and clear them.
+    pub fn take_log(&self) -> Vec<String> {
+        LOG.with(|log| ::std::mem::take(&mut *log.borrow_mut()))
+    }
+
+    /// Get the queued events, and clear the queue.
+    pub fn take_events(&self) -> Vec<Event> {
+        self.state.borrow_mut().events.drain(..).collect()
+    }
+
+    /// Deliver the queued events to a plugin, including the events the plugin causes while
+    /// handling them, the way `chunkwm_plugin!` does: events the plugin didn't subscribe to, or
+    /// that don't pass its `filters`, are not handled, but its `tracked_state` is still cleaned
+    /// up. Stops at the first error, leaving the remaining events queued. Returns the number of
+    /// handled events.
+    pub fn deliver<P: HandleEvent>(&self, plugin: &mut P) -> Result<usize, ChunkWMError> {
+        let mut delivered = 0;
+        loop {
+            let event = match self.state.borrow_mut().events.pop_front() {
+                Some(event) => event,
+                None => return Ok(delivered),
+            };
+            let subscribed = event
+                .subscription()
+                .is_none_or(|s| P::subscribe().contains(&s));
+            let passes = subscribed
+                && plugin
+                    .filters()
+                    .is_none_or(|(filters, system)| filters.matches(&event, system));
+
+            let cleanup = Cleanup::from_event(&event);
+            let result = if passes { plugin.handle(event) } else { Ok(()) };
+            if let Some(cleanup) = cleanup {
+                cleanup.apply(&mut plugin.tracked_state());
+            }
+            result?;
+            if passes {
+                delivered += 1;
+            }
+        }
+    }
+
+    /// Launch an application, queueing `ApplicationLaunched`.
+    pub fn launch(&self, pid: PID, name: &str) -> Application {
+        let mut state = self.state.borrow_mut();
+        let raw = Box::into_raw(Box::new(RawApplication {
+            element: ptr::null(),
+            observer: RawObserver {
+                observer: ptr::null(),
+                enabled: false,
+                valid: false,
+            },
+            name: CString::new(name)
+                .expect("application name contains a NUL byte")
+                .into_raw(),
+            pid,
+            process_serial_number: ProcessSerialNumber {
+                high_long: 0,
+                low_long: pid as _,
+            },
+        }));
+        if let Some(previous) = state.applications.insert(
+            pid,
+            SimApplication {
+                raw,
+                hidden: false,
+                focused_window: None,
+            },
+        ) {
+            state.retired_applications.push(previous.raw);
+        }
+        state
+            .events
+            .push_back(Event::ApplicationLaunched(Application::from(raw)));
+        Application::from(raw)
+    }
+
+    /// Terminate an application, queueing `ApplicationTerminated`. Its windows are removed
+    /// without `WindowDestroyed` events, as chunkwm does.
+    pub fn terminate(&self, pid: PID) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        state.push_application_event(pid, Event::ApplicationTerminated)?;
+        let ids: Vec<u32> = state
+            .windows
+            .keys()
+            .cloned()
+            .filter(|id| state.owner(*id).ok() == Some(pid))
+            .collect();
+        for id in ids {
+            if let Some(window) = state.windows.remove(&id) {
+                state.retired_windows.push(window.raw);
+            }
+        }
+        let application = state
+            .applications
+            .remove(&pid)
+            .ok_or_else(no_such_application)?;
+        state.retired_applications.push(application.raw);
+        if state.active_application == Some(pid) {
+            state.active_application = None;
+        }
+        Ok(())
+    }
+
+    /// Activate an application, queueing `ApplicationDeactivated` for the previously active
+    /// application, `ApplicationActivated`, and `WindowFocused` for its focused window.
+    pub fn activate(&self, pid: PID) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        if state.active_application == Some(pid) {
+            return Ok(());
+        }
+        state.activate(pid)?;
+        if let Some(id) = state.application(pid)?.focused_window {
+            state.push_window_event(id, Event::WindowFocused)?;
+        }
+        Ok(())
+    }
+
+    /// Hide or unhide an application, queueing `ApplicationHidden` or `ApplicationUnhidden`.
+    pub fn set_hidden(&self, pid: PID, hidden: bool) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        let application = state
+            .applications
+            .get_mut(&pid)
+            .ok_or_else(no_such_application)?;
+        if application.hidden == hidden {
+            return Ok(());
+        }
+        application.hidden = hidden;
+        if hidden {
+            state.push_application_event(pid, Event::ApplicationHidden)
+        } else {
+            state.push_application_event(pid, Event::ApplicationUnhidden)
+        }
+    }
+
+    /// Check whether an application is hidden.
+    pub fn is_hidden(&self, pid: PID) -> Result<bool, ChunkWMError> {
+        Ok(self.state.borrow().application(pid)?.hidden)
+    }
+
+    /// Create a window on the active space of the display that contains its center, queueing
+    /// `WindowCreated`. Windows get increasing ids, starting at 1.
+    pub fn create_window(&self, pid: PID, spec: WindowSpec) -> Result<Window, ChunkWMError> {
+        self.state
+            .borrow_mut()
+            .create_window(pid, spec, Event::WindowCreated)
+    }
+
+    /// Create a sheet, queueing `WindowSheetCreated`.
+    pub fn create_sheet(&self, pid: PID, spec: WindowSpec) -> Result<Window, ChunkWMError> {
+        self.state
+            .borrow_mut()
+            .create_window(pid, spec, Event::WindowSheetCreated)
+    }
+
+    /// Get a window.
+    pub fn window(&self, id: u32) -> Result<Window, ChunkWMError> {
+        self.state.borrow().window_handle(id)
+    }
+
+    /// Get the id of the focused window.
+    pub fn focused_window_id(&self) -> Option<u32> {
+        let state = self.state.borrow();
+        state
+            .active_application
+            .and_then(|pid| state.applications.get(&pid))
+            .and_then(|application| application.focused_window)
+    }
+
+    /// Destroy a window, queueing `WindowDestroyed`.
+    pub fn destroy_window(&self, id: u32) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().destroy_window(id)
+    }
+
+    /// Focus a window, queueing `WindowFocused` after the events of activating its application.
+    pub fn focus_window(&self, id: u32) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().focus_window(id)
+    }
+
+    /// Move and resize a window, queueing `WindowMoved` and `WindowResized` for what changed.
+    pub fn set_window_frame(&self, id: u32, frame: CGRect) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().set_frame(id, frame)
+    }
+
+    /// Minimize or deminimize a window, queueing `WindowMinimized` or `WindowDeminimized`.
+    pub fn set_window_minimized(&self, id: u32, minimized: bool) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        if state.raw_window(id)?.flags & WindowFlag::Minimized.bit() != 0 {
+            if minimized {
+                return Ok(());
+            }
+        } else if !minimized {
+            return Ok(());
+        }
+        state.set_flag(id, WindowFlag::Minimized, minimized)?;
+        if minimized {
+            state.push_window_event(id, Event::WindowMinimized)
+        } else {
+            state.push_window_event(id, Event::WindowDeminimized)
+        }
+    }
+
+    /// Rename a window, queueing `WindowTitleChanged`.
+    pub fn set_window_name(&self, id: u32, name: &str) -> Result<(), ChunkWMError> {
+        let name = CString::new(name)
+            .map_err(|_| ChunkWMError::ParseError("window name contains a NUL byte"))?;
+        let mut state = self.state.borrow_mut();
+        let raw = state.raw_window_mut(id)?;
+        let old = ::std::mem::replace(&mut raw.name, name.into_raw());
+        unsafe { drop(CString::from_raw(old as *mut c_char)) };
+        state.push_window_event(id, Event::WindowTitleChanged)
+    }
+
+    /// Set the level of a window. chunkwm doesn't broadcast level changes.
+    pub fn set_window_level(&self, id: u32, level: u32) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().raw_window_mut(id)?.level = level;
+        Ok(())
+    }
+
+    /// Enter or leave fullscreen. Entering creates a fullscreen space on the window's display and
+    /// switches to it, leaving removes it again; both queue `SpaceChanged` and the events of
+    /// resizing the window.
+    pub fn set_fullscreen(&self, id: u32, fullscreen: bool) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().set_fullscreen(id, fullscreen)
+    }
+
+    /// Get the space a window is on.
+    pub fn window_space(&self, id: u32) -> Result<CGSSpaceID, ChunkWMError> {
+        Ok(self.state.borrow().window(id)?.space)
+    }
+
+    /// Add a display after the others in the arrangement, with one user space, queueing
+    /// `DisplayAdded`.
+    pub fn add_display(&self, frame: CGRect) -> DisplayID {
+        let identifier = simulated_identifier(self.state.borrow().next_display + 1);
+        self.add_display_with_identifier(frame, identifier)
+    }
+
+    /// Add a display with a given UUID, e.g. to reconnect a display that was removed. The display
+    /// gets a new id, like a reconnected display does.
+    pub fn add_display_with_identifier(&self, frame: CGRect, identifier: DisplayUuid) -> DisplayID {
+        let mut state = self.state.borrow_mut();
+        state.next_display += 1;
+        let id = state.next_display;
+        let space = state.add_space(id, SpaceType::User);
+        let arrangement = state.displays.len() as u32;
+        state.displays.insert(
+            id,
+            SimDisplay {
+                identifier,
+                arrangement,
+                frame,
+                active_space: space,
+            },
+        );
+        state.events.push_back(Event::DisplayAdded(id));
+        id
+    }
+
+    /// Remove a display and its spaces, queueing `DisplayRemoved`. Its windows move to the active
+    /// space of the first remaining display, keeping their offset from the display's origin, which
+    /// queues `WindowMoved` for each of them.
+    pub fn remove_display(&self, id: DisplayID) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        let removed = state.displays.remove(&id).ok_or_else(no_such_display)?;
+        let first = match state.displays_by_arrangement().first() {
+            Some(first) => *first,
+            None => {
+                state.displays.insert(id, removed);
+                return Err(ChunkWMError::Internal("cannot remove the last display"));
+            }
+        };
+
+        let spaces: Vec<CGSSpaceID> = state
+            .spaces
+            .iter()
+            .filter(|&(_, space)| space.display == id)
+            .map(|(space, _)| *space)
+            .collect();
+        let (target, origin) = {
+            let first = state.display(first)?;
+            (first.active_space, first.frame.origin)
+        };
+        let mut moved = Vec::new();
+        for (id, window) in &mut state.windows {
+            if spaces.contains(&window.space) {
+                window.space = target;
+                moved.push(*id);
+            }
+        }
+        for space in spaces {
+            state.spaces.remove(&space);
+        }
+        for display in state.displays.values_mut() {
+            if display.arrangement > removed.arrangement {
+                display.arrangement -= 1;
+            }
+        }
+        if state.active_display == id {
+            state.active_display = first;
+        }
+        state.events.push_back(Event::DisplayRemoved(id));
+        for window in moved {
+            let raw = state.raw_window_mut(window)?;
+            raw.position.x += origin.x - removed.frame.origin.x;
+            raw.position.y += origin.y - removed.frame.origin.y;
+            state.push_window_event(window, Event::WindowMoved)?;
+        }
+        Ok(())
+    }
+
+    /// Move a display, queueing `DisplayMoved`.
+    pub fn move_display(&self, id: DisplayID, origin: CGPoint) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        state
+            .displays
+            .get_mut(&id)
+            .ok_or_else(no_such_display)?
+            .frame
+            .origin = origin;
+        state.events.push_back(Event::DisplayMoved(id));
+        Ok(())
+    }
+
+    /// Resize a display, queueing `DisplayResized`.
+    pub fn resize_display(&self, id: DisplayID, size: CGSize) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        state
+            .displays
+            .get_mut(&id)
+            .ok_or_else(no_such_display)?
+            .frame
+            .size = size;
+        state.events.push_back(Event::DisplayResized(id));
+        Ok(())
+    }
+
+    /// Get the display that has focus.
+    pub fn active_display(&self) -> DisplayID {
+        self.state.borrow().active_display
+    }
+
+    /// Get the arrangement index of a display; the first display is 0.
+    pub fn arrangement(&self, id: DisplayID) -> Result<u32, ChunkWMError> {
+        Ok(self.state.borrow().display(id)?.arrangement)
+    }
+
+    /// Add a space to a display.
+    pub fn add_space(
+        &self,
+        display: DisplayID,
+        space_type: SpaceType,
+    ) -> Result<CGSSpaceID, ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        state.display(display)?;
+        Ok(state.add_space(display, space_type))
+    }
+
+    /// Switch to a space, queueing `SpaceChanged`, or `DisplayChanged` when the space is on
+    /// another display than the active display.
+    pub fn switch_space(&self, space: CGSSpaceID) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().switch_space(space)
+    }
+
+    /// Move a space to a position among the spaces of its display, like dragging it in Mission
+    /// Control. No event is queued, as chunkwm doesn't send one.
+    pub fn move_space(&self, space: CGSSpaceID, position: usize) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        let display = state.spaces.get(&space).ok_or_else(no_such_space)?.display;
+        let mut spaces = state.spaces_of(display);
+        spaces.retain(|&other| other != space);
+        spaces.insert(position.min(spaces.len()), space);
+        for (order, id) in spaces.into_iter().enumerate() {
+            if let Some(space) = state.spaces.get_mut(&id) {
+                space.order = order;
+            }
+        }
+        Ok(())
+    }
+
+    /// Change the dock.
+    pub fn set_dock(&self, dock: Dock) {
+        self.state.borrow_mut().dock = dock;
+    }
+}
+
+impl WindowSystem for Simulator {
+    fn focused_application(&self) -> Result<Application, ChunkWMError> {
+        let state = self.state.borrow();
+        let pid = state
+            .active_application
+            .ok_or(ChunkWMError::Internal("no application is active"))?;
+        state.application_handle(pid)
+    }
+
+    fn focused_window(&self, application: &Application) -> Result<Window, ChunkWMError> {
+        let state = self.state.borrow();
+        let id = state
+            .application(application.pid()?)?
+            .focused_window
+            .ok_or(ChunkWMError::Internal(
+                "the application has no focused window",
+            ))?;
+        state.window_handle(id)
+    }
+
+    fn applications(&self) -> Result<Vec<Application>, ChunkWMError> {
+        let state = self.state.borrow();
+        Ok(state
+            .applications
+            .values()
+            .map(|application| Application::from(application.raw))
+            .collect())
+    }
+
+    fn window(&self, id: u32) -> Result<Window, ChunkWMError> {
+        Simulator::window(self, id)
+    }
+
+    fn windows(&self, application: &Application) -> Result<Vec<Window>, ChunkWMError> {
+        let pid = application.pid()?;
+        let state = self.state.borrow();
+        state.application(pid)?;
+        Ok(state
+            .windows
+            .keys()
+            .filter(|id| state.owner(**id).ok() == Some(pid))
+            .map(|id| Window::from(state.windows[id].raw))
+            .collect())
+    }
+
+    fn is_minimized(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        self.has_flag(window, WindowFlag::Minimized)
+    }
+
+    fn is_resizable(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        Ok(self.state.borrow().window(window.id()?)?.resizable)
+    }
+
+    fn is_movable(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        Ok(self.state.borrow().window(window.id()?)?.movable)
+    }
+
+    fn is_fullscreen(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        Ok(self
+            .state
+            .borrow()
+            .window(window.id()?)?
+            .fullscreen
+            .is_some())
+    }
+
+    fn is_standard(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        let state = self.state.borrow();
+        let window = state.window(window.id()?)?;
+        Ok(window.role == "AXWindow" && window.subrole == "AXStandardWindow")
+    }
+
+    fn role(&self, window: &Window) -> Result<String, ChunkWMError> {
+        Ok(self.state.borrow().window(window.id()?)?.role.clone())
+    }
+
+    fn subrole(&self, window: &Window) -> Result<String, ChunkWMError> {
+        Ok(self.state.borrow().window(window.id()?)?.subrole.clone())
+    }
+
+    fn set_position(&mut self, window: &Window, position: CGPoint) -> Result<(), ChunkWMError> {
+        let id = window.id()?;
+        let mut state = self.state.borrow_mut();
+        if !state.window(id)?.movable {
+            return Err(ChunkWMError::Internal(
+                "could not run set_position successfully",
+            ));
+        }
+        let size = state.frame(id)?.size;
+        state.set_frame(id, CGRect::new(&position, &size))
+    }
+
+    fn set_size(&mut self, window: &Window, size: CGSize) -> Result<(), ChunkWMError> {
+        let id = window.id()?;
+        let mut state = self.state.borrow_mut();
+        if !state.window(id)?.resizable {
+            return Err(ChunkWMError::Internal(
+                "could not run set_size successfully",
+            ));
+        }
+        let origin = state.frame(id)?.origin;
+        state.set_frame(id, CGRect::new(&origin, &size))
+    }
+
+    fn set_minimized(&mut self, window: &Window, minimized: bool) -> Result<(), ChunkWMError> {
+        self.set_window_minimized(window.id()?, minimized)
+    }
+
+    fn focus(&mut self, window: &Window) -> Result<(), ChunkWMError> {
+        self.focus_window(window.id()?)
+    }
+
+    fn close(&mut self, window: &Window) -> Result<(), ChunkWMError> {
+        self.destroy_window(window.id()?)
+    }
+
+    fn add_flag(&mut self, window: &Window, flag: WindowFlag) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().set_flag(window.id()?, flag, true)
+    }
+
+    fn remove_flag(&mut self, window: &Window, flag: WindowFlag) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().set_flag(window.id()?, flag, false)
+    }
+
+    fn active_space(&self, display: DisplayID) -> Result<CGSSpaceID, ChunkWMError> {
+        Ok(self.state.borrow().display(display)?.active_space)
+    }
+
+    fn space_has_window(&self, space: CGSSpaceID, window: &Window) -> Result<bool, ChunkWMError> {
+        Ok(self.state.borrow().window(window.id()?)?.space == space)
+    }
+
+    fn move_to_space(&mut self, window: &Window, space: CGSSpaceID) -> Result<(), ChunkWMError> {
+        let mut state = self.state.borrow_mut();
+        if !state.spaces.contains_key(&space) {
+            return Err(no_such_space());
+        }
+        state.window_mut(window.id()?)?.space = space;
+        Ok(())
+    }
+
+    fn spaces(&self, display: DisplayID) -> Result<Vec<CGSSpaceID>, ChunkWMError> {
+        let state = self.state.borrow();
+        state.display(display)?;
+        Ok(state.spaces_of(display))
+    }
+
+    fn space_type(&self, space: CGSSpaceID) -> Result<SpaceType, ChunkWMError> {
+        self.state
+            .borrow()
+            .spaces
+            .get(&space)
+            .map(|space| space.space_type)
+            .ok_or_else(no_such_space)
+    }
+
+    fn displays(&self) -> Result<Vec<DisplayID>, ChunkWMError> {
+        Ok(self.state.borrow().displays_by_arrangement())
+    }
+
+    fn display_bounds(&self, display: DisplayID) -> Result<CGRect, ChunkWMError> {
+        Ok(self.state.borrow().display(display)?.frame)
+    }
+
+    fn display_identifier(&self, display: DisplayID) -> Result<DisplayUuid, ChunkWMError> {
+        Ok(self.state.borrow().display(display)?.identifier.clone())
+    }
+
+    fn dock(&self) -> Result<Dock, ChunkWMError> {
+        Ok(self.state.borrow().dock)
+    }
+
+    fn cursor_position(&self) -> Result<CGPoint, ChunkWMError> {
+        Ok(self.state.borrow().cursor)
+    }
+
+    fn set_cursor_position(&mut self, position: CGPoint) -> Result<(), ChunkWMError> {
+        self.state.borrow_mut().cursor = position;
+        Ok(())
+    }
+}
+
+thread_local! {
+    static CVARS: RefCell<BTreeMap<String, CString>> = const { RefCell::new(BTreeMap::new()) };
+    static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+unsafe fn string(s: *const c_char) -> String {
+    CStr::from_ptr(s).to_string_lossy().into_owned()
+}
+
+unsafe extern "C" fn api_update_cvar(name: *const c_char, value: *const c_char) {
+    let (name, value) = (string(name), CStr::from_ptr(value).to_owned());
+    CVARS.with(|cvars| cvars.borrow_mut().insert(name, value));
+}
+
+unsafe extern "C" fn api_acquire_cvar(name: *const c_char) -> *const c_char {
+    let name = string(name);
+    CVARS.with(|cvars| {
+        cvars
+            .borrow()
+            .get(&name)
+            .map_or(b"\0".as_ptr() as *const c_char, |value| value.as_ptr())
+    })
+}
+
+unsafe extern "C" fn api_find_cvar(name: *const c_char) -> bool {
+    let name = string(name);
+    CVARS.with(|cvars| cvars.borrow().contains_key(&name))
+}
+
+unsafe extern "C" fn api_plugin_broadcast(
+    _plugin: *const c_char,
+    _event: *const c_char,
+    _data: *mut c_void,
+    _size: usize,
+) {
+}
+
+#[cfg(not(feature = "api-7"))]
+unsafe extern "C" fn api_log(_level: LogLevel, message: *const c_char) {
+    let message = string(message);
+    LOG.with(|log| log.borrow_mut().push(message.trim_end().to_owned()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use animation::apply_frame;
+    use api::LogLevel;
+    use borders::{BorderManager, BorderSettings, RecordingBackend};
+    use cursor::window_under_cursor;
+    use event::{EventSnapshot, Subscription};
+    use filter::EventFilters;
+    use geometry::CGFloat;
+    use state::{TrackedState, WindowMap};
+    use testing::rect;
+
+    /// The names of the events, and the window or application they are about.
+    fn names(events: Vec<Event>) -> Vec<String> {
+        events
+            .iter()
+            .map(|event| match EventSnapshot::from_event(event).unwrap() {
+                EventSnapshot::WindowCreated(w) => format!("WindowCreated {}", w.id),
+                EventSnapshot::WindowDestroyed(w) => format!("WindowDestroyed {}", w.id),
+                EventSnapshot::WindowFocused(w) => format!("WindowFocused {}", w.id),
+                EventSnapshot::WindowMoved(w) => format!("WindowMoved {}", w.id),
+                EventSnapshot::WindowResized(w) => format!("WindowResized {}", w.id),
+                EventSnapshot::WindowMinimized(w) => format!("WindowMinimized {}", w.id),
+                EventSnapshot::WindowDeminimized(w) => format!("WindowDeminimized {}", w.id),
+                EventSnapshot::WindowSheetCreated(w) => format!("WindowSheetCreated {}", w.id),
+                EventSnapshot::WindowTitleChanged(w) => format!("WindowTitleChanged {}", w.name),
+                EventSnapshot::ApplicationLaunched(a) => format!("ApplicationLaunched {}", a.pid),
+                EventSnapshot::ApplicationTerminated(a) => {
+                    format!("ApplicationTerminated {}", a.pid)
+                }
+                EventSnapshot::ApplicationActivated(a) => {
+                    format!("ApplicationActivated {}", a.pid)
+                }
+                EventSnapshot::ApplicationDeactivated(a) => {
+                    format!("ApplicationDeactivated {}", a.pid)
+                }
+                EventSnapshot::ApplicationHidden(a) => format!("ApplicationHidden {}", a.pid),
+                EventSnapshot::ApplicationUnhidden(a) => format!("ApplicationUnhidden {}", a.pid),
+                EventSnapshot::DisplayAdded(id) => format!("DisplayAdded {}", id),
+                EventSnapshot::DisplayRemoved(id) => format!("DisplayRemoved {}", id),
+                EventSnapshot::DisplayMoved(id) => format!("DisplayMoved {}", id),
+                EventSnapshot::DisplayResized(id) => format!("DisplayResized {}", id),
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn window_lifecycle_events() {
+        let simulator = Simulator::new();
+        simulator.launch(1, "Terminal");
+        simulator.launch(2, "Finder");
+        let a = simulator
+            .create_window(1, WindowSpec::new("a", rect(0.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        simulator
+            .create_sheet(1, WindowSpec::new("sheet", rect(0.0, 0.0, 50.0, 50.0)))
+            .unwrap();
+        simulator
+            .create_window(2, WindowSpec::new("b", rect(0.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        simulator.focus_window(1).unwrap();
+        simulator.focus_window(3).unwrap();
+        simulator.activate(1).unwrap();
+        simulator
+            .set_window_frame(1, rect(10.0, 0.0, 100.0, 100.0))
+            .unwrap();
+        simulator
+            .set_window_frame(1, rect(10.0, 0.0, 200.0, 100.0))
+            .unwrap();
+        simulator.set_window_minimized(1, true).unwrap();
+        simulator.set_window_minimized(1, true).unwrap();
+        simulator.set_window_minimized(1, false).unwrap();
+        simulator.set_window_name(1, "vim").unwrap();
+        simulator.set_hidden(2, true).unwrap();
+        simulator.set_hidden(2, false).unwrap();
+        simulator.destroy_window(1).unwrap();
+        simulator.terminate(2).unwrap();
+
+        assert_eq!(
+            names(simulator.take_events()),
+            vec![
+                "ApplicationLaunched 1",
+                "ApplicationLaunched 2",
+                "WindowCreated 1",
+                "WindowSheetCreated 2",
+                "WindowCreated 3",
+                "ApplicationActivated 1",
+                "WindowFocused 1",
+                "ApplicationDeactivated 1",
+                "ApplicationActivated 2",
+                "WindowFocused 3",
+                "ApplicationDeactivated 2",
+                "ApplicationActivated 1",
+                "WindowFocused 1",
+                "WindowMoved 1",
+                "WindowResized 1",
+                "WindowMinimized 1",
+                "WindowDeminimized 1",
+                "WindowTitleChanged vim",
+                "ApplicationHidden 2",
+                "ApplicationUnhidden 2",
+                "WindowDestroyed 1",
+                "ApplicationTerminated 2",
+            ]
+        );
+
+        // The handles of destroyed windows stay valid.
+        assert_eq!(a.name().unwrap(), "vim");
+        assert_eq!(a.size().unwrap().width, 200.0);
+        assert!(simulator.window(1).is_err());
+        assert!(simulator.window(3).is_err());
+        assert_eq!(simulator.focused_window_id(), None);
+    }
+
+    #[test]
+    fn window_system_operations() {
+        let mut simulator = Simulator::new();
+        let second = simulator.add_display(rect(1920.0, 0.0, 1280.0, 800.0));
+        let application = simulator.launch(1, "Terminal");
+        let mut spec = WindowSpec::new("a", rect(0.0, 0.0, 100.0, 100.0));
+        spec.subrole = "AXDialog".to_owned();
+        spec.resizable = false;
+        let dialog = simulator.create_window(1, spec).unwrap();
+        let window = simulator
+            .create_window(1, WindowSpec::new("b", rect(100.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        simulator.take_events();
+
+        assert_eq!(simulator.displays().unwrap(), vec![1, second]);
+        assert_eq!(simulator.windows(&application).unwrap().len(), 2);
+        assert!(!simulator.is_standard(&dialog).unwrap());
+        assert!(simulator.is_standard(&window).unwrap());
+        assert!(simulator
+            .set_size(&dialog, CGSize::new(10.0, 10.0))
+            .is_err());
+
+        simulator.focus(&window).unwrap();
+        let focused = simulator.focused_application().unwrap();
+        assert_eq!(simulator.focused_window(&focused).unwrap().id().unwrap(), 2);
+
+        apply_frame(&mut simulator, &window, &rect(2000.0, 0.0, 300.0, 300.0)).unwrap();
+        assert_eq!(simulator.window_display(&window).unwrap(), second);
+        let space = simulator.active_space(second).unwrap();
+        assert!(simulator.space_has_window(space, &window).unwrap());
+        assert_eq!(simulator.window_space(2).unwrap(), space);
+
+        simulator.add_flag(&window, WindowFlag::Float).unwrap();
+        assert!(simulator.has_flag(&window, WindowFlag::Float).unwrap());
+        assert_eq!(window.flags().unwrap(), vec![WindowFlag::Float]);
+        simulator.remove_flag(&window, WindowFlag::Float).unwrap();
+        assert!(window.flags().unwrap().is_empty());
+
+        simulator
+            .set_cursor_position(CGPoint::new(2100.0, 100.0))
+            .unwrap();
+        let windows = [dialog, window];
+        let under = window_under_cursor(&simulator, &windows).unwrap();
+        assert_eq!(under.map(|window| window.id().unwrap()), Some(2));
+        assert_eq!(
+            simulator.dock().unwrap().orientation,
+            DockOrientation::Bottom
+        );
+
+        simulator.close(&windows[1]).unwrap();
+        assert_eq!(
+            names(simulator.take_events()),
+            vec![
+                "ApplicationActivated 1",
+                "WindowFocused 2",
+                "WindowMoved 2",
+                "WindowResized 2",
+                "WindowDestroyed 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn displays_and_spaces() {
+        let simulator = Simulator::new();
+        simulator.launch(1, "Terminal");
+        let second = simulator.add_display(rect(1920.0, 0.0, 1280.0, 800.0));
+        let window = simulator
+            .create_window(1, WindowSpec::new("a", rect(2000.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        let first_space = simulator.window_space(1).unwrap();
+        let space = simulator.add_space(second, SpaceType::User).unwrap();
+        assert_eq!(simulator.spaces(second).unwrap(), vec![first_space, space]);
+        assert_eq!(simulator.desktop_id(space).unwrap(), 3);
+        simulator.move_space(space, 0).unwrap();
+        assert_eq!(simulator.desktop_id(space).unwrap(), 2);
+        assert_eq!(simulator.desktop_space(3).unwrap(), first_space);
+        assert!(simulator.desktop_space(4).is_err());
+        simulator.switch_space(space).unwrap();
+        simulator.switch_space(first_space).unwrap();
+        simulator.take_events();
+
+        simulator.set_fullscreen(1, true).unwrap();
+        let fullscreen = simulator.window_space(1).unwrap();
+        assert_eq!(
+            simulator.space_type(fullscreen).unwrap(),
+            SpaceType::Fullscreen
+        );
+        assert!(simulator.is_fullscreen(&window).unwrap());
+        assert_eq!(window.size().unwrap().width, 1280.0);
+        simulator.set_fullscreen(1, false).unwrap();
+        assert_eq!(simulator.window_space(1).unwrap(), first_space);
+        assert!(simulator.space_type(fullscreen).is_err());
+        assert_eq!(window.position().unwrap().x, 2000.0);
+
+        simulator.switch_space(1).unwrap();
+        assert_eq!(
+            simulator.display_identifier(second).unwrap().as_str(),
+            "00000000-0000-0000-0000-000000000002"
+        );
+        simulator
+            .move_display(second, CGPoint::new(0.0, 1080.0))
+            .unwrap();
+        simulator
+            .resize_display(second, CGSize::new(1440.0, 900.0))
+            .unwrap();
+        simulator.remove_display(second).unwrap();
+        assert_eq!(simulator.window_space(1).unwrap(), 1);
+        assert!(simulator.remove_display(1).is_err());
+
+        assert_eq!(
+            names(simulator.take_events()),
+            vec![
+                "SpaceChanged",
+                "WindowMoved 1",
+                "WindowResized 1",
+                "SpaceChanged",
+                "WindowMoved 1",
+                "WindowResized 1",
+                "DisplayChanged",
+                format!("DisplayMoved {}", second).as_str(),
+                format!("DisplayResized {}", second).as_str(),
+                format!("DisplayRemoved {}", second).as_str(),
+                "WindowMoved 1",
+            ]
+        );
+    }
+
+    /// A plugin that puts new windows in the top left corner and keeps a border around the
+    /// focused window.
+    struct Plugin {
+        system: Simulator,
+        api: API,
+        borders: BorderManager<RecordingBackend>,
+        created: WindowMap<String>,
+    }
+
+    impl HandleEvent for Plugin {
+        fn new(api: API) -> Plugin {
+            Plugin {
+                system: Simulator::new(),
+                api,
+                borders: BorderManager::new(RecordingBackend::new(), BorderSettings::default()),
+                created: WindowMap::new(),
+            }
+        }
+
+        fn subscribe() -> &'static [Subscription] {
+            &[
+                Subscription::WindowCreated,
+                Subscription::WindowDestroyed,
+                Subscription::WindowFocused,
+                Subscription::WindowMoved,
+            ]
+        }
+
+        fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+            if let Event::WindowCreated(ref window) = event {
+                let gap: CGFloat = self.api.get_cvar("plugin_gap").unwrap_or(0.0);
+                self.system.set_position(window, CGPoint::new(gap, gap))?;
+                self.created.insert(window, window.name()?)?;
+                self.api
+                    .log(LogLevel::Debug, format!("placed {}", window.name()?));
+            }
+            self.borders.handle(&event)
+        }
+
+        fn shutdown(&self) {}
+
+        fn tracked_state(&mut self) -> Vec<&mut dyn TrackedState> {
+            vec![&mut self.created]
+        }
+    }
+
+    #[test]
+    fn run_a_plugin() {
+        let simulator = Simulator::new();
+        simulator.set_cvar("plugin_gap", 10);
+        let mut plugin: Plugin = simulator.start();
+        plugin.system = simulator.clone();
+
+        simulator.launch(1, "Terminal");
+        simulator
+            .create_window(1, WindowSpec::new("a", rect(500.0, 500.0, 100.0, 100.0)))
+            .unwrap();
+        simulator.focus_window(1).unwrap();
+        // The launch and activation aren't subscribed to; the move the plugin causes is.
+        assert_eq!(simulator.deliver(&mut plugin).unwrap(), 3);
+
+        let frame = simulator.window(1).unwrap().frame().unwrap();
+        assert_eq!((frame.origin.x, frame.origin.y), (10.0, 10.0));
+        assert_eq!(plugin.created.get(1).map(String::as_str), Some("a"));
+        let border = plugin.borders.backend().live();
+        assert_eq!(border.len(), 1);
+        assert_eq!(
+            plugin.borders.backend().border(border[0]).unwrap().0,
+            (10, 10, 100, 100)
+        );
+        #[cfg(not(feature = "api-7"))]
+        assert_eq!(simulator.take_log(), vec!["placed a"]);
+
+        simulator.destroy_window(1).unwrap();
+        simulator.deliver(&mut plugin).unwrap();
+        assert!(plugin.created.is_empty());
+        assert!(plugin.borders.backend().live().is_empty());
+    }
+
+    /// A plugin that only handles the windows Terminal creates, and remembers them.
+    struct Filtered {
+        system: Simulator,
+        filters: EventFilters,
+        created: WindowMap<String>,
+    }
+
+    impl HandleEvent for Filtered {
+        fn new(_: API) -> Filtered {
+            let mut filters = EventFilters::new();
+            filters.set(Subscription::WindowCreated, "app=Terminal".parse().unwrap());
+            Filtered {
+                system: Simulator::new(),
+                filters,
+                created: WindowMap::new(),
+            }
+        }
+
+        fn subscribe() -> &'static [Subscription] {
+            &[Subscription::WindowCreated]
+        }
+
+        fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+            if let Event::WindowCreated(ref window) = event {
+                self.created.insert(window, window.name()?)?;
+            }
+            Ok(())
+        }
+
+        fn shutdown(&self) {}
+
+        fn tracked_state(&mut self) -> Vec<&mut dyn TrackedState> {
+            vec![&mut self.created]
+        }
+
+        fn filters(&mut self) -> Option<(&EventFilters, &dyn WindowSystem)> {
+            Some((&self.filters, &self.system))
+        }
+    }
+
+    #[test]
+    fn deliver_applies_filters() {
+        let simulator = Simulator::new();
+        let mut plugin: Filtered = simulator.start();
+        plugin.system = simulator.clone();
+
+        simulator.launch(1, "Terminal");
+        simulator.launch(2, "Safari");
+        simulator
+            .create_window(1, WindowSpec::new("zsh", rect(0.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        simulator
+            .create_window(2, WindowSpec::new("GitHub", rect(0.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        assert_eq!(simulator.deliver(&mut plugin).unwrap(), 1);
+        assert_eq!(
+            plugin
+                .created
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["zsh"]
+        );
+
+        // The destruction isn't handled, but the window's state is still removed.
+        simulator.destroy_window(1).unwrap();
+        assert_eq!(simulator.deliver(&mut plugin).unwrap(), 0);
+        assert!(plugin.created.is_empty());
+    }
+}
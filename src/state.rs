@@ -0,0 +1,508 @@
+//! The `state` module contains maps for per-window and per-application plugin state, which are
+//! cleaned up automatically.
+//!
+//! A plugin lists its maps in `HandleEvent::tracked_state`. After `handle` has been called with a
+//! `WindowDestroyed` or `ApplicationTerminated` event, `chunkwm_plugin!` removes the entries of the
+//! window, or of the application and all its windows, from these maps, calling the maps' removal
+//! hooks. Because the entries are removed after `handle`, the plugin can still use them while
+//! handling the event.
+//!
+//! `chunkwm_plugin!` subscribes to these events for every plugin (see `cleanup_subscriptions`),
+//! so the maps are cleaned up even if the plugin doesn't subscribe to them itself. The events are
+//! only passed on to `handle` if the plugin did.
+//!
+//! ## Example
+//! ```rust
+//! # #[macro_use] extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::state::{TrackedState, WindowMap};
+//!
+//! pub struct Plugin {
+//!     focus_count: WindowMap<u32>,
+//! }
+//!
+//! chunkwm_plugin!{ Plugin }
+//!
+//! impl HandleEvent for Plugin {
+//!     fn new(_: API) -> Plugin {
+//!         let mut focus_count = WindowMap::new();
+//!         focus_count.on_remove(|id, count| println!("window {} was focused {} times", id, count));
+//!         Plugin { focus_count }
+//!     }
+//!
+//!     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+//!         if let Event::WindowFocused(window) = event {
+//!             *self.focus_count.entry(&window, 0)? += 1;
+//!         }
+//!         Ok(())
+//!     }
+//!
+//!     fn tracked_state(&mut self) -> Vec<&mut dyn TrackedState> {
+//!         vec![&mut self.focus_count]
+//!     }
+//!     // some methods omitted
+//!     # subscribe_all!();
+//!     # fn shutdown(&self) {}
+//! }
+//! # fn main() {}
+//! ```
+
+use application::Application;
+use event::{Event, Subscription};
+use raw::PID;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+use window::Window;
+use ChunkWMError;
+
+/// State that is cleaned up when windows are destroyed and applications terminate.
+pub trait TrackedState {
+    /// Remove the state of a destroyed window.
+    fn window_destroyed(&mut self, id: u32);
+    /// Remove the state of a terminated application, and of its windows.
+    fn application_terminated(&mut self, pid: PID);
+}
+
+/// The cleanup an event requires, see `TrackedState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cleanup {
+    Window(u32),
+    Application(PID),
+}
+
+impl Cleanup {
+    /// The cleanup for an event, if any. Events with a null pointer require no cleanup.
+    pub fn from_event(event: &Event) -> Option<Cleanup> {
+        match *event {
+            Event::WindowDestroyed(ref window) => window.id().ok().map(Cleanup::Window),
+            Event::ApplicationTerminated(ref application) => {
+                application.pid().ok().map(Cleanup::Application)
+            }
+            _ => None,
+        }
+    }
+
+    /// Clean up the state.
+    pub fn apply(self, state: &mut [&mut dyn TrackedState]) {
+        for state in state.iter_mut() {
+            match self {
+                Cleanup::Window(id) => state.window_destroyed(id),
+                Cleanup::Application(pid) => state.application_terminated(pid),
+            }
+        }
+    }
+}
+
+/// The events `TrackedState` is cleaned up on.
+pub const CLEANUP_EVENTS: &[Subscription] = &[
+    Subscription::WindowDestroyed,
+    Subscription::ApplicationTerminated,
+];
+
+/// The plugin's subscriptions, followed by the `CLEANUP_EVENTS` it didn't subscribe to.
+pub fn cleanup_subscriptions(subscriptions: &[Subscription]) -> Vec<Subscription> {
+    let missing = CLEANUP_EVENTS
+        .iter()
+        .filter(|subscription| !subscriptions.contains(subscription));
+    subscriptions.iter().chain(missing).cloned().collect()
+}
+
+/// A map that iterates in insertion order.
+struct OrderedMap<K, T> {
+    next: u64,
+    order: BTreeMap<u64, K>,
+    entries: HashMap<K, (u64, T)>,
+}
+
+impl<K: Copy + Eq + Hash, T> OrderedMap<K, T> {
+    fn new() -> OrderedMap<K, T> {
+        OrderedMap {
+            next: 0,
+            order: BTreeMap::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn insert(&mut self, key: K, value: T) -> Option<T> {
+        if let Some(entry) = self.entries.get_mut(&key) {
+            return Some(::std::mem::replace(&mut entry.1, value));
+        }
+        self.order.insert(self.next, key);
+        self.entries.insert(key, (self.next, value));
+        self.next += 1;
+        None
+    }
+
+    fn entry(&mut self, key: K, default: T) -> &mut T {
+        if !self.entries.contains_key(&key) {
+            self.insert(key, default);
+        }
+        &mut self.entries.get_mut(&key).unwrap().1
+    }
+
+    fn remove(&mut self, key: &K) -> Option<T> {
+        self.entries.remove(key).map(|(index, value)| {
+            self.order.remove(&index);
+            value
+        })
+    }
+
+    fn get(&self, key: &K) -> Option<&T> {
+        self.entries.get(key).map(|entry| &entry.1)
+    }
+
+    fn get_mut(&mut self, key: &K) -> Option<&mut T> {
+        self.entries.get_mut(key).map(|entry| &mut entry.1)
+    }
+
+    fn keys<'a>(&'a self) -> impl Iterator<Item = K> + 'a {
+        self.order.values().cloned()
+    }
+
+    fn iter<'a>(&'a self) -> impl Iterator<Item = (K, &'a T)> + 'a {
+        self.order
+            .values()
+            .map(move |key| (*key, &self.entries[key].1))
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clear(&mut self) {
+        self.order.clear();
+        self.entries.clear();
+    }
+}
+
+/// A hook that is called with the entries the dispatch machinery removes.
+type Hook<K, T> = Box<dyn FnMut(K, T) + Send>;
+
+/// A map from window ids to state. Entries are removed when the window is destroyed or its
+/// application terminates, if the map is listed in `HandleEvent::tracked_state`.
+pub struct WindowMap<T> {
+    map: OrderedMap<u32, (Option<PID>, T)>,
+    hook: Option<Hook<u32, T>>,
+}
+
+impl<T> Default for WindowMap<T> {
+    fn default() -> WindowMap<T> {
+        WindowMap::new()
+    }
+}
+
+impl<T> WindowMap<T> {
+    /// Create an empty map, without a removal hook.
+    pub fn new() -> WindowMap<T> {
+        WindowMap {
+            map: OrderedMap::new(),
+            hook: None,
+        }
+    }
+
+    /// Call `hook` with the id and state of every window that is removed because it was destroyed
+    /// or its application terminated. Explicit removals don't call the hook. The hook must be
+    /// `Send`, like the plugin the map is part of.
+    pub fn on_remove<F: FnMut(u32, T) + Send + 'static>(&mut self, hook: F) {
+        self.hook = Some(Box::new(hook));
+    }
+
+    /// Insert the state of a window, returning the previous state. A window that is already in
+    /// the map keeps its position in the iteration order.
+    pub fn insert(&mut self, window: &Window, value: T) -> Result<Option<T>, ChunkWMError> {
+        let pid = window.owner()?.pid()?;
+        Ok(self.insert_id(window.id()?, Some(pid), value))
+    }
+
+    /// Insert the state of a window by id. Without an owner, the entry is only removed when the
+    /// window itself is destroyed.
+    pub fn insert_id(&mut self, id: u32, owner: Option<PID>, value: T) -> Option<T> {
+        self.map
+            .insert(id, (owner, value))
+            .map(|(_, previous)| previous)
+    }
+
+    /// Get the state of a window, inserting `default` if there is none.
+    pub fn entry(&mut self, window: &Window, default: T) -> Result<&mut T, ChunkWMError> {
+        let id = window.id()?;
+        let pid = window.owner()?.pid()?;
+        Ok(&mut self.map.entry(id, (Some(pid), default)).1)
+    }
+
+    /// The state of a window.
+    pub fn get(&self, id: u32) -> Option<&T> {
+        self.map.get(&id).map(|entry| &entry.1)
+    }
+
+    /// The state of a window, mutably.
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut T> {
+        self.map.get_mut(&id).map(|entry| &mut entry.1)
+    }
+
+    /// Check whether the map contains the state of a window.
+    pub fn contains(&self, id: u32) -> bool {
+        self.map.get(&id).is_some()
+    }
+
+    /// Remove the state of a window, without calling the hook.
+    pub fn remove(&mut self, id: u32) -> Option<T> {
+        self.map.remove(&id).map(|entry| entry.1)
+    }
+
+    /// The window ids, in insertion order.
+    pub fn ids<'a>(&'a self) -> impl Iterator<Item = u32> + 'a {
+        self.map.keys()
+    }
+
+    /// The window ids and their state, in insertion order.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (u32, &'a T)> + 'a {
+        self.map.iter().map(|(id, entry)| (id, &entry.1))
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.len() == 0
+    }
+
+    /// Remove all state, without calling the hook.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    fn remove_tracked(&mut self, id: u32) {
+        if let Some((_, value)) = self.map.remove(&id) {
+            if let Some(ref mut hook) = self.hook {
+                hook(id, value);
+            }
+        }
+    }
+}
+
+impl<T> TrackedState for WindowMap<T> {
+    fn window_destroyed(&mut self, id: u32) {
+        self.remove_tracked(id);
+    }
+
+    fn application_terminated(&mut self, pid: PID) {
+        let ids: Vec<u32> = self
+            .map
+            .iter()
+            .filter(|&(_, entry)| entry.0 == Some(pid))
+            .map(|(id, _)| id)
+            .collect();
+        for id in ids {
+            self.remove_tracked(id);
+        }
+    }
+}
+
+/// A map from application process ids to state. Entries are removed when the application
+/// terminates, if the map is listed in `HandleEvent::tracked_state`.
+pub struct ApplicationMap<T> {
+    map: OrderedMap<PID, T>,
+    hook: Option<Hook<PID, T>>,
+}
+
+impl<T> Default for ApplicationMap<T> {
+    fn default() -> ApplicationMap<T> {
+        ApplicationMap::new()
+    }
+}
+
+impl<T> ApplicationMap<T> {
+    /// Create an empty map, without a removal hook.
+    pub fn new() -> ApplicationMap<T> {
+        ApplicationMap {
+            map: OrderedMap::new(),
+            hook: None,
+        }
+    }
+
+    /// Call `hook` with the process id and state of every application that is removed because it
+    /// terminated. Explicit removals don't call the hook. The hook must be `Send`, like the plugin
+    /// the map is part of.
+    pub fn on_remove<F: FnMut(PID, T) + Send + 'static>(&mut self, hook: F) {
+        self.hook = Some(Box::new(hook));
+    }
+
+    /// Insert the state of an application, returning the previous state.
+    pub fn insert(
+        &mut self,
+        application: &Application,
+        value: T,
+    ) -> Result<Option<T>, ChunkWMError> {
+        Ok(self.insert_pid(application.pid()?, value))
+    }
+
+    /// Insert the state of an application by process id.
+    pub fn insert_pid(&mut self, pid: PID, value: T) -> Option<T> {
+        self.map.insert(pid, value)
+    }
+
+    /// Get the state of an application, inserting `default` if there is none.
+    pub fn entry(&mut self, application: &Application, default: T) -> Result<&mut T, ChunkWMError> {
+        Ok(self.map.entry(application.pid()?, default))
+    }
+
+    /// The state of an application.
+    pub fn get(&self, pid: PID) -> Option<&T> {
+        self.map.get(&pid)
+    }
+
+    /// The state of an application, mutably.
+    pub fn get_mut(&mut self, pid: PID) -> Option<&mut T> {
+        self.map.get_mut(&pid)
+    }
+
+    /// Check whether the map contains the state of an application.
+    pub fn contains(&self, pid: PID) -> bool {
+        self.map.get(&pid).is_some()
+    }
+
+    /// Remove the state of an application, without calling the hook.
+    pub fn remove(&mut self, pid: PID) -> Option<T> {
+        self.map.remove(&pid)
+    }
+
+    /// The process ids, in insertion order.
+    pub fn pids<'a>(&'a self) -> impl Iterator<Item = PID> + 'a {
+        self.map.keys()
+    }
+
+    /// The process ids and their state, in insertion order.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (PID, &'a T)> + 'a {
+        self.map.iter()
+    }
+
+    /// The number of entries.
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Check whether the map has no entries.
+    pub fn is_empty(&self) -> bool {
+        self.map.len() == 0
+    }
+
+    /// Remove all state, without calling the hook.
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<T> TrackedState for ApplicationMap<T> {
+    fn window_destroyed(&mut self, _: u32) {}
+
+    fn application_terminated(&mut self, pid: PID) {
+        if let Some(value) = self.map.remove(&pid) {
+            if let Some(ref mut hook) = self.hook {
+                hook(pid, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use testing;
+
+    #[test]
+    fn cleanup_subscriptions_are_added() {
+        assert_eq!(
+            cleanup_subscriptions(&[Subscription::WindowFocused]),
+            vec![
+                Subscription::WindowFocused,
+                Subscription::WindowDestroyed,
+                Subscription::ApplicationTerminated,
+            ]
+        );
+        // Subscriptions are not duplicated.
+        assert_eq!(
+            cleanup_subscriptions(&[Subscription::ApplicationTerminated]),
+            vec![
+                Subscription::ApplicationTerminated,
+                Subscription::WindowDestroyed,
+            ]
+        );
+        assert_eq!(
+            cleanup_subscriptions(Subscription::all()),
+            Subscription::all()
+        );
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut map = WindowMap::new();
+        map.insert_id(3, None, "c");
+        map.insert_id(1, None, "a");
+        map.insert_id(2, None, "b");
+        // Replacing a value keeps the position.
+        assert_eq!(map.insert_id(3, None, "C"), Some("c"));
+        map.remove(1);
+        map.insert_id(1, None, "A");
+
+        let entries: Vec<_> = map.iter().map(|(id, &value)| (id, value)).collect();
+        assert_eq!(entries, vec![(3, "C"), (2, "b"), (1, "A")]);
+        assert_eq!(map.ids().collect::<Vec<_>>(), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn cleanup_from_events() {
+        let terminal = testing::application(100, "Terminal");
+        let safari = testing::application(200, "Safari");
+        let shell = testing::window(&terminal, 1, "~", (0.0, 0.0, 10.0, 10.0));
+        let editor = testing::window(&terminal, 2, "vim", (0.0, 0.0, 10.0, 10.0));
+        let browser = testing::window(&safari, 3, "GitHub", (0.0, 0.0, 10.0, 10.0));
+
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let mut windows = WindowMap::new();
+        {
+            let removed = removed.clone();
+            windows.on_remove(move |id, value| removed.lock().unwrap().push((id, value)));
+        }
+        *windows.entry(&shell, 0).unwrap() += 1;
+        *windows.entry(&editor, 0).unwrap() += 2;
+        *windows.entry(&browser, 0).unwrap() += 3;
+        let mut applications = ApplicationMap::new();
+        applications.insert(&terminal, "dark").unwrap();
+        applications.insert(&safari, "light").unwrap();
+
+        let event = Event::WindowDestroyed(testing::same_window(&browser));
+        let cleanup = Cleanup::from_event(&event).unwrap();
+        assert_eq!(cleanup, Cleanup::Window(3));
+        cleanup.apply(&mut [&mut windows, &mut applications]);
+        assert_eq!(*removed.lock().unwrap(), vec![(3, 3)]);
+        assert_eq!(applications.len(), 2);
+
+        let event = Event::ApplicationTerminated(testing::same_application(&terminal));
+        Cleanup::from_event(&event)
+            .unwrap()
+            .apply(&mut [&mut windows, &mut applications]);
+        assert_eq!(*removed.lock().unwrap(), vec![(3, 3), (1, 1), (2, 2)]);
+        assert!(windows.is_empty());
+        assert_eq!(applications.pids().collect::<Vec<_>>(), vec![200]);
+
+        assert!(Cleanup::from_event(&Event::SpaceChanged).is_none());
+    }
+
+    #[test]
+    fn explicit_removal_skips_hook() {
+        let called = Arc::new(Mutex::new(false));
+        let mut applications = ApplicationMap::new();
+        {
+            let called = called.clone();
+            applications.on_remove(move |_, _: u32| *called.lock().unwrap() = true);
+        }
+        applications.insert_pid(100, 1);
+        assert_eq!(applications.remove(100), Some(1));
+        applications.application_terminated(100);
+        assert!(!*called.lock().unwrap());
+    }
+}
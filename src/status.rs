@@ -0,0 +1,450 @@
+//! The `status` module contains a `StatusExport`, which keeps a JSON file up to date with the
+//! state status bars show, so that a bar can watch one file instead of polling `chunkc`.
+//!
+//! The file is written whenever an event changes the state, but at most once per interval
+//! (100 milliseconds by default): changes within the interval are written together when a timer
+//! expires. The file is replaced at once, so it is never read half written, and it is not
+//! touched when the document didn't change.
+//!
+//! The document is one line of JSON, shown over several lines here:
+//!
+//! ```json
+//! {"displays":[{"id":1,"uuid":"37D8832A-2D66-02CA-B9F7-8F30A301B230","focused":true,
+//!   "space":1,"desktop":1,"label":"code","mode":"bsp",
+//!   "spaces":[{"id":1,"desktop":1,"label":"code","windows":2},
+//!             {"id":2,"desktop":2,"label":null,"windows":0}]}],
+//!  "focused":{"application":"Terminal","title":"zsh","display":1,"space":1}}
+//! ```
+//!
+//! `space`, `desktop`, `label` and `mode` of a display describe its active desktop. Windows are
+//! counted when they are standard windows and not minimized. Labels come from
+//! [`set_labels`](struct.StatusExport.html#method.set_labels), e.g. from a `SpaceLabels`, and
+//! layout modes from [`set_mode`](struct.StatusExport.html#method.set_mode) and
+//! [`set_space_mode`](struct.StatusExport.html#method.set_space_mode).
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::status::StatusExport;
+//! use chunkwm::system::WindowSystem;
+//!
+//! fn handle<S: WindowSystem>(
+//!     status: &mut StatusExport<S>,
+//!     event: &Event,
+//! ) -> Result<(), ChunkWMError> {
+//!     status.handle(event)?;
+//!     // handle other events
+//!     Ok(())
+//! }
+//! # fn main() {}
+//! ```
+
+use event::{DisplayID, Event};
+use files::write_atomically;
+use json::Json;
+use raw::CGSSpaceID;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use system::WindowSystem;
+use timer::{self, Clock, SystemClock, TimerId};
+use window::Window;
+use ChunkWMError;
+
+/// Schedules the timer that writes changes that came in too soon after the last write.
+type Scheduler = Box<dyn FnMut(Duration) -> Result<TimerId, ChunkWMError> + Send>;
+
+/// Writes the state status bars show to a file, as JSON.
+pub struct StatusExport<S: WindowSystem, C: Clock = SystemClock> {
+    system: S,
+    clock: C,
+    path: PathBuf,
+    interval: Duration,
+    schedule: Scheduler,
+    mode: Option<String>,
+    space_modes: BTreeMap<CGSSpaceID, String>,
+    labels: BTreeMap<CGSSpaceID, String>,
+    /// Whether something changed since the last write.
+    dirty: bool,
+    last_write: Option<Duration>,
+    pending: Option<TimerId>,
+    written: Option<String>,
+}
+
+impl<S: WindowSystem> StatusExport<S> {
+    /// Create a new `StatusExport` that writes to a file.
+    pub fn new<P: Into<PathBuf>>(system: S, path: P) -> StatusExport<S> {
+        StatusExport::with_clock(system, path, SystemClock::new())
+    }
+}
+
+impl<S: WindowSystem, C: Clock> StatusExport<S, C> {
+    /// Create a new `StatusExport` that uses a different clock for the interval.
+    pub fn with_clock<P: Into<PathBuf>>(system: S, path: P, clock: C) -> StatusExport<S, C> {
+        StatusExport {
+            system,
+            clock,
+            path: path.into(),
+            interval: Duration::from_millis(100),
+            schedule: Box::new(timer::after),
+            mode: None,
+            space_modes: BTreeMap::new(),
+            labels: BTreeMap::new(),
+            dirty: false,
+            last_write: None,
+            pending: None,
+            written: None,
+        }
+    }
+
+    /// Write the file at most once per `interval`.
+    pub fn with_interval(mut self, interval: Duration) -> StatusExport<S, C> {
+        self.interval = interval;
+        self
+    }
+
+    /// Schedule the delayed writes with a different function than `timer::after`. The function
+    /// gets the delay, and the returned timer must be delivered to `handle` as `Event::Timer`. The
+    /// function must be `Send`, like the plugin the export is part of.
+    pub fn with_scheduler<F>(mut self, schedule: F) -> StatusExport<S, C>
+    where
+        F: FnMut(Duration) -> Result<TimerId, ChunkWMError> + Send + 'static,
+    {
+        self.schedule = Box::new(schedule);
+        self
+    }
+
+    /// Get the window system.
+    pub fn system(&self) -> &S {
+        &self.system
+    }
+
+    /// Get the window system mutably.
+    pub fn system_mut(&mut self) -> &mut S {
+        &mut self.system
+    }
+
+    /// Get the path of the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Set the layout mode of the desktops that have no mode of their own, e.g. the value of the
+    /// `global_desktop_mode` `CVar`.
+    pub fn set_mode(&mut self, mode: Option<String>) -> Result<(), ChunkWMError> {
+        self.mode = mode;
+        self.changed()
+    }
+
+    /// Set the layout mode of a desktop.
+    pub fn set_space_mode(
+        &mut self,
+        space: CGSSpaceID,
+        mode: Option<String>,
+    ) -> Result<(), ChunkWMError> {
+        match mode {
+            Some(mode) => self.space_modes.insert(space, mode),
+            None => self.space_modes.remove(&space),
+        };
+        self.changed()
+    }
+
+    /// Set the labels of spaces, e.g. from `SpaceLabels::labels`. The labels that were set before
+    /// are removed.
+    pub fn set_labels<I, L>(&mut self, labels: I) -> Result<(), ChunkWMError>
+    where
+        I: IntoIterator<Item = (CGSSpaceID, L)>,
+        L: Into<String>,
+    {
+        let labels = labels
+            .into_iter()
+            .map(|(space, label)| (space, label.into()))
+            .collect();
+        if labels != self.labels {
+            self.labels = labels;
+            self.changed()?;
+        }
+        Ok(())
+    }
+
+    /// Update the file after an event that changes the state, or when the timer for a delayed
+    /// write expires.
+    pub fn handle(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        match *event {
+            Event::Timer(id) if self.pending == Some(id) => {
+                self.pending = None;
+                self.flush()?;
+            }
+            Event::Timer(_) | Event::DaemonCommand(_) | Event::Other(_) => {}
+            _ => self.changed()?,
+        }
+        Ok(())
+    }
+
+    /// Write the file if something changed since the last write, even within the interval.
+    /// Returns whether the file was written.
+    pub fn flush(&mut self) -> Result<bool, ChunkWMError> {
+        if !self.dirty {
+            return Ok(false);
+        }
+        self.write()?;
+        Ok(true)
+    }
+
+    /// Write the file now, unless its contents would stay the same.
+    pub fn write(&mut self) -> Result<(), ChunkWMError> {
+        let document = self.document()?;
+        self.dirty = false;
+        self.last_write = Some(self.clock.now());
+        if self.written.as_ref() != Some(&document) {
+            write_atomically(&self.path, &document)
+                .map_err(|_| ChunkWMError::Internal("could not write the status file"))?;
+            self.written = Some(document);
+        }
+        Ok(())
+    }
+
+    /// Get the JSON document, followed by a newline.
+    pub fn document(&self) -> Result<String, ChunkWMError> {
+        let windows = self.windows()?;
+        let focused = self
+            .system
+            .focused_application()
+            .and_then(|application| self.system.focused_window(&application))
+            .ok();
+        let focused_display = focused
+            .as_ref()
+            .and_then(|window| self.system.window_display(window).ok());
+
+        let mut displays = Vec::new();
+        for display in self.system.displays()? {
+            displays.push(self.display(display, focused_display, &windows)?);
+        }
+        let focused = match focused {
+            Some(ref window) => self.focused(window, focused_display)?,
+            None => Json::Null,
+        };
+        let document = Json::object(vec![
+            ("displays", Json::Array(displays)),
+            ("focused", focused),
+        ]);
+        Ok(format!("{}\n", document))
+    }
+
+    /// Write now if the interval has passed since the last write; otherwise make sure a delayed
+    /// write is scheduled. When it cannot be scheduled, the next change after the interval writes
+    /// the file.
+    fn changed(&mut self) -> Result<(), ChunkWMError> {
+        self.dirty = true;
+        let since = self
+            .last_write
+            .map(|last| self.clock.now().checked_sub(last).unwrap_or_default());
+        match since {
+            Some(since) if since < self.interval => {
+                if self.pending.is_none() {
+                    self.pending = (self.schedule)(self.interval - since).ok();
+                }
+                Ok(())
+            }
+            _ => self.write(),
+        }
+    }
+
+    fn display(
+        &self,
+        display: DisplayID,
+        focused: Option<DisplayID>,
+        windows: &[Window],
+    ) -> Result<Json, ChunkWMError> {
+        let active = self.system.active_space(display)?;
+        let mut spaces = Vec::new();
+        for space in self.system.spaces(display)? {
+            let mut count = 0;
+            for window in windows {
+                if self.system.space_has_window(space, window)? {
+                    count += 1;
+                }
+            }
+            spaces.push(Json::object(vec![
+                ("id", space.into()),
+                ("desktop", self.system.desktop_id(space).ok().into()),
+                ("label", self.label(space).into()),
+                ("windows", Json::from(count as u32)),
+            ]));
+        }
+
+        Ok(Json::object(vec![
+            ("id", display.into()),
+            (
+                "uuid",
+                self.system.display_identifier(display)?.to_string().into(),
+            ),
+            ("focused", (focused == Some(display)).into()),
+            ("space", active.into()),
+            ("desktop", self.system.desktop_id(active).ok().into()),
+            ("label", self.label(active).into()),
+            ("mode", self.mode(active).into()),
+            ("spaces", Json::Array(spaces)),
+        ]))
+    }
+
+    fn focused(&self, window: &Window, display: Option<DisplayID>) -> Result<Json, ChunkWMError> {
+        let space = match display {
+            Some(display) => Some(self.system.active_space(display)?),
+            None => None,
+        };
+        Ok(Json::object(vec![
+            ("application", window.owner()?.name()?.into()),
+            ("title", window.name()?.into()),
+            ("display", display.into()),
+            ("space", space.into()),
+        ]))
+    }
+
+    fn label(&self, space: CGSSpaceID) -> Option<&str> {
+        self.labels.get(&space).map(String::as_str)
+    }
+
+    fn mode(&self, space: CGSSpaceID) -> Option<&str> {
+        self.space_modes
+            .get(&space)
+            .or(self.mode.as_ref())
+            .map(String::as_str)
+    }
+
+    /// The standard windows that are not minimized.
+    fn windows(&self) -> Result<Vec<Window>, ChunkWMError> {
+        let mut windows = Vec::new();
+        for application in self.system.applications()? {
+            for window in self.system.windows(&application)? {
+                if self.system.is_standard(&window)? && !self.system.is_minimized(&window)? {
+                    windows.push(window);
+                }
+            }
+        }
+        Ok(windows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::SpaceType;
+    use simulator::{Simulator, WindowSpec};
+    use std::env;
+    use std::fs;
+    use std::process;
+    use std::sync::{Arc, Mutex};
+    use testing::{pump, rect};
+    use timer::ManualClock;
+
+    #[test]
+    fn document() {
+        let simulator = Simulator::new();
+        let second = simulator.add_space(1, SpaceType::User).unwrap();
+        simulator.launch(1, "Terminal");
+        simulator
+            .create_window(1, WindowSpec::new("zsh", rect(0.0, 0.0, 400.0, 300.0)))
+            .unwrap();
+        simulator
+            .create_window(1, WindowSpec::new("vim", rect(400.0, 0.0, 400.0, 300.0)))
+            .unwrap();
+        simulator.set_window_minimized(2, true).unwrap();
+        simulator.focus_window(1).unwrap();
+
+        let path = env::temp_dir().join(format!("chunkwm-status-document-{}.json", process::id()));
+        let mut status = StatusExport::new(simulator.clone(), &path);
+        status.set_labels(vec![(1, "code")]).unwrap();
+        status.set_mode(Some("bsp".to_owned())).unwrap();
+        status
+            .set_space_mode(second, Some("monocle".to_owned()))
+            .unwrap();
+        assert_eq!(
+            status.document().unwrap(),
+            format!(
+                "{{\"displays\":[{{\"id\":1,\"uuid\":\"00000000-0000-0000-0000-000000000001\",\
+                 \"focused\":true,\"space\":1,\"desktop\":1,\"label\":\"code\",\"mode\":\"bsp\",\
+                 \"spaces\":[{{\"id\":1,\"desktop\":1,\"label\":\"code\",\"windows\":1}},\
+                 {{\"id\":{},\"desktop\":2,\"label\":null,\"windows\":0}}]}}],\
+                 \"focused\":{{\"application\":\"Terminal\",\"title\":\"zsh\",\"display\":1,\
+                 \"space\":1}}}}\n",
+                second
+            )
+        );
+        status.write().unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            status.document().unwrap()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn throttled_writes() {
+        let simulator = Simulator::new();
+        simulator.launch(1, "Terminal");
+        simulator.take_events();
+        let clock = ManualClock::new();
+        let scheduled = Arc::new(Mutex::new(Vec::new()));
+        let path = env::temp_dir().join(format!("chunkwm-status-throttled-{}.json", process::id()));
+        let _ = fs::remove_file(&path);
+        let mut status = {
+            let scheduled = scheduled.clone();
+            StatusExport::with_clock(simulator.clone(), &path, clock.clone()).with_scheduler(
+                move |delay| {
+                    let mut scheduled = scheduled.lock().unwrap();
+                    scheduled.push(delay);
+                    Ok(TimerId(scheduled.len() as u64))
+                },
+            )
+        };
+        let windows = || {
+            let document = fs::read_to_string(&path).unwrap();
+            document.matches("\"windows\":1").count()
+        };
+
+        status.write().unwrap();
+        assert_eq!(windows(), 0);
+
+        // A change after the interval is written right away.
+        clock.advance(Duration::from_millis(100));
+        simulator
+            .create_window(1, WindowSpec::new("a", rect(0.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        pump(&simulator, &mut status);
+        assert_eq!(windows(), 1);
+
+        // Changes within the interval wait for one timer.
+        clock.advance(Duration::from_millis(30));
+        simulator.destroy_window(1).unwrap();
+        pump(&simulator, &mut status);
+        status.handle(&Event::SpaceChanged).unwrap();
+        assert_eq!(windows(), 1);
+        assert_eq!(*scheduled.lock().unwrap(), vec![Duration::from_millis(70)]);
+
+        clock.advance(Duration::from_millis(70));
+        status.handle(&Event::Timer(TimerId(99))).unwrap();
+        assert_eq!(windows(), 1);
+        status.handle(&Event::Timer(TimerId(1))).unwrap();
+        assert_eq!(windows(), 0);
+        assert!(!status.flush().unwrap());
+
+        // After the interval, changes are written right away again.
+        clock.advance(Duration::from_millis(100));
+        simulator
+            .create_window(1, WindowSpec::new("b", rect(0.0, 0.0, 100.0, 100.0)))
+            .unwrap();
+        pump(&simulator, &mut status);
+        assert_eq!(windows(), 1);
+        assert_eq!(scheduled.lock().unwrap().len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "accessibility")]
+    #[test]
+    fn axlib_export_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<StatusExport<::system::Axlib>>();
+    }
+}
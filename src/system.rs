@@ -0,0 +1,444 @@
+//! The `system` module contains the `WindowSystem` trait, which covers the operations on
+//! applications, windows, spaces, displays and the cursor that chunkwm's accessibility library
+//! (axlib) provides.
+//!
+//! Code that is written against `WindowSystem` instead of calling the `accessibility` methods on
+//! `Window` and `Application` directly works with any backend. `Axlib` is the backend that talks to
+//! the window server; it needs the `accessibility` feature.
+//!
+//! ## Example
+//! ```rust
+//! # extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::geometry::CGPoint;
+//! use chunkwm::system::WindowSystem;
+//!
+//! /// Move the focused window of the focused application to the top left of its display.
+//! fn move_to_corner<S: WindowSystem>(system: &mut S) -> Result<(), ChunkWMError> {
+//!     let application = system.focused_application()?;
+//!     let window = system.focused_window(&application)?;
+//!     let display = system.display_bounds(system.window_display(&window)?)?;
+//!     system.set_position(&window, CGPoint::new(display.origin.x, display.origin.y))
+//! }
+//! # fn main() {}
+//! ```
+
+use application::Application;
+use display::{DisplayUuid, DockOrientation, SpaceType};
+use event::DisplayID;
+use geometry::{self, CGPoint, CGRect, CGSize};
+use raw::CGSSpaceID;
+use window::{Window, WindowFlag};
+use ChunkWMError;
+
+#[cfg(feature = "accessibility")]
+use common::accessibility::display;
+#[cfg(feature = "accessibility")]
+use core_foundation::base::TCFType;
+#[cfg(feature = "accessibility")]
+use core_foundation::string::CFString;
+#[cfg(feature = "accessibility")]
+use core_graphics::display::CGDisplay;
+#[cfg(feature = "accessibility")]
+use cursor;
+
+/// The state of the dock.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Dock {
+    /// The side of the screen the dock is on.
+    pub orientation: DockOrientation,
+    /// The size of the dock's icons.
+    pub tile_size: usize,
+    /// Whether the dock is hidden automatically.
+    pub auto_hide: bool,
+}
+
+/// The operations on the window system.
+///
+/// Windows and applications are passed as the handles chunkwm gives plugins; displays and spaces
+/// are passed by id.
+pub trait WindowSystem {
+    /// Get the focused application.
+    fn focused_application(&self) -> Result<Application, ChunkWMError>;
+
+    /// Get the focused window of an application.
+    fn focused_window(&self, application: &Application) -> Result<Window, ChunkWMError>;
+
+    /// Get the running applications.
+    fn applications(&self) -> Result<Vec<Application>, ChunkWMError>;
+
+    /// Get the windows of an application.
+    fn windows(&self, application: &Application) -> Result<Vec<Window>, ChunkWMError>;
+
+    /// Get a window by id. Plugins keep window ids between events rather than `Window` handles,
+    /// which are only valid until the window is destroyed.
+    ///
+    /// The default implementation searches the windows of every application.
+    fn window(&self, id: u32) -> Result<Window, ChunkWMError> {
+        for application in self.applications()? {
+            for window in self.windows(&application)? {
+                if window.id()? == id {
+                    return Ok(window);
+                }
+            }
+        }
+        Err(ChunkWMError::Internal("no such window"))
+    }
+
+    /// Check whether a window is minimized.
+    fn is_minimized(&self, window: &Window) -> Result<bool, ChunkWMError>;
+
+    /// Check whether a window is resizable.
+    fn is_resizable(&self, window: &Window) -> Result<bool, ChunkWMError>;
+
+    /// Check whether a window is movable.
+    fn is_movable(&self, window: &Window) -> Result<bool, ChunkWMError>;
+
+    /// Check whether a window is fullscreen.
+    fn is_fullscreen(&self, window: &Window) -> Result<bool, ChunkWMError>;
+
+    /// Check whether a window is a standard window.
+    fn is_standard(&self, window: &Window) -> Result<bool, ChunkWMError>;
+
+    /// Get the accessibility role of a window, e.g. `AXWindow`.
+    fn role(&self, window: &Window) -> Result<String, ChunkWMError>;
+
+    /// Get the accessibility subrole of a window, e.g. `AXStandardWindow` or `AXDialog`.
+    fn subrole(&self, window: &Window) -> Result<String, ChunkWMError>;
+
+    /// Move a window.
+    fn set_position(&mut self, window: &Window, position: CGPoint) -> Result<(), ChunkWMError>;
+
+    /// Resize a window.
+    fn set_size(&mut self, window: &Window, size: CGSize) -> Result<(), ChunkWMError>;
+
+    /// Minimize or deminimize a window.
+    fn set_minimized(&mut self, window: &Window, minimized: bool) -> Result<(), ChunkWMError>;
+
+    /// Focus a window, and activate the application it belongs to.
+    fn focus(&mut self, window: &Window) -> Result<(), ChunkWMError>;
+
+    /// Close a window.
+    fn close(&mut self, window: &Window) -> Result<(), ChunkWMError>;
+
+    /// Add a flag to a window.
+    fn add_flag(&mut self, window: &Window, flag: WindowFlag) -> Result<(), ChunkWMError>;
+
+    /// Remove a flag from a window.
+    fn remove_flag(&mut self, window: &Window, flag: WindowFlag) -> Result<(), ChunkWMError>;
+
+    /// Check whether a window has a flag.
+    fn has_flag(&self, window: &Window, flag: WindowFlag) -> Result<bool, ChunkWMError> {
+        Ok(window.raw_flags()? & flag.bit() != 0)
+    }
+
+    /// Get the active space of a display.
+    fn active_space(&self, display: DisplayID) -> Result<CGSSpaceID, ChunkWMError>;
+
+    /// Check whether a space contains a window.
+    fn space_has_window(&self, space: CGSSpaceID, window: &Window) -> Result<bool, ChunkWMError>;
+
+    /// Move a window to a space.
+    fn move_to_space(&mut self, window: &Window, space: CGSSpaceID) -> Result<(), ChunkWMError>;
+
+    /// Get the spaces of a display, in the order Mission Control shows them.
+    fn spaces(&self, display: DisplayID) -> Result<Vec<CGSSpaceID>, ChunkWMError>;
+
+    /// Get the type of a space.
+    fn space_type(&self, space: CGSSpaceID) -> Result<SpaceType, ChunkWMError>;
+
+    /// Get the desktop number of a space. Desktops are numbered from 1, through the spaces of the
+    /// displays in arrangement order.
+    ///
+    /// The default implementation counts the spaces of `displays`, which must then be in
+    /// arrangement order.
+    fn desktop_id(&self, space: CGSSpaceID) -> Result<u32, ChunkWMError> {
+        let mut desktop = 0;
+        for display in self.displays()? {
+            for other in self.spaces(display)? {
+                desktop += 1;
+                if other == space {
+                    return Ok(desktop);
+                }
+            }
+        }
+        Err(ChunkWMError::Internal("the space is not a desktop"))
+    }
+
+    /// Get the space of a desktop number.
+    ///
+    /// The default implementation counts the spaces of `displays`, which must then be in
+    /// arrangement order.
+    fn desktop_space(&self, desktop: u32) -> Result<CGSSpaceID, ChunkWMError> {
+        let mut spaces = Vec::new();
+        for display in self.displays()? {
+            spaces.extend(self.spaces(display)?);
+        }
+        (desktop as usize)
+            .checked_sub(1)
+            .and_then(|index| spaces.get(index).cloned())
+            .ok_or(ChunkWMError::Internal("there is no such desktop"))
+    }
+
+    /// Get the ids of the displays.
+    fn displays(&self) -> Result<Vec<DisplayID>, ChunkWMError>;
+
+    /// Get the bounds of a display.
+    fn display_bounds(&self, display: DisplayID) -> Result<CGRect, ChunkWMError>;
+
+    /// Get the UUID of a display. Unlike its id, the UUID of a display stays the same when it is
+    /// reconnected.
+    fn display_identifier(&self, display: DisplayID) -> Result<DisplayUuid, ChunkWMError>;
+
+    /// Get the display a window is on: the display that contains the window's center.
+    fn window_display(&self, window: &Window) -> Result<DisplayID, ChunkWMError> {
+        let center = geometry::center(&window.frame()?);
+        for display in self.displays()? {
+            if geometry::contains(&self.display_bounds(display)?, &center) {
+                return Ok(display);
+            }
+        }
+        Err(ChunkWMError::Internal("the window is not on a display"))
+    }
+
+    /// Get the display under the cursor.
+    fn cursor_display(&self) -> Result<DisplayID, ChunkWMError> {
+        let cursor = self.cursor_position()?;
+        for display in self.displays()? {
+            if geometry::contains(&self.display_bounds(display)?, &cursor) {
+                return Ok(display);
+            }
+        }
+        Err(ChunkWMError::Internal("the cursor is not on a display"))
+    }
+
+    /// Get the state of the dock.
+    fn dock(&self) -> Result<Dock, ChunkWMError>;
+
+    /// Get the position of the cursor.
+    fn cursor_position(&self) -> Result<CGPoint, ChunkWMError>;
+
+    /// Move the cursor, without generating mouse events.
+    fn set_cursor_position(&mut self, position: CGPoint) -> Result<(), ChunkWMError>;
+}
+
+/// The display under the cursor, or the first display when the cursor is not on one.
+pub(crate) fn cursor_or_first_display<S: WindowSystem>(
+    system: &S,
+) -> Result<DisplayID, ChunkWMError> {
+    match system.cursor_display() {
+        Ok(display) => Ok(display),
+        Err(_) => system
+            .displays()?
+            .first()
+            .cloned()
+            .ok_or(ChunkWMError::Internal("there are no displays")),
+    }
+}
+
+/// The `WindowSystem` that talks to the window server, through chunkwm's accessibility library.
+/// Needed features: `accessibility`.
+#[cfg(feature = "accessibility")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Axlib;
+
+#[cfg(feature = "accessibility")]
+impl Axlib {
+    pub fn new() -> Axlib {
+        Axlib
+    }
+}
+
+/// Get the identifier of a display, which the display functions of axlib take.
+#[cfg(feature = "accessibility")]
+fn display_identifier(display: DisplayID) -> Result<CFString, ChunkWMError> {
+    Ok(DisplayUuid::of_display(display)?.to_cf_string())
+}
+
+#[cfg(feature = "accessibility")]
+impl WindowSystem for Axlib {
+    fn focused_application(&self) -> Result<Application, ChunkWMError> {
+        Application::focused()
+    }
+
+    fn focused_window(&self, application: &Application) -> Result<Window, ChunkWMError> {
+        use common::accessibility::element;
+
+        let element = unsafe { element::get_focused_window(application.element()?) };
+        if element.is_null() {
+            return Err(ChunkWMError::NullPointer);
+        }
+        let id = unsafe { element::get_window_id(element) };
+        Window::list_for_application(application)?
+            .into_iter()
+            .find(|window| window.id().ok() == Some(id))
+            .ok_or(ChunkWMError::Internal("the focused window is not known"))
+    }
+
+    fn applications(&self) -> Result<Vec<Application>, ChunkWMError> {
+        Application::processes()
+    }
+
+    fn windows(&self, application: &Application) -> Result<Vec<Window>, ChunkWMError> {
+        Window::list_for_application(application)
+    }
+
+    fn is_minimized(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        window.is_minimized()
+    }
+
+    fn is_resizable(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        window.is_resizable()
+    }
+
+    fn is_movable(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        window.is_movable()
+    }
+
+    fn is_fullscreen(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        window.is_fullscreen()
+    }
+
+    fn is_standard(&self, window: &Window) -> Result<bool, ChunkWMError> {
+        window.is_standard()
+    }
+
+    fn role(&self, window: &Window) -> Result<String, ChunkWMError> {
+        window.main_role()
+    }
+
+    fn subrole(&self, window: &Window) -> Result<String, ChunkWMError> {
+        window.sub_role()
+    }
+
+    fn set_position(&mut self, window: &Window, position: CGPoint) -> Result<(), ChunkWMError> {
+        window.set_position(position.x as f32, position.y as f32)
+    }
+
+    fn set_size(&mut self, window: &Window, size: CGSize) -> Result<(), ChunkWMError> {
+        window.set_size(size.width as f32, size.height as f32)
+    }
+
+    fn set_minimized(&mut self, window: &Window, minimized: bool) -> Result<(), ChunkWMError> {
+        window.set_minimized(minimized)
+    }
+
+    fn focus(&mut self, window: &Window) -> Result<(), ChunkWMError> {
+        window.focus()
+    }
+
+    fn close(&mut self, window: &Window) -> Result<(), ChunkWMError> {
+        window.close()
+    }
+
+    fn add_flag(&mut self, window: &Window, flag: WindowFlag) -> Result<(), ChunkWMError> {
+        window.add_flag(flag)
+    }
+
+    fn remove_flag(&mut self, window: &Window, flag: WindowFlag) -> Result<(), ChunkWMError> {
+        window.remove_flag(flag)
+    }
+
+    fn active_space(&self, display: DisplayID) -> Result<CGSSpaceID, ChunkWMError> {
+        let identifier = display_identifier(display)?;
+        Ok(unsafe { display::active_spaceid(identifier.as_concrete_TypeRef()) })
+    }
+
+    fn space_has_window(&self, space: CGSSpaceID, window: &Window) -> Result<bool, ChunkWMError> {
+        Ok(unsafe { display::space_has_window(space, window.id()?) })
+    }
+
+    fn move_to_space(&mut self, window: &Window, space: CGSSpaceID) -> Result<(), ChunkWMError> {
+        unsafe { display::space_move_window(space, window.id()?) };
+        Ok(())
+    }
+
+    fn spaces(&self, display: DisplayID) -> Result<Vec<CGSSpaceID>, ChunkWMError> {
+        let uuid = DisplayUuid::of_display(display)?;
+        let mut spaces = Vec::new();
+        for desktop in 1.. {
+            let (mut arrangement, mut space) = (0, 0);
+            if !unsafe {
+                display::cgsspaceid_from_desktop_id(desktop, &mut arrangement, &mut space)
+            } {
+                break;
+            }
+            if DisplayUuid::of_arrangement(arrangement)? == uuid {
+                spaces.push(space);
+            }
+        }
+        Ok(spaces)
+    }
+
+    fn space_type(&self, space: CGSSpaceID) -> Result<SpaceType, ChunkWMError> {
+        let identifier = DisplayUuid::of_space(space)?.to_cf_string();
+        unsafe {
+            let spaces = display::spaces_for_display(identifier.as_concrete_TypeRef());
+            if spaces.is_null() {
+                return Err(ChunkWMError::NullPointer);
+            }
+            let mut space_type = None;
+            let mut i = 0;
+            while !(*spaces.add(i)).is_null() {
+                let raw = *spaces.add(i);
+                if (*raw).id == space {
+                    space_type = Some((*raw).space_type);
+                }
+                i += 1;
+            }
+            display::destroy_space_list(spaces);
+            space_type.ok_or(ChunkWMError::Internal("the space is not on its display"))
+        }
+    }
+
+    fn desktop_id(&self, space: CGSSpaceID) -> Result<u32, ChunkWMError> {
+        let (mut arrangement, mut desktop) = (0, 0);
+        if unsafe { display::cgsspaceid_to_desktop_id(space, &mut arrangement, &mut desktop) } {
+            Ok(desktop)
+        } else {
+            Err(ChunkWMError::Internal("the space is not a desktop"))
+        }
+    }
+
+    fn desktop_space(&self, desktop: u32) -> Result<CGSSpaceID, ChunkWMError> {
+        let (mut arrangement, mut space) = (0, 0);
+        if unsafe { display::cgsspaceid_from_desktop_id(desktop, &mut arrangement, &mut space) } {
+            Ok(space)
+        } else {
+            Err(ChunkWMError::Internal("there is no such desktop"))
+        }
+    }
+
+    fn displays(&self) -> Result<Vec<DisplayID>, ChunkWMError> {
+        CGDisplay::active_displays()
+            .map_err(|_| ChunkWMError::Internal("could not get the active displays"))
+    }
+
+    fn display_bounds(&self, display: DisplayID) -> Result<CGRect, ChunkWMError> {
+        let identifier = display_identifier(display)?;
+        Ok(unsafe { display::get_display_bounds(identifier.as_concrete_TypeRef()) })
+    }
+
+    fn display_identifier(&self, display: DisplayID) -> Result<DisplayUuid, ChunkWMError> {
+        DisplayUuid::of_display(display)
+    }
+
+    fn dock(&self) -> Result<Dock, ChunkWMError> {
+        unsafe {
+            Ok(Dock {
+                orientation: display::get_dock_orientation(),
+                tile_size: display::get_dock_tile_size(),
+                auto_hide: display::is_dock_auto_hide_enabled(),
+            })
+        }
+    }
+
+    fn cursor_position(&self) -> Result<CGPoint, ChunkWMError> {
+        Ok(cursor::cursor_position())
+    }
+
+    fn set_cursor_position(&mut self, position: CGPoint) -> Result<(), ChunkWMError> {
+        cursor::set_cursor_position(position)
+    }
+}
@@ -0,0 +1,154 @@
+//! Helpers for creating synthetic applications, windows and events in tests, and for feeding a
+//! `Simulator`'s events to the components that handle them.
+//!
+//! The raw structs are leaked, so that the `Application`s and `Window`s pointing to them stay
+//! valid for the rest of the test.
+
+use application::Application;
+use event::Event;
+use focus::FocusHistory;
+use geometry::{CGPoint, CGRect, CGSize};
+use labels::SpaceLabels;
+use layout::LayoutManager;
+use raw::*;
+use scratchpad::Scratchpad;
+use simulator::Simulator;
+use status::StatusExport;
+use std::ffi::CString;
+use std::ptr;
+use system::WindowSystem;
+use timer::Clock;
+use window::Window;
+use ChunkWMError;
+
+fn leak_str(s: &str) -> *const ::std::os::raw::c_char {
+    CString::new(s).unwrap().into_raw()
+}
+
+/// Create an application.
+pub fn application(pid: PID, name: &str) -> Application {
+    let raw = Box::new(RawApplication {
+        element: ptr::null(),
+        observer: RawObserver {
+            observer: ptr::null(),
+            enabled: false,
+            valid: false,
+        },
+        name: leak_str(name),
+        pid,
+        process_serial_number: ProcessSerialNumber {
+            high_long: 0,
+            low_long: pid as _,
+        },
+    });
+    Application::from(Box::into_raw(raw))
+}
+
+/// Create a window owned by `owner`, with the given frame.
+pub fn window(owner: &Application, id: u32, name: &str, frame: (f64, f64, f64, f64)) -> Window {
+    let raw = Box::new(RawWindow {
+        element: ptr::null(),
+        main_role: ptr::null(),
+        sub_role: ptr::null(),
+        owner: unsafe { owner.application_ref().unwrap() },
+        id,
+        name: leak_str(name),
+        flags: 0,
+        level: 0,
+        position: CGPoint::new(frame.0, frame.1),
+        size: CGSize::new(frame.2, frame.3),
+    });
+    Window::from(Box::into_raw(raw))
+}
+
+/// Move and resize a window, as chunkwm does before broadcasting `WindowMoved` or
+/// `WindowResized`.
+pub fn set_frame(window: &Window, frame: (f64, f64, f64, f64)) {
+    unsafe {
+        let raw = window.window_ref().unwrap();
+        (*raw).position = CGPoint::new(frame.0, frame.1);
+        (*raw).size = CGSize::new(frame.2, frame.3);
+    }
+}
+
+/// Rename a window, as chunkwm does before broadcasting `WindowTitleChanged`.
+pub fn set_name(window: &Window, name: &str) {
+    unsafe {
+        (*window.window_ref().unwrap()).name = leak_str(name);
+    }
+}
+
+/// Get another handle to the same window. `Window` is not `Clone` without the `accessibility`
+/// feature, and events take their window by value.
+pub fn same_window(window: &Window) -> Window {
+    Window::from(unsafe { window.window_ref().unwrap() })
+}
+
+/// Get another handle to the same application.
+pub fn same_application(application: &Application) -> Application {
+    Application::from(unsafe { application.application_ref().unwrap() })
+}
+
+/// Create a rectangle.
+pub fn rect(x: f64, y: f64, w: f64, h: f64) -> CGRect {
+    CGRect::new(&CGPoint::new(x, y), &CGSize::new(w, h))
+}
+
+/// Get the frame of a simulated window as `(x, y, width, height)`.
+pub fn frame(simulator: &Simulator, id: u32) -> (f64, f64, f64, f64) {
+    let frame = simulator.window(id).unwrap().frame().unwrap();
+    (
+        frame.origin.x,
+        frame.origin.y,
+        frame.size.width,
+        frame.size.height,
+    )
+}
+
+/// A component that handles events.
+pub trait Handler {
+    fn handle_event(&mut self, event: &Event) -> Result<(), ChunkWMError>;
+}
+
+impl<S: WindowSystem, C: Clock> Handler for FocusHistory<S, C> {
+    fn handle_event(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        self.handle(event).map(|_| ())
+    }
+}
+
+impl<S: WindowSystem> Handler for LayoutManager<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        self.handle(event)
+    }
+}
+
+impl<S: WindowSystem> Handler for Scratchpad<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        self.handle(event).map(|_| ())
+    }
+}
+
+impl<S: WindowSystem> Handler for SpaceLabels<S> {
+    fn handle_event(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        self.handle(event).map(|_| ())
+    }
+}
+
+impl<S: WindowSystem, C: Clock> Handler for StatusExport<S, C> {
+    fn handle_event(&mut self, event: &Event) -> Result<(), ChunkWMError> {
+        self.handle(event)
+    }
+}
+
+/// Feed the simulator's events to a component, including the events it causes.
+pub fn pump<H: Handler>(simulator: &Simulator, handler: &mut H) {
+    loop {
+        let events = simulator.take_events();
+        if events.is_empty() {
+            return;
+        }
+        for event in events {
+            handler.handle_event(&event).unwrap();
+        }
+    }
+}
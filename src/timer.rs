@@ -0,0 +1,508 @@
+//! The `timer` module lets plugins schedule delayed and repeating actions, e.g. "re-check this
+//! window in 200ms". When a timer expires, the plugin's `handle` is called with
+//! `Event::Timer(id)`.
+//!
+//! `chunkwm_plugin!` starts the timer service before the plugin is created (so timers can be
+//! scheduled from `new`) and stops it (which cancels all timers) when the plugin is deinitialised,
+//! so plugins only need `after`, `every` and `cancel`.
+//!
+//! Timer events are delivered from the timer service's own thread. They wait for the plugin's
+//! other calls to finish, so `handle` is never called concurrently, but it is called from
+//! different threads; this is why the plugin type has to be `Send`.
+//!
+//! The scheduling itself is done by `Timers`, which takes its time from a `Clock`. With a
+//! `ManualClock`, timer-dependent logic can be tested without waiting.
+//!
+//! ## Example
+//! ```rust
+//! # #[macro_use] extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::timer::{self, TimerId};
+//! use std::time::Duration;
+//!
+//! pub struct Plugin {
+//!     recheck: Option<TimerId>,
+//! }
+//!
+//! impl HandleEvent for Plugin {
+//!     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+//!         match event {
+//!             Event::WindowCreated(_) => {
+//!                 self.recheck = Some(timer::after(Duration::from_millis(200))?);
+//!             }
+//!             Event::Timer(id) if Some(id) == self.recheck => {
+//!                 // Re-check the window.
+//!             }
+//!             _ => {}
+//!         }
+//!         Ok(())
+//!     }
+//!     // some methods omitted
+//!     # fn new(_: API) -> Plugin { Plugin { recheck: None } }
+//!     # subscribe_all!();
+//!     # fn shutdown(&self) {}
+//! }
+//! # fn main() {}
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use ChunkWMError;
+
+/// The identifier of a timer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TimerId(pub u64);
+
+impl fmt::Display for TimerId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// A source of time for `Timers`.
+pub trait Clock: Send + 'static {
+    /// The time since some fixed point, e.g. the creation of the clock.
+    fn now(&self) -> Duration;
+}
+
+/// The monotonic system clock.
+#[derive(Debug, Clone, Copy)]
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+/// A clock that only moves when told to, for tests. Clones share the same time.
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    now: Arc<Mutex<Duration>>,
+}
+
+impl ManualClock {
+    /// Create a clock at time zero.
+    pub fn new() -> ManualClock {
+        ManualClock::default()
+    }
+
+    /// Move the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+
+    /// Set the time.
+    pub fn set(&self, now: Duration) {
+        *self.lock() = now;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Duration> {
+        self.now.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Duration {
+        *self.lock()
+    }
+}
+
+struct Timer {
+    deadline: Duration,
+    interval: Option<Duration>,
+}
+
+/// A set of one-shot and repeating timers. `Timers` doesn't deliver anything by itself; `expired`
+/// returns the timers that have expired since the last call.
+pub struct Timers<C: Clock> {
+    clock: C,
+    next_id: u64,
+    timers: BTreeMap<TimerId, Timer>,
+}
+
+impl<C: Clock> Timers<C> {
+    pub fn new(clock: C) -> Timers<C> {
+        Timers {
+            clock,
+            next_id: 1,
+            timers: BTreeMap::new(),
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Schedule a timer that expires once, after `delay`.
+    pub fn after(&mut self, delay: Duration) -> TimerId {
+        self.schedule(delay, None)
+    }
+
+    /// Schedule a timer that expires every `interval` (at least a millisecond), until it is
+    /// cancelled.
+    pub fn every(&mut self, interval: Duration) -> TimerId {
+        let interval = interval.max(Duration::from_millis(1));
+        self.schedule(interval, Some(interval))
+    }
+
+    fn schedule(&mut self, delay: Duration, interval: Option<Duration>) -> TimerId {
+        let id = TimerId(self.next_id);
+        self.next_id += 1;
+        let deadline = self.clock.now() + delay;
+        self.timers.insert(id, Timer { deadline, interval });
+        id
+    }
+
+    /// Cancel a timer. Returns whether it was scheduled.
+    pub fn cancel(&mut self, id: TimerId) -> bool {
+        self.timers.remove(&id).is_some()
+    }
+
+    /// Cancel all timers.
+    pub fn cancel_all(&mut self) {
+        self.timers.clear();
+    }
+
+    pub fn is_scheduled(&self, id: TimerId) -> bool {
+        self.timers.contains_key(&id)
+    }
+
+    /// The number of scheduled timers.
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    /// The time until the next timer expires; zero if a timer has already expired.
+    pub fn next_deadline(&self) -> Option<Duration> {
+        let now = self.clock.now();
+        self.timers
+            .values()
+            .map(|timer| timer.deadline)
+            .min()
+            .map(|deadline| deadline.checked_sub(now).unwrap_or_default())
+    }
+
+    /// Take the timers that have expired, ordered by deadline. One-shot timers are removed;
+    /// repeating timers are rescheduled, and are returned once even if several intervals have
+    /// passed.
+    pub fn expired(&mut self) -> Vec<TimerId> {
+        let now = self.clock.now();
+        let mut expired: Vec<_> = self
+            .timers
+            .iter()
+            .filter(|&(_, timer)| timer.deadline <= now)
+            .map(|(&id, timer)| (timer.deadline, id))
+            .collect();
+        expired.sort();
+
+        for &(_, id) in &expired {
+            let repeating = match self.timers.get_mut(&id) {
+                Some(Timer {
+                    ref mut deadline,
+                    interval: Some(interval),
+                }) => {
+                    while *deadline <= now {
+                        *deadline += *interval;
+                    }
+                    true
+                }
+                _ => false,
+            };
+            if !repeating {
+                self.timers.remove(&id);
+            }
+        }
+        expired.into_iter().map(|(_, id)| id).collect()
+    }
+}
+
+struct State<C: Clock> {
+    timers: Timers<C>,
+    stopped: bool,
+}
+
+struct Shared<C: Clock> {
+    state: Mutex<State<C>>,
+    wake: Condvar,
+}
+
+/// A thread-safe handle to a set of timers, which can deliver the expired timers from a
+/// background thread. Clones refer to the same timers.
+pub struct TimerService<C: Clock = SystemClock> {
+    shared: Arc<Shared<C>>,
+}
+
+impl<C: Clock> Clone for TimerService<C> {
+    fn clone(&self) -> TimerService<C> {
+        TimerService {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<C: Clock> TimerService<C> {
+    pub fn new(clock: C) -> TimerService<C> {
+        TimerService {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    timers: Timers::new(clock),
+                    stopped: false,
+                }),
+                wake: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Schedule a timer that expires once, after `delay`.
+    pub fn after(&self, delay: Duration) -> TimerId {
+        self.update(|timers| timers.after(delay))
+    }
+
+    /// Schedule a timer that expires every `interval`, until it is cancelled.
+    pub fn every(&self, interval: Duration) -> TimerId {
+        self.update(|timers| timers.every(interval))
+    }
+
+    /// Cancel a timer. Returns whether it was scheduled.
+    pub fn cancel(&self, id: TimerId) -> bool {
+        self.update(|timers| timers.cancel(id))
+    }
+
+    pub fn is_scheduled(&self, id: TimerId) -> bool {
+        self.lock().timers.is_scheduled(id)
+    }
+
+    /// Deliver the expired timers on this thread. Returns the number of delivered timers.
+    pub fn poll<F: FnMut(TimerId)>(&self, mut deliver: F) -> usize {
+        // The lock is released before delivering, so `deliver` can schedule new timers.
+        let expired = self.lock().timers.expired();
+        for &id in &expired {
+            deliver(id);
+        }
+        expired.len()
+    }
+
+    /// Deliver the expired timers from a background thread, until `stop` is called.
+    pub fn spawn<F: FnMut(TimerId) + Send + 'static>(&self, mut deliver: F) -> JoinHandle<()> {
+        let shared = self.shared.clone();
+        thread::spawn(move || loop {
+            let expired = {
+                let mut state = shared.state.lock().unwrap_or_else(PoisonError::into_inner);
+                loop {
+                    if state.stopped {
+                        return;
+                    }
+                    match state.timers.next_deadline() {
+                        Some(wait) if wait == Duration::from_secs(0) => break,
+                        Some(wait) => {
+                            state = shared
+                                .wake
+                                .wait_timeout(state, wait)
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .0;
+                        }
+                        None => {
+                            state = shared
+                                .wake
+                                .wait(state)
+                                .unwrap_or_else(PoisonError::into_inner);
+                        }
+                    }
+                }
+                state.timers.expired()
+            };
+            for id in expired {
+                deliver(id);
+            }
+        })
+    }
+
+    /// Cancel all timers, and stop the background thread.
+    pub fn stop(&self) {
+        self.update(|timers| timers.cancel_all());
+        self.lock().stopped = true;
+        self.shared.wake.notify_all();
+    }
+
+    fn update<R, F: FnOnce(&mut Timers<C>) -> R>(&self, f: F) -> R {
+        let result = f(&mut self.lock().timers);
+        self.shared.wake.notify_all();
+        result
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State<C>> {
+        self.shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The plugin's timer service, see `start`.
+static SERVICE: Mutex<Option<(TimerService, JoinHandle<()>)>> = Mutex::new(None);
+
+fn service() -> Result<TimerService, ChunkWMError> {
+    match *SERVICE.lock().unwrap_or_else(PoisonError::into_inner) {
+        Some((ref service, _)) => Ok(service.clone()),
+        None => Err(ChunkWMError::Internal("the timer service is not running")),
+    }
+}
+
+/// Start the plugin's timer service, which calls `deliver` from a background thread when a timer
+/// expires. `chunkwm_plugin!` does this before the plugin is created. A running service is
+/// stopped first.
+pub fn start<F: FnMut(TimerId) + Send + 'static>(deliver: F) {
+    stop();
+    let service = TimerService::new(SystemClock::new());
+    let thread = service.spawn(deliver);
+    *SERVICE.lock().unwrap_or_else(PoisonError::into_inner) = Some((service, thread));
+}
+
+/// Stop the plugin's timer service, cancelling all timers. `chunkwm_plugin!` does this when the
+/// plugin is deinitialised.
+pub fn stop() {
+    let running = SERVICE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .take();
+    if let Some((service, thread)) = running {
+        service.stop();
+        // A timer callback can't wait for its own thread.
+        if thread.thread().id() != thread::current().id() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// Schedule a timer that expires once, after `delay`, with the plugin's timer service.
+pub fn after(delay: Duration) -> Result<TimerId, ChunkWMError> {
+    Ok(service()?.after(delay))
+}
+
+/// Schedule a timer that expires every `interval` until it is cancelled, with the plugin's timer
+/// service.
+pub fn every(interval: Duration) -> Result<TimerId, ChunkWMError> {
+    Ok(service()?.every(interval))
+}
+
+/// Cancel a timer of the plugin's timer service. Returns whether it was scheduled.
+pub fn cancel(id: TimerId) -> bool {
+    service().map(|service| service.cancel(id)).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn one_shot_timers() {
+        let clock = ManualClock::new();
+        let mut timers = Timers::new(clock.clone());
+        let late = timers.after(ms(200));
+        let early = timers.after(ms(100));
+        assert_eq!(timers.next_deadline(), Some(ms(100)));
+
+        clock.advance(ms(99));
+        assert!(timers.expired().is_empty());
+        clock.advance(ms(150));
+        assert_eq!(timers.expired(), vec![early, late]);
+        assert!(timers.is_empty());
+        assert_eq!(timers.next_deadline(), None);
+    }
+
+    #[test]
+    fn repeating_timers() {
+        let clock = ManualClock::new();
+        let mut timers = Timers::new(clock.clone());
+        let id = timers.every(ms(100));
+
+        clock.advance(ms(100));
+        assert_eq!(timers.expired(), vec![id]);
+        assert_eq!(timers.next_deadline(), Some(ms(100)));
+
+        // Missed intervals are delivered once.
+        clock.advance(ms(350));
+        assert_eq!(timers.expired(), vec![id]);
+        assert_eq!(timers.next_deadline(), Some(ms(50)));
+
+        assert!(timers.cancel(id));
+        clock.advance(ms(100));
+        assert!(timers.expired().is_empty());
+        assert!(!timers.cancel(id));
+    }
+
+    #[test]
+    fn overdue_deadline_is_zero() {
+        let clock = ManualClock::new();
+        let mut timers = Timers::new(clock.clone());
+        timers.after(ms(10));
+        clock.advance(ms(20));
+        assert_eq!(timers.next_deadline(), Some(ms(0)));
+    }
+
+    #[test]
+    fn poll_can_schedule() {
+        let clock = ManualClock::new();
+        let service = TimerService::new(clock.clone());
+        let first = service.after(ms(10));
+
+        clock.advance(ms(10));
+        let mut delivered = Vec::new();
+        let count = service.poll(|id| {
+            delivered.push(id);
+            // Re-check later.
+            service.after(ms(10));
+        });
+        assert_eq!(count, 1);
+        assert_eq!(delivered, vec![first]);
+
+        clock.advance(ms(10));
+        assert_eq!(service.poll(|_| {}), 1);
+    }
+
+    #[test]
+    fn background_delivery() {
+        let service = TimerService::new(SystemClock::new());
+        let (sender, receiver) = mpsc::channel();
+        let thread = service.spawn(move |id| sender.send(id).unwrap());
+
+        let cancelled = service.after(ms(10));
+        let id = service.after(ms(20));
+        assert!(service.cancel(cancelled));
+        assert_eq!(receiver.recv_timeout(Duration::from_secs(5)).unwrap(), id);
+
+        service.stop();
+        thread.join().unwrap();
+        assert!(!service.is_scheduled(id));
+    }
+}
@@ -0,0 +1,728 @@
+//! The `workspace` module contains the `WorkspaceModel`, an in-memory model of the applications,
+//! windows, spaces and displays, which is kept up to date from `Event`s.
+//!
+//! ## Example
+//! ```rust
+//! # #[macro_use] extern crate chunkwm;
+//! # use chunkwm::prelude::*;
+//! use chunkwm::workspace::WorkspaceModel;
+//!
+//! pub struct Plugin {
+//!     model: WorkspaceModel,
+//! }
+//!
+//! impl HandleEvent for Plugin {
+//!     fn new(_: API) -> Plugin {
+//!         Plugin { model: WorkspaceModel::new() }
+//!     }
+//!
+//!     fn handle(&mut self, event: Event) -> Result<(), ChunkWMError> {
+//!         self.model.handle(&event);
+//!         if let Some(window) = self.model.focused_window() {
+//!             println!("focused: {}", window.name);
+//!         }
+//!         Ok(())
+//!     }
+//!     // some methods omitted
+//!     # subscribe_all!();
+//!     # fn shutdown(&self) {}
+//! }
+//! # fn main() {}
+//! ```
+
+use application::Application;
+use event::{DisplayID, Event};
+use geometry;
+use geometry::{CGPoint, CGRect, CGSize};
+use raw::{CGSSpaceID, ProcessSerialNumber, PID};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use window::Window;
+use ChunkWMError;
+
+#[cfg(feature = "accessibility")]
+use system::{Axlib, WindowSystem};
+
+/// A snapshot of an application.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ApplicationInfo {
+    /// The application's pid.
+    pub pid: PID,
+    /// The application's name.
+    pub name: String,
+    /// The application's process serial number.
+    pub process_serial_number: ProcessSerialNumber,
+    /// Whether the application is hidden.
+    pub hidden: bool,
+}
+
+impl ApplicationInfo {
+    /// Create a snapshot of an `Application`.
+    pub fn from_application(application: &Application) -> Result<ApplicationInfo, ChunkWMError> {
+        Ok(ApplicationInfo {
+            pid: application.pid()?,
+            name: application.name()?,
+            process_serial_number: application.process_serial_number()?,
+            hidden: false,
+        })
+    }
+}
+
+/// A snapshot of a window.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WindowInfo {
+    /// The window's id.
+    pub id: u32,
+    /// The pid of the application the window belongs to.
+    pub owner: PID,
+    /// The window's name (title).
+    pub name: String,
+    /// The window's main role, if it has one.
+    pub main_role: Option<String>,
+    /// The window's sub role, if it has one.
+    pub sub_role: Option<String>,
+    /// The raw flags on the window, see `WindowFlag`.
+    pub flags: u32,
+    /// The window's level.
+    pub level: u32,
+    /// The window's position.
+    #[cfg_attr(feature = "serde", serde(with = "geometry::CGPointDef"))]
+    pub position: CGPoint,
+    /// The window's size.
+    #[cfg_attr(feature = "serde", serde(with = "geometry::CGSizeDef"))]
+    pub size: CGSize,
+    /// Whether the window is minimized.
+    pub minimized: bool,
+    /// The display the window is on, if known.
+    pub display: Option<DisplayID>,
+    /// The space the window is on, if known.
+    pub space: Option<CGSSpaceID>,
+}
+
+impl WindowInfo {
+    /// Create a snapshot of a `Window`. The display and space are not known yet.
+    pub fn from_window(window: &Window) -> Result<WindowInfo, ChunkWMError> {
+        Ok(WindowInfo {
+            id: window.id()?,
+            owner: window.owner()?.pid()?,
+            name: window.name()?,
+            main_role: window.main_role().ok(),
+            sub_role: window.sub_role().ok(),
+            flags: window.raw_flags()?,
+            level: window.level()?,
+            position: window.position()?,
+            size: window.size()?,
+            minimized: false,
+            display: None,
+            space: None,
+        })
+    }
+
+    /// Get the frame of the window.
+    pub fn frame(&self) -> CGRect {
+        CGRect::new(&self.position, &self.size)
+    }
+}
+
+/// A snapshot of a display.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DisplayInfo {
+    /// The display's id.
+    pub id: DisplayID,
+    /// The display's frame, if known.
+    #[cfg_attr(feature = "serde", serde(with = "geometry::OptionCGRectDef"))]
+    pub frame: Option<CGRect>,
+    /// The active space on the display, if known.
+    pub active_space: Option<CGSSpaceID>,
+}
+
+/// A snapshot of a space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpaceInfo {
+    /// The space's id.
+    pub id: CGSSpaceID,
+    /// The display the space belongs to.
+    pub display: DisplayID,
+}
+
+/// The `WorkspaceModel` keeps track of applications, windows, spaces and displays.
+///
+/// Feed every event to [`handle`](#method.handle). Events only carry part of the state: the
+/// frames of displays and the active space of each display are queried from the window server
+/// when the `accessibility` feature is enabled, and can otherwise be set with
+/// [`set_display_frame`](#method.set_display_frame) and
+/// [`set_active_space`](#method.set_active_space).
+///
+/// A window is placed on the display that contains its center, and on that display's active
+/// space.
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceModel {
+    applications: BTreeMap<PID, ApplicationInfo>,
+    windows: BTreeMap<u32, WindowInfo>,
+    windows_by_application: HashMap<PID, BTreeSet<u32>>,
+    displays: BTreeMap<DisplayID, DisplayInfo>,
+    spaces: BTreeMap<CGSSpaceID, SpaceInfo>,
+    space_labels: BTreeMap<CGSSpaceID, String>,
+    focused_window: Option<u32>,
+    focused_application: Option<PID>,
+}
+
+impl WorkspaceModel {
+    /// Create an empty model.
+    pub fn new() -> WorkspaceModel {
+        WorkspaceModel::default()
+    }
+
+    /// Update the model from an event. Events that carry a null pointer are ignored.
+    pub fn handle(&mut self, event: &Event) {
+        match *event {
+            Event::ApplicationLaunched(ref application) => {
+                if let Ok(info) = ApplicationInfo::from_application(application) {
+                    self.add_application(info);
+                }
+            }
+            Event::ApplicationTerminated(ref application) => {
+                if let Ok(pid) = application.pid() {
+                    self.remove_application(pid);
+                }
+            }
+            Event::ApplicationActivated(ref application) => {
+                if let Ok(info) = ApplicationInfo::from_application(application) {
+                    let pid = info.pid;
+                    self.applications.entry(pid).or_insert(info);
+                    self.focused_application = Some(pid);
+                }
+            }
+            Event::ApplicationDeactivated(ref application) => {
+                if application.pid().ok() == self.focused_application {
+                    self.focused_application = None;
+                }
+            }
+            Event::ApplicationHidden(ref application) => {
+                self.set_application_hidden(application, true);
+            }
+            Event::ApplicationUnhidden(ref application) => {
+                self.set_application_hidden(application, false);
+            }
+            Event::WindowCreated(ref window) | Event::WindowSheetCreated(ref window) => {
+                if let Ok(info) = WindowInfo::from_window(window) {
+                    self.add_window(info);
+                }
+            }
+            Event::WindowDestroyed(ref window) => {
+                if let Ok(id) = window.id() {
+                    self.remove_window(id);
+                }
+            }
+            Event::WindowFocused(ref window) => {
+                if let Ok(info) = WindowInfo::from_window(window) {
+                    let (id, owner) = (info.id, info.owner);
+                    if !self.windows.contains_key(&id) {
+                        self.add_window(info);
+                    }
+                    self.focused_window = Some(id);
+                    self.focused_application = Some(owner);
+                }
+            }
+            Event::WindowMoved(ref window) | Event::WindowResized(ref window) => {
+                if let (Ok(id), Ok(position), Ok(size)) =
+                    (window.id(), window.position(), window.size())
+                {
+                    self.set_window_frame(id, CGRect::new(&position, &size));
+                }
+            }
+            Event::WindowMinimized(ref window) => {
+                self.set_window_minimized(window, true);
+            }
+            Event::WindowDeminimized(ref window) => {
+                self.set_window_minimized(window, false);
+            }
+            Event::WindowTitleChanged(ref window) => {
+                if let (Ok(id), Ok(name)) = (window.id(), window.name()) {
+                    if let Some(info) = self.windows.get_mut(&id) {
+                        info.name = name;
+                    }
+                }
+            }
+            Event::DisplayAdded(id) => {
+                self.add_display(id);
+                self.refresh_display(id);
+            }
+            Event::DisplayRemoved(id) => {
+                self.remove_display(id);
+            }
+            Event::DisplayMoved(id) | Event::DisplayResized(id) => {
+                self.refresh_display(id);
+            }
+            Event::DisplayChanged | Event::SpaceChanged => {
+                let ids: Vec<DisplayID> = self.displays.keys().cloned().collect();
+                for id in ids {
+                    self.refresh_display(id);
+                }
+            }
+            Event::Timer(_) | Event::DaemonCommand(_) | Event::Other(_) => {}
+        }
+    }
+
+    /// Add (or replace) an application.
+    pub fn add_application(&mut self, info: ApplicationInfo) {
+        self.windows_by_application.entry(info.pid).or_default();
+        self.applications.insert(info.pid, info);
+    }
+
+    /// Remove an application and all of its windows.
+    pub fn remove_application(&mut self, pid: PID) -> Option<ApplicationInfo> {
+        if let Some(ids) = self.windows_by_application.remove(&pid) {
+            for id in ids {
+                self.windows.remove(&id);
+                if self.focused_window == Some(id) {
+                    self.focused_window = None;
+                }
+            }
+        }
+        if self.focused_application == Some(pid) {
+            self.focused_application = None;
+        }
+        self.applications.remove(&pid)
+    }
+
+    /// Add (or replace) a window. The window is placed on a display and space based on its
+    /// frame.
+    pub fn add_window(&mut self, mut info: WindowInfo) {
+        if let Some(old) = self.windows.get(&info.id) {
+            if old.owner != info.owner {
+                self.unindex_window(old.owner, info.id);
+            }
+        }
+        self.place(&mut info);
+        self.windows_by_application
+            .entry(info.owner)
+            .or_default()
+            .insert(info.id);
+        self.windows.insert(info.id, info);
+    }
+
+    /// Remove a window.
+    pub fn remove_window(&mut self, id: u32) -> Option<WindowInfo> {
+        let info = self.windows.remove(&id)?;
+        self.unindex_window(info.owner, id);
+        if self.focused_window == Some(id) {
+            self.focused_window = None;
+        }
+        Some(info)
+    }
+
+    /// Update the frame of a window, which may move it to another display.
+    pub fn set_window_frame(&mut self, id: u32, frame: CGRect) {
+        if let Some(mut info) = self.windows.remove(&id) {
+            let (display, space) = (info.display, info.space);
+            info.position = frame.origin;
+            info.size = frame.size;
+            self.place(&mut info);
+            if display.is_some() && info.display == display {
+                // Moving a window within a display doesn't move it to the active space.
+                info.space = space;
+            }
+            self.windows.insert(id, info);
+        }
+    }
+
+    /// Add a display, if it is not known yet.
+    pub fn add_display(&mut self, id: DisplayID) {
+        self.displays.entry(id).or_insert(DisplayInfo {
+            id,
+            frame: None,
+            active_space: None,
+        });
+    }
+
+    /// Remove a display and its spaces. The windows on the display are kept, but their display
+    /// and space are unknown until they move.
+    pub fn remove_display(&mut self, id: DisplayID) -> Option<DisplayInfo> {
+        let info = self.displays.remove(&id)?;
+        self.spaces.retain(|_, space| space.display != id);
+        for window in self.windows.values_mut() {
+            if window.display == Some(id) {
+                window.display = None;
+                window.space = None;
+            }
+        }
+        Some(info)
+    }
+
+    /// Set the frame of a display. Unknown displays are added.
+    pub fn set_display_frame(&mut self, id: DisplayID, frame: CGRect) {
+        self.add_display(id);
+        if let Some(display) = self.displays.get_mut(&id) {
+            display.frame = Some(frame);
+        }
+    }
+
+    /// Set the active space of a display. Unknown displays and spaces are added.
+    pub fn set_active_space(&mut self, display: DisplayID, space: CGSSpaceID) {
+        self.add_display(display);
+        if let Some(info) = self.displays.get_mut(&display) {
+            info.active_space = Some(space);
+        }
+        self.spaces.insert(space, SpaceInfo { id: space, display });
+    }
+
+    /// Set the labels of spaces, e.g. from a `SpaceLabels`. The labels that were set before are
+    /// removed.
+    pub fn set_space_labels<I, S>(&mut self, labels: I)
+    where
+        I: IntoIterator<Item = (CGSSpaceID, S)>,
+        S: Into<String>,
+    {
+        self.space_labels = labels
+            .into_iter()
+            .map(|(space, label)| (space, label.into()))
+            .collect();
+    }
+
+    /// Move a window to a space. The window is placed on the display the space belongs to.
+    pub fn assign_window_to_space(&mut self, id: u32, space: CGSSpaceID) {
+        let display = self.spaces.get(&space).map(|space| space.display);
+        if let Some(window) = self.windows.get_mut(&id) {
+            window.space = Some(space);
+            window.display = display.or(window.display);
+        }
+    }
+
+    /// Remove everything from the model.
+    pub fn purge(&mut self) {
+        *self = WorkspaceModel::default();
+    }
+
+    /// Remove all windows for which the predicate returns `false`, e.g. windows that are no
+    /// longer valid.
+    pub fn retain_windows<F: FnMut(&WindowInfo) -> bool>(&mut self, mut f: F) {
+        let removed: Vec<u32> = self
+            .windows
+            .values()
+            .filter(|window| !f(window))
+            .map(|window| window.id)
+            .collect();
+        for id in removed {
+            self.remove_window(id);
+        }
+    }
+
+    /// Get an application by pid.
+    pub fn application(&self, pid: PID) -> Option<&ApplicationInfo> {
+        self.applications.get(&pid)
+    }
+
+    /// Get all applications, ordered by pid.
+    pub fn applications(&self) -> impl Iterator<Item = &ApplicationInfo> {
+        self.applications.values()
+    }
+
+    /// Get a window by id.
+    pub fn window(&self, id: u32) -> Option<&WindowInfo> {
+        self.windows.get(&id)
+    }
+
+    /// Get all windows, ordered by id.
+    pub fn windows(&self) -> impl Iterator<Item = &WindowInfo> {
+        self.windows.values()
+    }
+
+    /// Get the windows of an application.
+    pub fn windows_of_application(&self, pid: PID) -> Vec<&WindowInfo> {
+        self.windows_by_application
+            .get(&pid)
+            .map(|ids| ids.iter().filter_map(|id| self.windows.get(id)).collect())
+            .unwrap_or_default()
+    }
+
+    /// Get the windows on a display.
+    pub fn windows_on_display(&self, display: DisplayID) -> Vec<&WindowInfo> {
+        self.windows
+            .values()
+            .filter(|window| window.display == Some(display))
+            .collect()
+    }
+
+    /// Get the windows on a space.
+    pub fn windows_on_space(&self, space: CGSSpaceID) -> Vec<&WindowInfo> {
+        self.windows
+            .values()
+            .filter(|window| window.space == Some(space))
+            .collect()
+    }
+
+    /// Get the focused window.
+    pub fn focused_window(&self) -> Option<&WindowInfo> {
+        self.focused_window.and_then(|id| self.windows.get(&id))
+    }
+
+    /// Get the focused application.
+    pub fn focused_application(&self) -> Option<&ApplicationInfo> {
+        self.focused_application
+            .and_then(|pid| self.applications.get(&pid))
+    }
+
+    /// Get a display by id.
+    pub fn display(&self, id: DisplayID) -> Option<&DisplayInfo> {
+        self.displays.get(&id)
+    }
+
+    /// Get all displays, ordered by id.
+    pub fn displays(&self) -> impl Iterator<Item = &DisplayInfo> {
+        self.displays.values()
+    }
+
+    /// Get a space by id.
+    pub fn space(&self, id: CGSSpaceID) -> Option<&SpaceInfo> {
+        self.spaces.get(&id)
+    }
+
+    /// Get all known spaces, ordered by id.
+    pub fn spaces(&self) -> impl Iterator<Item = &SpaceInfo> {
+        self.spaces.values()
+    }
+
+    /// Get the label of a space.
+    pub fn space_label(&self, space: CGSSpaceID) -> Option<&str> {
+        self.space_labels.get(&space).map(String::as_str)
+    }
+
+    /// Get the spaces on a display.
+    pub fn spaces_on_display(&self, display: DisplayID) -> Vec<&SpaceInfo> {
+        self.spaces
+            .values()
+            .filter(|space| space.display == display)
+            .collect()
+    }
+
+    fn unindex_window(&mut self, owner: PID, id: u32) {
+        if let Some(ids) = self.windows_by_application.get_mut(&owner) {
+            ids.remove(&id);
+        }
+    }
+
+    fn set_application_hidden(&mut self, application: &Application, hidden: bool) {
+        if let Ok(pid) = application.pid() {
+            if let Some(info) = self.applications.get_mut(&pid) {
+                info.hidden = hidden;
+            }
+        }
+    }
+
+    fn set_window_minimized(&mut self, window: &Window, minimized: bool) {
+        if let Ok(id) = window.id() {
+            if let Some(info) = self.windows.get_mut(&id) {
+                info.minimized = minimized;
+            }
+            if minimized && self.focused_window == Some(id) {
+                self.focused_window = None;
+            }
+        }
+    }
+
+    /// Place a window on the display containing its center, and on that display's active space.
+    fn place(&self, info: &mut WindowInfo) {
+        let center = geometry::center(&info.frame());
+        let display = self.displays.values().find(|display| {
+            display
+                .frame
+                .map(|frame| geometry::contains(&frame, &center))
+                .unwrap_or(false)
+        });
+        let display = match display {
+            Some(display) => Some(display),
+            // With a single display there's only one place the window can be.
+            None if self.displays.len() == 1 => self.displays.values().next(),
+            None => None,
+        };
+
+        info.display = display.map(|display| display.id);
+        info.space = display.and_then(|display| display.active_space);
+    }
+
+    /// Query the frame and active space of a display from the window server.
+    #[cfg(feature = "accessibility")]
+    fn refresh_display(&mut self, id: DisplayID) {
+        let system = Axlib::new();
+        if let (Ok(frame), Ok(space)) = (system.display_bounds(id), system.active_space(id)) {
+            self.set_display_frame(id, frame);
+            self.set_active_space(id, space);
+        }
+    }
+
+    /// Without the `accessibility` feature nothing can be queried.
+    #[cfg(not(feature = "accessibility"))]
+    fn refresh_display(&mut self, _id: DisplayID) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use testing;
+    use testing::rect;
+
+    /// A model with two side by side displays, with spaces 1 and 2 active.
+    fn two_displays() -> WorkspaceModel {
+        let mut model = WorkspaceModel::new();
+        model.handle(&Event::DisplayAdded(10));
+        model.handle(&Event::DisplayAdded(20));
+        model.set_display_frame(10, rect(0.0, 0.0, 1000.0, 1000.0));
+        model.set_display_frame(20, rect(1000.0, 0.0, 1000.0, 1000.0));
+        model.set_active_space(10, 1);
+        model.set_active_space(20, 2);
+        model
+    }
+
+    fn ids(windows: Vec<&WindowInfo>) -> Vec<u32> {
+        windows.iter().map(|window| window.id).collect()
+    }
+
+    #[test]
+    fn applications_and_windows() {
+        let mut model = two_displays();
+        let app = testing::application(100, "Terminal");
+        model.handle(&Event::ApplicationLaunched(testing::same_application(&app)));
+        let left = testing::window(&app, 1, "left", (10.0, 10.0, 100.0, 100.0));
+        let right = testing::window(&app, 2, "right", (1100.0, 10.0, 100.0, 100.0));
+        model.handle(&Event::WindowCreated(left));
+        model.handle(&Event::WindowCreated(right));
+
+        assert_eq!(model.application(100).unwrap().name, "Terminal");
+        assert_eq!(ids(model.windows_of_application(100)), vec![1, 2]);
+        assert_eq!(ids(model.windows_on_display(10)), vec![1]);
+        assert_eq!(ids(model.windows_on_display(20)), vec![2]);
+        assert_eq!(ids(model.windows_on_space(2)), vec![2]);
+    }
+
+    #[test]
+    fn terminated_application_removes_windows() {
+        let mut model = two_displays();
+        let app = testing::application(100, "Terminal");
+        let other = testing::application(200, "Safari");
+        model.handle(&Event::ApplicationLaunched(testing::same_application(&app)));
+        model.handle(&Event::ApplicationLaunched(testing::same_application(
+            &other,
+        )));
+        let window = testing::window(&app, 1, "a", (10.0, 10.0, 100.0, 100.0));
+        model.handle(&Event::WindowCreated(testing::same_window(&window)));
+        model.handle(&Event::WindowFocused(window));
+        model.handle(&Event::WindowCreated(testing::window(
+            &other,
+            2,
+            "b",
+            (10.0, 10.0, 100.0, 100.0),
+        )));
+
+        model.handle(&Event::ApplicationTerminated(app));
+        assert!(model.application(100).is_none());
+        assert!(model.window(1).is_none());
+        assert!(model.focused_window().is_none());
+        assert_eq!(ids(model.windows().collect()), vec![2]);
+    }
+
+    #[test]
+    fn focus_and_destroy() {
+        let mut model = two_displays();
+        let app = testing::application(100, "Terminal");
+        let window = testing::window(&app, 1, "a", (10.0, 10.0, 100.0, 100.0));
+        // A window that is focused before its creation was seen is added.
+        model.handle(&Event::WindowFocused(testing::same_window(&window)));
+        assert_eq!(model.focused_window().unwrap().id, 1);
+
+        model.handle(&Event::WindowMinimized(testing::same_window(&window)));
+        assert!(model.window(1).unwrap().minimized);
+        assert!(model.focused_window().is_none());
+
+        model.handle(&Event::WindowDestroyed(window));
+        assert!(model.window(1).is_none());
+        assert!(model.windows_of_application(100).is_empty());
+    }
+
+    #[test]
+    fn moving_to_another_display() {
+        let mut model = two_displays();
+        let app = testing::application(100, "Terminal");
+        let window = testing::window(&app, 1, "a", (10.0, 10.0, 100.0, 100.0));
+        model.handle(&Event::WindowCreated(testing::same_window(&window)));
+        assert_eq!(model.window(1).unwrap().space, Some(1));
+
+        testing::set_frame(&window, (1200.0, 10.0, 100.0, 100.0));
+        model.handle(&Event::WindowMoved(testing::same_window(&window)));
+        let info = model.window(1).unwrap();
+        assert_eq!((info.display, info.space), (Some(20), Some(2)));
+        assert_eq!(info.position.x, 1200.0);
+    }
+
+    #[test]
+    fn moving_within_a_display_keeps_the_space() {
+        let mut model = two_displays();
+        let app = testing::application(100, "Terminal");
+        let window = testing::window(&app, 1, "a", (10.0, 10.0, 100.0, 100.0));
+        model.handle(&Event::WindowCreated(testing::same_window(&window)));
+        model.set_active_space(10, 3);
+
+        testing::set_frame(&window, (20.0, 20.0, 300.0, 300.0));
+        model.handle(&Event::WindowResized(window));
+        assert_eq!(model.window(1).unwrap().space, Some(1));
+        assert_eq!(model.window(1).unwrap().size.width, 300.0);
+    }
+
+    #[test]
+    fn title_changed() {
+        let mut model = WorkspaceModel::new();
+        let app = testing::application(100, "Terminal");
+        let window = testing::window(&app, 1, "old", (10.0, 10.0, 100.0, 100.0));
+        model.handle(&Event::WindowCreated(testing::same_window(&window)));
+        testing::set_name(&window, "new");
+        model.handle(&Event::WindowTitleChanged(window));
+        assert_eq!(model.window(1).unwrap().name, "new");
+    }
+
+    #[test]
+    fn removed_display_forgets_placement() {
+        let mut model = two_displays();
+        let app = testing::application(100, "Terminal");
+        model.handle(&Event::WindowCreated(testing::window(
+            &app,
+            1,
+            "a",
+            (1100.0, 10.0, 100.0, 100.0),
+        )));
+
+        model.handle(&Event::DisplayRemoved(20));
+        assert!(model.display(20).is_none());
+        assert!(model.space(2).is_none());
+        let info = model.window(1).unwrap();
+        assert_eq!((info.display, info.space), (None, None));
+    }
+
+    #[test]
+    fn purge() {
+        let mut model = two_displays();
+        let app = testing::application(100, "Terminal");
+        model.handle(&Event::ApplicationLaunched(testing::same_application(&app)));
+        model.handle(&Event::WindowCreated(testing::window(
+            &app,
+            1,
+            "a",
+            (10.0, 10.0, 100.0, 100.0),
+        )));
+        model.handle(&Event::WindowCreated(testing::window(
+            &app,
+            2,
+            "b",
+            (10.0, 10.0, 100.0, 100.0),
+        )));
+
+        model.retain_windows(|window| window.id != 1);
+        assert_eq!(ids(model.windows_of_application(100)), vec![2]);
+
+        model.purge();
+        assert_eq!(model.windows().count(), 0);
+        assert_eq!(model.displays().count(), 0);
+    }
+}
@@ -0,0 +1,96 @@
+//! Checks the layout of the raw types against the C header in `chunkwm-lib/include`, so that ABI
+//! drift is caught before a plugin crashes chunkwm.
+//!
+//! The C side is compiled with the system's C compiler, for the API version selected with the
+//! `api-7` and `api-8` features.
+
+extern crate cc;
+extern crate chunkwm;
+
+mod common;
+
+use chunkwm::display::SpaceType;
+use chunkwm::prelude::*;
+use std::collections::BTreeSet;
+use std::mem;
+use std::process::Command;
+
+/// The layout of the Rust types, in the format `chunkwm-lib/abi/layout.c` prints.
+macro_rules! rust_layout {
+    ($($ty:ident { $($field:ident),* })*) => {{
+        let mut lines = Vec::new();
+        $(
+            lines.push(format!(
+                "type {} {} {}",
+                stringify!($ty),
+                mem::size_of::<$ty>(),
+                mem::align_of::<$ty>()
+            ));
+            $(
+                lines.push(format!(
+                    "field {} {} {}",
+                    stringify!($ty),
+                    stringify!($field),
+                    std::mem::offset_of!($ty, $field)
+                ));
+            )*
+        )*
+        lines
+    }};
+}
+
+fn c_layout() -> Vec<String> {
+    let lib = common::chunkwm_lib();
+    let exe = common::out_dir().join("chunkwm-layout");
+    common::run(
+        common::c_compiler()
+            .arg("-I")
+            .arg(lib.join("include"))
+            .arg(format!("-DCHUNKWM_PLUGIN_API_VERSION={}", API_VERSION))
+            .arg(lib.join("abi").join("layout.c"))
+            .arg("-o")
+            .arg(&exe),
+    );
+
+    common::run(&mut Command::new(&exe))
+        .lines()
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn layout_matches_c_header() {
+    let c = c_layout();
+    assert_eq!(c[0], format!("api_version {}", API_VERSION));
+
+    let rust = rust_layout! {
+        Subscription {}
+        LogLevel {}
+        SpaceType {}
+        API {}
+        ChunkWMPlugin { init, deinit, run, subscriptions, subscription_count }
+        ChunkWMPluginDetails {
+            magic, api_version, file_name, plugin_name, plugin_version, initialize
+        }
+        RawPayload { sock_fd, command, message }
+        RawObserver { observer, enabled, valid }
+        ProcessSerialNumber { high_long, low_long }
+        RawApplication { element, observer, name, pid, process_serial_number }
+        RawWindow {
+            element, main_role, sub_role, owner, id, name, flags, level, position, size
+        }
+        RawDisplay { display_ref, id, arrangement, x, y, width, height }
+        RawSpace { space_ref, id, space_type }
+    };
+
+    let c: BTreeSet<_> = c[1..].iter().collect();
+    let rust: BTreeSet<_> = rust.iter().collect();
+    let only_c: Vec<_> = c.difference(&rust).collect();
+    let only_rust: Vec<_> = rust.difference(&c).collect();
+    assert!(
+        only_c.is_empty() && only_rust.is_empty(),
+        "layout mismatch\nC:    {:?}\nRust: {:?}",
+        only_c,
+        only_rust
+    );
+}
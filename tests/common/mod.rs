@@ -0,0 +1,42 @@
+//! Helpers for the tests that compile C code with the system's C compiler.
+
+use cc;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The `chunkwm-lib` directory.
+pub fn chunkwm_lib() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("chunkwm-lib")
+}
+
+/// A directory for the files the tests generate.
+pub fn out_dir() -> PathBuf {
+    PathBuf::from(env!("OUT_DIR"))
+}
+
+/// A command that runs the C compiler for the target the tests are compiled for.
+pub fn c_compiler() -> Command {
+    cc::Build::new()
+        .target(env!("CHUNKWM_TARGET"))
+        .host(env!("CHUNKWM_HOST"))
+        .opt_level(0)
+        .debug(false)
+        .cargo_metadata(false)
+        .get_compiler()
+        .to_command()
+}
+
+/// Run a command, and return its output if it succeeded.
+pub fn run(command: &mut Command) -> String {
+    let output = command
+        .output()
+        .unwrap_or_else(|e| panic!("could not run {:?}: {}", command, e));
+    assert!(
+        output.status.success(),
+        "{:?} failed:\n{}{}",
+        command,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
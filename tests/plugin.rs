@@ -0,0 +1,116 @@
+//! Loads the `plugin` example the way chunkwm does, to check that a plain `cdylib` built with
+//! `chunkwm_plugin!` is a loadable plugin, and that it exports exactly the expected symbols.
+
+extern crate cc;
+extern crate chunkwm;
+
+mod common;
+
+use chunkwm::plugin::{self, EXPORTED_SYMBOLS};
+use chunkwm::raw::API_VERSION;
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// The path to the `plugin` example, building it if `cargo test` didn't.
+fn example_plugin() -> PathBuf {
+    let file_name = format!(
+        "{}plugin{}",
+        env::consts::DLL_PREFIX,
+        env::consts::DLL_SUFFIX
+    );
+
+    // `cargo test` builds the examples next to the `deps` directory the test runs from.
+    let exe = env::current_exe().unwrap();
+    let built = exe
+        .parent()
+        .unwrap()
+        .with_file_name("examples")
+        .join(&file_name);
+    if built.exists() {
+        return built;
+    }
+
+    let target_dir = common::out_dir().join("example-target");
+    let mut cargo = Command::new(env!("CARGO"));
+    cargo
+        .args(["build", "--example", "plugin", "--manifest-path"])
+        .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", &target_dir);
+    if cfg!(feature = "api-7") {
+        cargo.args(["--features", "api-7"]);
+    }
+    common::run(&mut cargo);
+    target_dir.join("debug").join("examples").join(file_name)
+}
+
+#[test]
+fn exported_symbols() {
+    let library = example_plugin();
+    let symbols = if cfg!(target_os = "macos") {
+        common::run(Command::new("nm").arg("-gjU").arg(&library))
+    } else {
+        common::run(
+            Command::new("nm")
+                .args(["-D", "--defined-only", "--format=just-symbols"])
+                .arg(&library),
+        )
+    };
+
+    let mut symbols: Vec<_> = symbols
+        .lines()
+        .map(|symbol| symbol.trim_start_matches('_'))
+        .filter(|symbol| !symbol.is_empty())
+        .collect();
+    symbols.sort();
+    assert_eq!(symbols, EXPORTED_SYMBOLS);
+}
+
+#[test]
+fn load_plugin() {
+    let library = example_plugin();
+    let out_dir = common::out_dir();
+    plugin::write_exports_header(out_dir.join("chunkwm_plugin.h")).unwrap();
+
+    let loader = out_dir.join("chunkwm-loader");
+    let mut compiler = common::c_compiler();
+    compiler
+        .arg("-I")
+        .arg(&out_dir)
+        .arg(common::chunkwm_lib().join("abi").join("loader.c"))
+        .arg("-o")
+        .arg(&loader);
+    if cfg!(target_os = "linux") {
+        compiler.arg("-ldl");
+    }
+    common::run(&mut compiler);
+
+    let output = common::run(Command::new(&loader).arg(&library));
+    let mut expected = vec![
+        "magic chwmpl".to_owned(),
+        format!("api_version {}", API_VERSION),
+        "file_name examples/plugin.rs".to_owned(),
+        "plugin_name example".to_owned(),
+        "plugin_version 0.1.0".to_owned(),
+        "initialize_is_get_plugin 1".to_owned(),
+        "same_plugin 1".to_owned(),
+        // `WindowFocused` and `WindowCreated`, and the cleanup events `WindowDestroyed` and
+        // `ApplicationTerminated`.
+        "subscriptions 14 12 13 1".to_owned(),
+        "init 1".to_owned(),
+    ];
+    if API_VERSION >= 8 {
+        expected.push("log window: ~".to_owned());
+    }
+    expected.extend(vec![
+        "run 1".to_owned(),
+        "version_command 1".to_owned(),
+        format!(
+            "version example 0.1.0 (chunkwm-rs {}, API version {})",
+            plugin::CRATE_VERSION,
+            API_VERSION
+        ),
+        "deinit".to_owned(),
+    ]);
+    assert_eq!(output.lines().collect::<Vec<_>>(), expected);
+}